@@ -3,7 +3,7 @@ use std::{env, error::Error, fs::File};
 
 use mirror_verse::{
     mirror::{self, Random, JsonSer},
-    rand, serde_json, Simulation,
+    rand, serde_json, Propagation, Simulation,
 };
 
 trait JsonTypeDyn {
@@ -24,9 +24,18 @@ struct Dynamic<T, const D: usize>(T);
 
 impl mirror::Random for Dynamic<Box<dyn JsonSerDyn>, 2> {
     fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
-        Self(match rng.gen_range(0usize..2) {
+        Self(match rng.gen_range(0usize..11) {
             0 => Box::new(mirror::plane::PlaneMirror::<2>::random(rng)) as Box<dyn JsonSerDyn>,
             1 => Box::new(mirror::sphere::EuclideanSphereMirror::<2>::random(rng)),
+            2 => Box::new(mirror::cone::ConicalMirror::<2>::random(rng)),
+            3 => Box::new(mirror::cylinder::CylindricalMirror::<2>::random(rng)),
+            4 => Box::new(mirror::disk::DiskMirror::<2>::random(rng)),
+            5 => Box::new(mirror::simplex::SimplexMirror::<2>::random(rng)),
+            6 => Box::new(mirror::spherical_cap::SphericalCapMirror::<2>::random(rng)),
+            7 => Box::new(mirror::sphere::LpSphereMirror::<2>::random(rng)),
+            8 => Box::new(mirror::grating::GratingMirror::<2>::random(rng)),
+            9 => Box::new(mirror::optics::ThinLens::<2>::random(rng)),
+            10 => Box::new(mirror::optics::BeamSplitter::<2>::random(rng)),
             _ => unreachable!(),
         })
     }
@@ -34,10 +43,20 @@ impl mirror::Random for Dynamic<Box<dyn JsonSerDyn>, 2> {
 
 impl mirror::Random for Dynamic<Box<dyn JsonSerDyn>, 3> {
     fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
-        Self(match rng.gen_range(0usize..3) {
+        Self(match rng.gen_range(0usize..13) {
             0 => Box::new(mirror::plane::PlaneMirror::<3>::random(rng)) as Box<dyn JsonSerDyn>,
             1 => Box::new(mirror::sphere::EuclideanSphereMirror::<3>::random(rng)),
-            2 => Box::new(mirror::cylinder::CylindricalMirror::random(rng)),
+            2 => Box::new(mirror::cylinder::CylindricalMirror::<3>::random(rng)),
+            3 => Box::new(mirror::torus::TorusMirror::random(rng)),
+            4 => Box::new(mirror::cone::ConicalMirror::<3>::random(rng)),
+            5 => Box::new(mirror::disk::DiskMirror::<3>::random(rng)),
+            6 => Box::new(mirror::simplex::SimplexMirror::<3>::random(rng)),
+            7 => Box::new(mirror::spherical_cap::SphericalCapMirror::<3>::random(rng)),
+            8 => Box::new(mirror::sphere::LpSphereMirror::<3>::random(rng)),
+            9 => Box::new(mirror::heightfield::HeightfieldMirror::random(rng)),
+            10 => Box::new(mirror::grating::GratingMirror::<3>::random(rng)),
+            11 => Box::new(mirror::optics::ThinLens::<3>::random(rng)),
+            12 => Box::new(mirror::optics::BeamSplitter::<3>::random(rng)),
             _ => unreachable!(),
         })
     }
@@ -83,6 +102,10 @@ fn generate_random_simulation(
             rays: iter::repeat_with(|| mirror::Ray::<2>::random(&mut rng))
                 .take(num_rays)
                 .collect(),
+            seed: 0,
+            propagation: Propagation::Straight,
+            periodic: None,
+            world: None,
         }
         .to_json())
     } else if dim == 3 {
@@ -94,6 +117,10 @@ fn generate_random_simulation(
             rays: iter::repeat_with(|| mirror::Ray::<3>::random(&mut rng))
                 .take(num_rays)
                 .collect(),
+            seed: 0,
+            propagation: Propagation::Straight,
+            periodic: None,
+            world: None,
         }
         .to_json())
     } else {
@@ -0,0 +1,433 @@
+use super::*;
+
+use mirror::{csg::Solid, sphere::EuclideanSphereMirror, Mirror};
+use std::io;
+
+/// The speed of sound in dry air, at 20°C, in metres per second
+pub const SPEED_OF_SOUND: Float = 343.0;
+
+/// The sample rate of impulse responses, when not specified, in Hz
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+/// The largest sample rate of impulse responses, that of high resolution audio, in Hz
+pub const MAX_SAMPLE_RATE: u32 = 384000;
+
+/// The length of impulse responses, in seconds, arrivals after it are ignored.
+///
+/// It is longer than the reverberation time of the largest rooms, and bounds the
+/// memory taken by responses, to `MAX_DURATION * sample_rate` bins.
+pub const MAX_DURATION: Float = 30.0;
+
+/// The setup of an acoustic simulation, computing the impulse response of a room,
+/// (whose walls are the mirrors of a [`Simulation`]) with specular ray tracing.
+///
+/// Rays are emitted in all directions from a point source, and their arrivals in a receiver
+/// sphere, transparent to them, are recorded, at the time they took to get there, travelling
+/// at the speed of sound. Rays travel in straight lines, (see [`Propagation::Straight`])
+/// the unit of length being the metre, and the absorption of walls is modelled with coated
+/// mirrors, (see [`mirror::material::Coated`]) which only reflect part of the energy of rays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Acoustics<const D: usize> {
+    source: SVector<Float, D>,
+    num_rays: usize,
+    receiver: EuclideanSphereMirror<D>,
+    speed_of_sound: Float,
+    sample_rate: u32,
+}
+
+impl<const D: usize> Acoustics<D> {
+    /// Returns `None` if there are no rays, if the speed of sound, or the sample rate isn't
+    /// positive, or if the sample rate is larger than [`MAX_SAMPLE_RATE`].
+    pub fn new(
+        source: SVector<Float, D>,
+        num_rays: usize,
+        receiver: EuclideanSphereMirror<D>,
+        speed_of_sound: Float,
+        sample_rate: u32,
+    ) -> Option<Self> {
+        (num_rays > 0
+            && speed_of_sound.is_normal()
+            && speed_of_sound > 0.0
+            && (1..=MAX_SAMPLE_RATE).contains(&sample_rate))
+        .then_some(Self {
+            source,
+            num_rays,
+            receiver,
+            speed_of_sound,
+            sample_rate,
+        })
+    }
+
+    pub fn source(&self) -> &SVector<Float, D> {
+        &self.source
+    }
+
+    pub fn receiver(&self) -> &EuclideanSphereMirror<D> {
+        &self.receiver
+    }
+
+    /// Returns `num_rays` rays, leaving the source in directions drawn uniformly from `rng`,
+    /// whose powers add up to `1.0`, to be traced by a simulation, see [`Self::impulse_response`]
+    pub fn source_rays(&self, rng: &mut (impl rand::Rng + ?Sized)) -> Vec<mirror::Ray<D>> {
+        Vec::from_iter((0..self.num_rays).map(|_| {
+            // `util::rand_unit_vect` favors the corners of the cube, sample the ball instead
+            let direction = iter::repeat_with(|| util::rand_vect::<D>(rng, 1.0))
+                .find_map(|v| Unit::try_new(v, Float::EPSILON * 8.0).filter(|_| v.norm() <= 1.0))
+                // SAFETY: the iterator is infinite
+                .unwrap();
+
+            mirror::Ray {
+                power: 1.0 / self.num_rays as Float,
+                ..mirror::Ray::new(self.source, direction)
+            }
+        }))
+    }
+
+    /// Returns the distance after which `ray` enters the receiver, if it does before travelling `length`
+    fn arrival(&self, ray: &mirror::Ray<D>, length: Float) -> Option<Float> {
+        let mut intersections = vec![];
+        self.receiver
+            .append_intersecting_points(ray, util::List::new(&mut intersections));
+
+        intersections
+            .iter()
+            .filter_map(|tangent| tangent.try_ray_intersection(ray))
+            .min_by(Float::total_cmp)
+            .filter(|t| (0.0..length).contains(t))
+    }
+
+    /// Returns the times, in seconds, at which the rays of `paths`, (and the rays
+    /// split from them) entered the receiver, along with their energy at that time.
+    ///
+    /// If the source is inside the receiver, every ray arrives at time `0.0`.
+    pub fn arrivals(&self, paths: &[RayPath<D>]) -> Vec<(Float, Float)> {
+        let mut arrivals = vec![];
+
+        for path in paths.iter().flat_map(RayPath::all_branches) {
+            if let Some((_, ray)) = path.bounces().first() {
+                if ray.time == 0.0 && self.receiver.contains(&ray.origin) {
+                    arrivals.push((0.0, ray.power));
+                }
+            }
+
+            for (ray, length) in path.segments() {
+                if let Some(t) = self.arrival(&ray, length) {
+                    arrivals.push(((ray.time + t) / self.speed_of_sound, ray.power));
+                }
+            }
+        }
+
+        arrivals
+    }
+
+    /// Returns the energy impulse response at the receiver, from the `paths` traced
+    /// from the rays returned by [`Self::source_rays`], see [`Self::arrivals`].
+    ///
+    /// Only the paths traced until they're absorbed, or escape, contribute to
+    /// the whole response, the reflection limit of the simulation truncates it,
+    /// as does [`MAX_DURATION`].
+    pub fn impulse_response(&self, paths: &[RayPath<D>]) -> ImpulseResponse {
+        let mut energies = vec![];
+
+        for (time, energy) in self.arrivals(paths) {
+            if time >= MAX_DURATION {
+                continue;
+            }
+
+            let bin = (time * self.sample_rate as Float) as usize;
+
+            if bin >= energies.len() {
+                energies.resize(bin + 1, 0.0);
+            }
+
+            energies[bin] += energy;
+        }
+
+        ImpulseResponse {
+            sample_rate: self.sample_rate,
+            energies,
+        }
+    }
+}
+
+impl<const D: usize> JsonDes for Acoustics<D> {
+    /// Deserialize the setup of an acoustic simulation from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "source": [1., 2., 3., ...], // (an array of D floats)
+    ///     "num_rays": 10000, // (a positive integer)
+    ///     "receiver": { // (see `EuclideanSphereMirror::from_json`)
+    ///         "center": [1., 2., 3., ...],
+    ///         "radius": 0.5,
+    ///     },
+    ///     "speed_of_sound": 343., // (optional, in metres per second, defaults to `SPEED_OF_SOUND`)
+    ///     "sample_rate": 44100, // (optional, in Hz, at most `MAX_SAMPLE_RATE`, defaults to `DEFAULT_SAMPLE_RATE`)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let source = json
+            .get("source")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse source")?;
+
+        let num_rays = json
+            .get("num_rays")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or("Failed to parse num_rays")? as usize;
+
+        let receiver =
+            EuclideanSphereMirror::from_json(json.get("receiver").ok_or("Missing receiver")?)?;
+
+        let speed_of_sound = json
+            .get("speed_of_sound")
+            .map(|speed| speed.as_f64().ok_or("Failed to parse speed_of_sound"))
+            .transpose()?
+            .unwrap_or(SPEED_OF_SOUND);
+
+        let sample_rate = json
+            .get("sample_rate")
+            .map(|rate| {
+                rate.as_u64()
+                    .and_then(|rate| u32::try_from(rate).ok())
+                    .ok_or("Failed to parse sample_rate")
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_SAMPLE_RATE);
+
+        Self::new(source, num_rays, receiver, speed_of_sound, sample_rate).ok_or(
+            format!(
+                "num_rays, speed_of_sound, and sample_rate must be positive, \
+                 and sample_rate at most {MAX_SAMPLE_RATE}"
+            )
+            .into(),
+        )
+    }
+}
+
+impl<const D: usize> JsonSer for Acoustics<D> {
+    /// Serialize the setup of an acoustic simulation into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "source": self.source.as_slice(),
+            "num_rays": self.num_rays,
+            "receiver": self.receiver.to_json(),
+            "speed_of_sound": self.speed_of_sound,
+            "sample_rate": self.sample_rate,
+        })
+    }
+}
+
+/// An energy impulse response: the energy arriving at a receiver, in consecutive
+/// time intervals, (bins) of length `1 / sample_rate`, see [`Acoustics::impulse_response`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImpulseResponse {
+    sample_rate: u32,
+    energies: Vec<Float>,
+}
+
+impl ImpulseResponse {
+    /// The number of bins per second, in Hz
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// The energy arriving in each bin, up to the last arrival
+    pub fn energies(&self) -> &[Float] {
+        self.energies.as_slice()
+    }
+
+    /// Writes this response as CSV, with a header, and a `time,energy` line for each bin, at it's start
+    pub fn write_csv(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writeln!(writer, "time,energy")?;
+
+        for (i, energy) in self.energies.iter().enumerate() {
+            writeln!(
+                writer,
+                "{},{energy}",
+                i as Float / self.sample_rate as Float
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes this response as a mono, 16-bit PCM, WAV file, at `self.sample_rate()`.
+    ///
+    /// Since sound pressure is proportional to the square root of energy, the samples
+    /// are the square roots of the energies of the bins, normalized so the largest
+    /// is `i16::MAX`. Listening to it gives an idea of the reverberation of the room.
+    pub fn write_wav(&self, mut writer: impl io::Write) -> io::Result<()> {
+        let max = self.energies.iter().copied().fold(0.0, Float::max);
+        let samples = Vec::from_iter(self.energies.iter().map(|energy| {
+            let amplitude = if max > 0.0 {
+                (energy / max).sqrt()
+            } else {
+                0.0
+            };
+            (amplitude * i16::MAX as Float).round() as i16
+        }));
+
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidInput, message);
+
+        let data_size = u32::try_from(samples.len() * 2)
+            .ok()
+            .filter(|size| *size <= u32::MAX - 36)
+            .ok_or_else(|| invalid("response too long"))?;
+        let byte_rate = self
+            .sample_rate
+            .checked_mul(2)
+            .ok_or_else(|| invalid("sample rate too large"))?;
+
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&(36 + data_size).to_le_bytes())?;
+        writer.write_all(b"WAVE")?;
+
+        // format chunk: PCM, 1 channel, 2 bytes per sample
+        writer.write_all(b"fmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?;
+        writer.write_all(&self.sample_rate.to_le_bytes())?;
+        writer.write_all(&byte_rate.to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&16u16.to_le_bytes())?;
+
+        writer.write_all(b"data")?;
+        writer.write_all(&data_size.to_le_bytes())?;
+        for sample in samples {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    /// A source at the origin, and a receiver of radius `0.5`, centered at `(4, 0)`
+    fn acoustics() -> Acoustics<2> {
+        Acoustics::from_json(&json!({
+            "source": [0., 0.],
+            "num_rays": 2,
+            "receiver": { "center": [4., 0.], "radius": 0.5 },
+            "sample_rate": 100,
+        }))
+        .expect("json error")
+    }
+
+    #[test]
+    fn test_source_rays() {
+        let acoustics = acoustics();
+
+        let rays = acoustics.source_rays(&mut rand::rngs::StdRng::seed_from_u64(0));
+        assert_eq!(rays.len(), 2);
+        assert!(rays
+            .iter()
+            .all(|ray| ray.origin == SVector::<Float, 2>::zeros() && ray.power == 0.5));
+
+        assert_eq!(
+            Acoustics::from_json(&acoustics.to_json()).ok(),
+            Some(acoustics)
+        );
+
+        let mut json = acoustics.to_json();
+        json["speed_of_sound"] = json!(0.);
+        assert!(Acoustics::<2>::from_json(&json).is_err());
+
+        let mut json = acoustics.to_json();
+        json["sample_rate"] = json!(MAX_SAMPLE_RATE + 1);
+        assert!(Acoustics::<2>::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_impulse_response() {
+        // the first ray reaches the receiver directly, then again, after bouncing off
+        // a wall absorbing half of it's energy, the second one goes the other way
+        let acoustics = acoustics();
+
+        let wall = mirror::material::Coated::<mirror::plane::PlaneMirror<2>>::from_json(&json!({
+            "absorption": 0.5,
+            "mirror": { "center": [6., 0.], "basis": [[0., 3.]] },
+        }))
+        .expect("json error");
+
+        let rays = Vec::from_iter([[1., 0.], [-1., 0.]].map(|direction| mirror::Ray {
+            power: 0.5,
+            ..mirror::Ray::new(SVector::zeros(), Unit::new_normalize(direction.into()))
+        }));
+
+        let simulation = Simulation {
+            rays,
+            mirror: wall,
+            seed: 0,
+            propagation: Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let paths = simulation.get_ray_paths(100);
+
+        let arrivals = acoustics.arrivals(&paths);
+        assert_eq!(arrivals.len(), 2);
+        assert!((arrivals[0].0 - 3.5 / SPEED_OF_SOUND).abs() < 1e-12);
+        assert!((arrivals[1].0 - 7.5 / SPEED_OF_SOUND).abs() < 1e-12);
+        assert_eq!(arrivals[0].1, 0.5);
+        assert!((arrivals[1].1 - 0.25).abs() < 1e-12);
+
+        let response = acoustics.impulse_response(&paths);
+        let energies = response.energies();
+        assert_eq!(energies.len(), 3);
+        assert_eq!(energies[..2], [0., 0.5]);
+        assert!((energies[2] - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_output() {
+        let response = ImpulseResponse {
+            sample_rate: 4,
+            energies: vec![0., 1., 0.25],
+        };
+
+        let mut csv = vec![];
+        response.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "time,energy\n0,0\n0.25,1\n0.5,0.25\n"
+        );
+
+        let mut wav = vec![];
+        response.write_wav(&mut wav).unwrap();
+        assert_eq!(wav.len(), 44 + 3 * 2);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 4);
+
+        let samples = Vec::from_iter(
+            wav[44..]
+                .chunks(2)
+                .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]])),
+        );
+        assert_eq!(
+            samples,
+            [0, i16::MAX, (i16::MAX as Float / 2.0).round() as i16]
+        );
+
+        let response = ImpulseResponse {
+            sample_rate: u32::MAX,
+            ..response
+        };
+        let error = response.write_wav(io::sink()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
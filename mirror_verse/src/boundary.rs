@@ -0,0 +1,393 @@
+use core::ops::Deref;
+
+use super::*;
+
+use util::List;
+
+/// A box, with faces orthogonal to the axes, the shape of both [`PeriodicBox`], and [`WorldBox`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisAlignedBox<const D: usize> {
+    min: SVector<Float, D>,
+    max: SVector<Float, D>,
+}
+
+impl<const D: usize> AxisAlignedBox<D> {
+    /// Returns `None` if some coordinate of `min` isn't smaller than that of `max`.
+    pub fn new(min: SVector<Float, D>, max: SVector<Float, D>) -> Option<Self> {
+        min.iter()
+            .zip(&max)
+            .all(|(a, b)| a < b)
+            .then_some(Self { min, max })
+    }
+
+    /// The corner of the box with the smallest coordinates
+    pub fn min(&self) -> &SVector<Float, D> {
+        &self.min
+    }
+
+    /// The corner of the box with the largest coordinates
+    pub fn max(&self) -> &SVector<Float, D> {
+        &self.max
+    }
+
+    pub fn contains(&self, p: &SVector<Float, D>) -> bool {
+        (0..D).all(|i| (self.min[i]..=self.max[i]).contains(&p[i]))
+    }
+
+    /// Returns the distances, along each axis, after which `ray` reaches
+    /// the face of the box it is heading to, on that axis
+    fn face_distances(&self, ray: &mirror::Ray<D>) -> SVector<Float, D> {
+        SVector::from_fn(|i, _| {
+            let d = ray.direction[i];
+            if d > 0.0 {
+                (self.max[i] - ray.origin[i]) / d
+            } else if d < 0.0 {
+                (self.min[i] - ray.origin[i]) / d
+            } else {
+                Float::INFINITY
+            }
+        })
+    }
+
+    /// Returns the edges of the box, as pairs of consecutive points
+    fn edges(&self) -> Vec<SVector<Float, D>> {
+        let corner = |bits: usize| {
+            SVector::from_fn(|i, _| {
+                if bits >> i & 1 == 0 {
+                    self.min[i]
+                } else {
+                    self.max[i]
+                }
+            })
+        };
+
+        // two corners are linked by an edge if they only differ by one coordinate
+        Vec::from_iter(
+            (0..1usize << D)
+                .flat_map(|bits| (0..D).map(move |i| (bits, i)))
+                .filter(|(bits, i)| bits >> i & 1 == 0)
+                .flat_map(|(bits, i)| [corner(bits), corner(bits | 1 << i)]),
+        )
+    }
+}
+
+impl<const D: usize> JsonDes for AxisAlignedBox<D> {
+    /// Deserialize a new box from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "min": [-1., -1., -1.], // (the corner with the smallest coordinates)
+    ///     "max": [1., 1., 1.], // (the corner with the largest coordinates)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let get_corner = |name: &str| {
+            json.get(name)
+                .and_then(serde_json::Value::as_array)
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector)
+                .ok_or(format!("Missing or invalid {name}"))
+        };
+
+        Self::new(get_corner("min")?, get_corner("max")?)
+            .ok_or("the coordinates of min must be smaller than those of max".into())
+    }
+}
+
+impl<const D: usize> JsonSer for AxisAlignedBox<D> {
+    /// Serialize a box into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "min": self.min.as_slice(),
+            "max": self.max.as_slice(),
+        })
+    }
+}
+
+/// A box, with faces orthogonal to the axes, whose opposite faces are identified:
+/// rays leaving it through a face re-enter it from the opposite one, making the
+/// space simulated periodic, (a torus) see [`Simulation::periodic`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PeriodicBox<const D: usize>(AxisAlignedBox<D>);
+
+impl<const D: usize> PeriodicBox<D> {
+    /// Returns `None` if some coordinate of `min` isn't smaller than that of `max`.
+    pub fn new(min: SVector<Float, D>, max: SVector<Float, D>) -> Option<Self> {
+        AxisAlignedBox::new(min, max).map(Self)
+    }
+
+    /// Returns the distance `t` after which `ray`, starting inside this box, leaves it,
+    /// and the point, on the opposite face(s), where it re-enters it.
+    pub fn exit(&self, ray: &mirror::Ray<D>) -> (Float, SVector<Float, D>) {
+        let distances = self.face_distances(ray);
+
+        let t = distances.min().max(0.0);
+        let mut entry = ray.at(t);
+
+        // the ray may leave through several faces at once, at a corner
+        for i in (0..D).filter(|&i| distances[i] <= t + Float::EPSILON * 64.0) {
+            entry[i] = if ray.direction[i] > 0.0 {
+                self.min[i]
+            } else {
+                self.max[i]
+            };
+        }
+
+        (t, entry)
+    }
+}
+
+impl<const D: usize> Deref for PeriodicBox<D> {
+    type Target = AxisAlignedBox<D>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// A box, with faces orthogonal to the axes, outside of which rays are absorbed: rays
+/// reaching it stop there, see [`Simulation::world`], and [`crate::Termination::WorldBoundary`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldBox<const D: usize>(AxisAlignedBox<D>);
+
+impl<const D: usize> WorldBox<D> {
+    /// Returns `None` if some coordinate of `min` isn't smaller than that of `max`.
+    pub fn new(min: SVector<Float, D>, max: SVector<Float, D>) -> Option<Self> {
+        AxisAlignedBox::new(min, max).map(Self)
+    }
+
+    /// Returns the distance after which `ray`, starting inside this box, leaves it
+    pub fn exit_distance(&self, ray: &mirror::Ray<D>) -> Float {
+        self.face_distances(ray).min().max(0.0)
+    }
+}
+
+impl<const D: usize> Deref for WorldBox<D> {
+    type Target = AxisAlignedBox<D>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const D: usize> JsonDes for PeriodicBox<D> {
+    /// Deserialize a new periodic box from a JSON object.
+    ///
+    /// The JSON object must follow the format of [`AxisAlignedBox::from_json`]
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        AxisAlignedBox::from_json(json).map(Self)
+    }
+}
+
+impl<const D: usize> JsonDes for WorldBox<D> {
+    /// Deserialize a new world box from a JSON object.
+    ///
+    /// The JSON object must follow the format of [`AxisAlignedBox::from_json`]
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        AxisAlignedBox::from_json(json).map(Self)
+    }
+}
+
+struct BoxRenderData<const D: usize> {
+    vertices: gl::VertexBuffer<render::Vertex<D>>,
+    color: Option<[f32; 4]>,
+}
+
+impl<const D: usize> render::RenderData for BoxRenderData<D> {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: gl::index::PrimitiveType::LinesList,
+        }
+    }
+
+    fn color(&self) -> Option<[f32; 4]> {
+        self.color
+    }
+}
+
+impl<const D: usize> BoxRenderData<D>
+where
+    render::Vertex<D>: gl::Vertex,
+{
+    fn new(display: &gl::Display, edges: Vec<SVector<Float, D>>, color: Option<[f32; 4]>) -> Self {
+        let vertices = Vec::from_iter(edges.into_iter().map(render::Vertex::from));
+
+        Self {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+            color,
+        }
+    }
+}
+
+impl<const D: usize> render::OpenGLRenderable for PeriodicBox<D>
+where
+    render::Vertex<D>: gl::Vertex,
+{
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        list.push(Box::new(BoxRenderData::new(display, self.edges(), None)))
+    }
+}
+
+impl<const D: usize> render::OpenGLRenderable for WorldBox<D>
+where
+    render::Vertex<D>: gl::Vertex,
+{
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        const WORLD_COLOR: [f32; 4] = [0.4, 0.4, 0.4, 1.0];
+
+        list.push(Box::new(BoxRenderData::new(
+            display,
+            self.edges(),
+            Some(WORLD_COLOR),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ray(origin: [Float; 2], direction: [Float; 2]) -> mirror::Ray<2> {
+        mirror::Ray::new(origin.into(), Unit::new_normalize(direction.into()))
+    }
+
+    fn unit_box() -> PeriodicBox<2> {
+        PeriodicBox::new([0., 0.].into(), [1., 1.].into()).unwrap()
+    }
+
+    #[test]
+    fn test_exit() {
+        let periodic = unit_box();
+
+        let (t, entry) = periodic.exit(&ray([0.5, 0.5], [1., 0.5]));
+        assert!((t - 0.5 * 1.25f64.sqrt()).abs() < 1e-12);
+        assert!((entry - SVector::from([0., 0.75])).norm() < 1e-12);
+
+        // through a corner
+        let (_, entry) = periodic.exit(&ray([0.5, 0.5], [-1., -1.]));
+        assert!((entry - SVector::from([1., 1.])).norm() < 1e-12);
+
+        assert_eq!(periodic.edges().len(), 8);
+        assert!(PeriodicBox::new([0., 0.].into(), [1., 0.].into()).is_none());
+
+        let json = periodic.to_json();
+        assert_eq!(PeriodicBox::<2>::from_json(&json).unwrap(), periodic);
+    }
+
+    #[test]
+    fn test_periodic_orbit() {
+        // with a rational slope, the ray eventually comes back to where it started
+        let simulation = Simulation {
+            rays: vec![ray([0.5, 0.1], [1., 2.])],
+            mirror: Vec::<mirror::plane::PlaneMirror<2>>::new(),
+            seed: 0,
+            propagation: Propagation::Straight,
+            periodic: Some(unit_box()),
+            world: None,
+        };
+
+        let path = simulation.get_ray_paths(100).remove(0);
+        assert!(path.divergence_direction().is_none());
+        assert!(!path.loop_points().is_empty());
+        assert!(!path.wraps().is_empty());
+
+        for &i in path.wraps() {
+            // the ray re-enters the box on the face opposite to the one it left it from
+            let (exit, entry) = (path.all_points_raw()[i - 1], path.all_points_raw()[i]);
+            assert!((exit - entry).norm() >= 1. - 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_world_box() {
+        let json = serde_json::json!({
+            "rays": [
+                { "origin": [0., 0.], "direction": [1., 0.1] },
+                { "origin": [0., 0.], "direction": [-1., 0.] },
+                { "origin": [0., 0.5], "direction": [0., -1.] },
+            ],
+            "mirror": [
+                { "center": [2., 0.], "basis": [[0., 1.]] },
+                { "center": [0., 1.], "basis": [[3., 0.]] },
+                { "center": [0., -1.], "basis": [[3., 0.]] },
+            ],
+            "world": { "min": [-5., -5.], "max": [5., 5.] },
+        });
+
+        let simulation = Simulation::<Vec<mirror::plane::PlaneMirror<2>>, 2>::from_json(&json)
+            .expect("json error");
+
+        let paths = simulation.get_ray_paths(100);
+
+        // the first ray bounces off the mirror at x = 2, then leaves
+        // between the two others, and stops at the boundary
+        assert_eq!(paths[0].termination(), Termination::WorldBoundary);
+        let last = paths[0].all_points_raw().last().unwrap();
+        assert!((last.x + 5.).abs() < 1e-12);
+        assert!(paths[0].divergence_direction().is_none());
+
+        assert_eq!(paths[1].termination(), Termination::WorldBoundary);
+        assert_eq!(paths[1].all_points_raw()[1], SVector::from([-5., 0.]));
+
+        // the last one bounces between the two horizontal mirrors forever
+        assert_eq!(paths[2].termination(), Termination::Loop);
+
+        let mut json = json;
+        json["periodic"] = json["world"].clone();
+        assert!(Simulation::<Vec<mirror::plane::PlaneMirror<2>>, 2>::from_json(&json).is_err());
+
+        // without a world box, rays escape
+        let simulation = Simulation {
+            world: None,
+            ..simulation
+        };
+        let paths = simulation.get_ray_paths(100);
+        assert_eq!(paths[0].termination(), Termination::Escaped);
+        assert!(paths[1].divergence_direction().is_some());
+    }
+
+    #[test]
+    fn test_sinai_billiard() {
+        let obstacle = mirror::sphere::EuclideanSphereMirror::new([0.5, 0.5].into(), 0.25).unwrap();
+
+        let simulation = Simulation {
+            rays: vec![ray([0.1, 0.05], [1., 0.377]), ray([0.9, 0.2], [-0.3, 1.])],
+            mirror: obstacle,
+            seed: 0,
+            propagation: Propagation::Straight,
+            periodic: Some(unit_box()),
+            world: None,
+        };
+
+        for path in simulation.get_ray_paths(50) {
+            assert!(path.divergence_direction().is_none());
+
+            let points = path.all_points_raw();
+            let margin = PeriodicBox::new([-1e-9; 2].into(), [1. + 1e-9; 2].into()).unwrap();
+            assert!(points.iter().all(|p| margin.contains(p)));
+
+            // apart from wraps, the path only joins points of the obstacle, and of the box
+            for i in 1..points.len() {
+                if !path.wraps().contains(&i) {
+                    assert!((points[i] - points[i - 1]).norm() < 2f64.sqrt());
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,11 @@
 extern crate alloc;
 
 // re-export deps for convenience
+pub mod acoustics;
+pub mod boundary;
 pub mod mirror;
 pub mod render;
+pub mod solar;
 pub use glium as gl;
 pub use nalgebra;
 pub use rand;
@@ -16,18 +19,60 @@ use std::{error::Error, time};
 
 use render::{
     camera::{Camera, CameraController, Projection},
-    DrawableSimulation,
+    DrawableSimulation, OpenGLRenderable,
 };
 
 use mirror::{JsonDes, JsonSer};
 
 pub type Float = f64;
 
+/// Branches split from a ray, carrying less than this fraction of it's initial power, are not traced
+pub const MIN_BRANCH_POWER: Float = 1e-3;
+
+/// The maximum number of steps a ray takes through gradient-index regions, between two hits
+pub const MAX_CURVED_STEPS: usize = 1 << 16;
+
+/// The maximum number of times a ray wraps around a periodic box, between two hits
+pub const MAX_WRAPS: usize = 1 << 12;
+
+/// Why a ray stopped being traced, see [`RayPath::termination`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Termination {
+    /// The ray went off to infinity, see [`RayPath::divergence_direction`], (or, in
+    /// [`Propagation::Hyperbolic`] simulations, reached the boundary of the disk)
+    Escaped,
+    /// The ray was absorbed by a surface, an aperture for example, (see [`mirror::aperture::Aperture`])
+    Absorbed,
+    /// The ray reached the boundary of the world box of the simulation, see [`Simulation::world`]
+    WorldBoundary,
+    /// The path of the ray loops forever, see [`RayPath::loop_points`]
+    Loop,
+    /// The ray reached a point of a gradient-index region where the refractive index
+    /// isn't positive, see [`mirror::grin::GradientIndex`], or hit a dielectric whose
+    /// index isn't positive at it's wavelength, see [`mirror::material::Dielectric`]
+    NonPositiveIndex,
+    /// The ray reached the maximum number of reflections, or of steps along curved paths
+    #[default]
+    Limit,
+}
+
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct RayPath<const D: usize> {
     points: Vec<SVector<Float, D>>,
+    /// The indices, in `points`, of the points where the ray started,
+    /// or bounced, as opposed to those sampled along curved sections
+    vertices: Vec<usize>,
+    /// The indices, in `points`, of the points where the ray re-entered a periodic box, or left a portal,
+    /// in increasing order
+    wraps: Vec<usize>,
     loop_start: Option<usize>,
     divergence_direction: Option<Unit<SVector<Float, D>>>,
+    final_ray: Option<mirror::Ray<D>>,
+    /// The indices, in `points`, of the points where the ray started, or bounced,
+    /// with the state of the ray leaving them, see [`RayPath::bounces`]
+    bounces: Vec<(usize, mirror::Ray<D>)>,
+    termination: Termination,
+    branches: Vec<RayPath<D>>,
 }
 
 impl<const D: usize> RayPath<D> {
@@ -57,20 +102,118 @@ impl<const D: usize> RayPath<D> {
         self.divergence_direction.as_ref()
     }
 
+    /// The state of the ray (power, polarization...) at the last point of this path
+    pub fn final_ray(&self) -> Option<&mirror::Ray<D>> {
+        self.final_ray.as_ref()
+    }
+
+    /// The indices, in [`Self::all_points_raw`], of the points where the ray started, or bounced,
+    /// (or left the portal it entered there) along with the state of the ray leaving them.
+    ///
+    /// The state of the ray (power, time...) at other points can be deduced from the last of
+    /// these before them, since it only changes when it bounces, or travels.
+    pub fn bounces(&self) -> &[(usize, mirror::Ray<D>)] {
+        self.bounces.as_slice()
+    }
+
+    /// The straight segments of this path, as the state of the ray at their start, (see
+    /// [`Self::bounces`]) and their length, skipping the jumps to the points of [`Self::wraps`].
+    ///
+    /// If the ray escaped, the last one is infinite, (see [`Self::divergence_direction`])
+    /// curved sections of the path are approximated by their sampled points.
+    pub fn segments(&self) -> impl Iterator<Item = (mirror::Ray<D>, Float)> + '_ {
+        let ends = self
+            .bounces
+            .iter()
+            .skip(1)
+            .map(|(i, _)| i + 1)
+            .chain([self.points.len()]);
+
+        let escape = self
+            .final_ray
+            .filter(|_| self.termination == Termination::Escaped)
+            .map(|ray| (ray, Float::INFINITY));
+
+        self.bounces
+            .iter()
+            .zip(ends)
+            .flat_map(move |(&(start, mut ray), end)| {
+                (start + 1..end).filter_map(move |i| {
+                    let point = self.points[i];
+                    let chord = point - ray.origin;
+                    let length = chord.norm();
+
+                    let segment = self.wraps.binary_search(&i).is_err().then(|| {
+                        if let Some(direction) = Unit::try_new(chord, Float::EPSILON) {
+                            ray.direction = direction;
+                        }
+
+                        let segment = (ray, length);
+                        ray.time += length;
+                        segment
+                    });
+
+                    ray.origin = point;
+                    segment
+                })
+            })
+            .chain(escape)
+    }
+
+    /// Why the ray stopped being traced, at the last point of this path
+    pub fn termination(&self) -> Termination {
+        self.termination
+    }
+
+    /// The paths of the rays split from this one (by beam splitters), starting
+    /// at the point where they were split, in the order they were split
+    pub fn branches(&self) -> &[RayPath<D>] {
+        self.branches.as_slice()
+    }
+
+    /// The indices, in [`Self::all_points_raw`], of the points where the ray re-entered
+    /// the periodic box of the simulation, (see [`Simulation::periodic`]) after leaving
+    /// it at the previous point, from the opposite face, or left the portal linked to the
+    /// one it entered at the previous point, (see [`mirror::portal::Portals`]). The path
+    /// is interrupted there.
+    pub fn wraps(&self) -> &[usize] {
+        self.wraps.as_slice()
+    }
+
+    /// Iterates over this path, and all of it's branches, recursively
+    pub fn all_branches(&self) -> impl Iterator<Item = &RayPath<D>> {
+        let mut stack = vec![self];
+        iter::from_fn(move || {
+            let path = stack.pop()?;
+            stack.extend(path.branches.iter().rev());
+            Some(path)
+        })
+    }
+
     pub fn push_point(&mut self, pt: SVector<Float, D>) {
+        self.vertices.push(self.points.len());
+        self.points.push(pt);
+    }
+
+    /// Pushes a point sampled along a curved section of the path, between two bounces.
+    ///
+    /// Unlike those pushed with [`Self::push_point`], it is ignored by [`Self::causes_loop_at`]
+    pub fn push_curve_point(&mut self, pt: SVector<Float, D>) {
         self.points.push(pt);
     }
 
     pub fn causes_loop_at(&self, pt: SVector<Float, D>, epsilon: Float) -> Option<usize> {
-        self.points.split_last().and_then(|(last_pt, points)| {
-            points.windows(2).enumerate().find_map(|(i, window)| {
+        self.vertices.split_last().and_then(|(last, vertices)| {
+            let last_pt = &self.points[*last];
+            vertices.windows(2).find_map(|window| {
                 // ugly, but `slice::array_windows` is unstable
-                let [this_pt, next_pt] = window else {
+                let &[this, next] = window else {
                     // because window.len() is always 2
                     unreachable!()
                 };
-                ((last_pt - this_pt).norm() <= epsilon && (pt - next_pt).norm() < epsilon)
-                    .then_some(i)
+                ((last_pt - self.points[this]).norm() <= epsilon
+                    && (pt - self.points[next]).norm() < epsilon)
+                    .then_some(this)
             })
         })
     }
@@ -82,6 +225,7 @@ impl<const D: usize> RayPath<D> {
 
         if let Some(loop_index) = maybe_loop_index {
             self.loop_start = Some(loop_index);
+            self.termination = Termination::Loop;
         } else {
             self.push_point(pt);
         }
@@ -89,6 +233,32 @@ impl<const D: usize> RayPath<D> {
         maybe_loop_index.is_none()
     }
 
+    /// Records that the ray left a periodic box at `exit`, and re-entered it at `entry`.
+    ///
+    /// Like [`Self::try_push_point`], returns `false` if `entry` causes an infinite loop.
+    pub fn try_push_wrap(
+        &mut self,
+        exit: SVector<Float, D>,
+        entry: SVector<Float, D>,
+        epsilon: Float,
+    ) -> bool {
+        self.push_curve_point(exit);
+        self.try_push_teleport(entry, epsilon)
+    }
+
+    /// Records that the ray jumped from the last point of the path, to `entry`, through a portal.
+    ///
+    /// Like [`Self::try_push_point`], returns `false` if `entry` causes an infinite loop.
+    pub fn try_push_teleport(&mut self, entry: SVector<Float, D>, epsilon: Float) -> bool {
+        let pushed = self.try_push_point(entry, epsilon);
+
+        if pushed {
+            self.wraps.push(self.points.len() - 1);
+        }
+
+        pushed
+    }
+
     pub fn set_divergence_direction(&mut self, dir: Unit<SVector<Float, D>>) -> bool {
         let first_time = self.divergence_direction.is_none();
         self.divergence_direction = Some(dir);
@@ -105,19 +275,25 @@ impl<const D: usize> RayPath<D> {
     where
         render::Vertex<D>: gl::Vertex,
     {
-        let (non_loop_pts, loop_pts) = self.all_points();
+        let loop_start = self.loop_start.unwrap_or(self.points.len());
+
+        // pairs of consecutive points, except around wraps, to be drawn as a list of lines
+        let segments = |start: usize, end: usize| {
+            (start + 1..end)
+                .filter(|i| self.wraps.binary_search(i).is_err())
+                .flat_map(|i| [self.points[i - 1], self.points[i]])
+        };
 
         let non_loop_pts = Vec::from_iter(
-            non_loop_pts
-                .iter()
-                .copied()
-                .chain(
-                    self.divergence_direction()
-                        .map(|dir| non_loop_pts.last().unwrap() + dir.as_ref() * 2000.),
-                )
+            segments(0, loop_start)
+                .chain(self.divergence_direction().into_iter().flat_map(|dir| {
+                    let last = self.points[loop_start - 1];
+                    [last, last + dir.as_ref() * 2000.]
+                }))
                 .map(render::Vertex::from),
         );
-        let loop_pts = Vec::from_iter(loop_pts.iter().copied().map(render::Vertex::from));
+        let loop_pts =
+            Vec::from_iter(segments(loop_start, self.points.len()).map(render::Vertex::from));
 
         (
             gl::VertexBuffer::immutable(display, non_loop_pts.as_slice()).unwrap(),
@@ -126,9 +302,36 @@ impl<const D: usize> RayPath<D> {
     }
 }
 
+/// The way rays travel between two surfaces
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Propagation {
+    /// Along straight lines, (or curves, in gradient-index regions)
+    #[default]
+    Straight,
+    /// Along circles, like charged particles in a uniform magnetic field,
+    /// see [`mirror::arc::Arc::larmor`]. Only supported in 2D.
+    Magnetic { larmor_radius: Float },
+    /// Along the geodesics of the hyperbolic plane, in the Poincaré disk model,
+    /// (see [`mirror::hyperbolic`]) until they reach the unit circle, (at infinity)
+    /// where they stop. Only supported in 2D, with rays starting inside the unit disk.
+    ///
+    /// The time of rays (see [`mirror::Ray::time`]) still grows by the euclidean length of
+    /// their path in the model, not by the hyperbolic one, which is infinite up to the unit circle.
+    Hyperbolic,
+}
+
 pub struct Simulation<T, const D: usize> {
     pub rays: Vec<mirror::Ray<D>>,
     pub mirror: T,
+    /// The seed of the random number generator used by scattering surfaces
+    pub seed: u64,
+    pub propagation: Propagation,
+    /// If set, rays leaving this box re-enter it from the opposite face, (only
+    /// with [`Propagation::Straight`], outside of gradient-index regions)
+    pub periodic: Option<boundary::PeriodicBox<D>>,
+    /// If set, rays reaching the boundary of this box are absorbed there, (only
+    /// with [`Propagation::Straight`], and without a periodic box)
+    pub world: Option<boundary::WorldBox<D>>,
 }
 
 impl<T: mirror::Random, const D: usize> mirror::Random for Simulation<T, D> {
@@ -144,6 +347,10 @@ impl<T: mirror::Random, const D: usize> mirror::Random for Simulation<T, D> {
                 .take(num_rays)
                 .collect(),
             mirror: T::random(rng),
+            seed: rng.gen(),
+            propagation: Propagation::Straight,
+            periodic: None,
+            world: None,
         }
     }
 }
@@ -152,68 +359,494 @@ impl<const D: usize, T: mirror::JsonDes> JsonDes for Simulation<T, D> {
     fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
         let mirror = T::from_json(json.get("mirror").ok_or("mirror field expected")?)?;
 
-        let rays = util::map_json_array(
+        let rays: Vec<mirror::Ray<D>> = util::map_json_array(
             json.get("rays").ok_or("ray field expected")?,
             mirror::Ray::from_json,
         )?;
 
-        Ok(Self { mirror, rays })
+        let seed = json
+            .get("seed")
+            .map(|seed| seed.as_u64().ok_or("seed must be a non-negative integer"))
+            .transpose()?
+            .unwrap_or_default();
+
+        let propagation = json
+            .get("propagation")
+            .map(|json| -> Result<_, Box<dyn Error>> {
+                let mode = json
+                    .get("mode")
+                    .and_then(serde_json::Value::as_str)
+                    .ok_or("Missing propagation mode")?;
+
+                match mode {
+                    "straight" => Ok(Propagation::Straight),
+                    "magnetic" | "hyperbolic" if D != 2 => {
+                        Err(format!("{mode} propagation is only supported in 2D").into())
+                    }
+                    "hyperbolic" => {
+                        if rays.iter().any(|ray| ray.origin.norm() >= 1.0) {
+                            return Err("rays must start inside the unit disk".into());
+                        }
+
+                        Ok(Propagation::Hyperbolic)
+                    }
+                    "magnetic" => {
+                        let larmor_radius = json
+                            .get("larmor_radius")
+                            .and_then(serde_json::Value::as_f64)
+                            .filter(|r| r.is_normal())
+                            .ok_or("Missing or invalid larmor radius")?;
+
+                        Ok(Propagation::Magnetic { larmor_radius })
+                    }
+                    other => Err(format!("Unknown propagation mode: {other}").into()),
+                }
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let periodic = json
+            .get("periodic")
+            .map(boundary::PeriodicBox::from_json)
+            .transpose()?;
+
+        if let Some(periodic) = &periodic {
+            if propagation != Propagation::Straight {
+                return Err("periodic boxes are only supported with straight propagation".into());
+            }
+
+            if !rays.iter().all(|ray| periodic.contains(&ray.origin)) {
+                return Err("rays must start inside the periodic box".into());
+            }
+        }
+
+        let world = json
+            .get("world")
+            .map(boundary::WorldBox::from_json)
+            .transpose()?;
+
+        if let Some(world) = &world {
+            if propagation != Propagation::Straight || periodic.is_some() {
+                return Err("world boxes are only supported with straight propagation, \
+                    without a periodic box"
+                    .into());
+            }
+
+            if !rays.iter().all(|ray| world.contains(&ray.origin)) {
+                return Err("rays must start inside the world box".into());
+            }
+        }
+
+        Ok(Self {
+            mirror,
+            rays,
+            seed,
+            propagation,
+            periodic,
+            world,
+        })
     }
 }
 
 impl<const D: usize, T: mirror::JsonSer> JsonSer for Simulation<T, D> {
     fn to_json(&self) -> serde_json::Value {
-        serde_json::json!({
+        let mut json = serde_json::json!({
             "dim": D,
             "rays": Vec::from_iter(self.rays.iter().map(mirror::Ray::to_json)),
             "mirror": self.mirror.to_json(),
-        })
+        });
+
+        if self.seed != 0 {
+            json["seed"] = self.seed.into();
+        }
+
+        match self.propagation {
+            Propagation::Straight => {}
+            Propagation::Magnetic { larmor_radius } => {
+                json["propagation"] = serde_json::json!({
+                    "mode": "magnetic",
+                    "larmor_radius": larmor_radius,
+                })
+            }
+            Propagation::Hyperbolic => {
+                json["propagation"] = serde_json::json!({ "mode": "hyperbolic" })
+            }
+        }
+
+        if let Some(periodic) = &self.periodic {
+            json["periodic"] = periodic.to_json();
+        }
+
+        if let Some(world) = &self.world {
+            json["world"] = world.to_json();
+        }
+
+        json
     }
 }
 
 impl<const D: usize, T: mirror::Mirror<D>> Simulation<T, D> {
+    /// Traces the paths of the rays of this simulation, see [`Self::get_ray_paths_with_rng`],
+    /// with a random number generator seeded with `self.seed`, so the result is reproducible.
     pub fn get_ray_paths(&self, reflection_limit: usize) -> Vec<RayPath<D>> {
+        use rand::SeedableRng;
+
+        self.get_ray_paths_with_rng(
+            reflection_limit,
+            &mut rand::rngs::StdRng::seed_from_u64(self.seed),
+        )
+    }
+
+    /// Traces the paths of the rays of this simulation, bouncing at most `reflection_limit` times.
+    ///
+    /// The random choices of scattering surfaces are drawn from `rng`.
+    ///
+    /// Rays hitting a beam splitter are split, see [`RayPath::branches`].
+    pub fn get_ray_paths_with_rng(
+        &self,
+        reflection_limit: usize,
+        rng: &mut (impl rand::Rng + ?Sized),
+    ) -> Vec<RayPath<D>> {
         let mut intersections_scratch = vec![];
         self.rays
             .iter()
             .map(|ray| {
-                let mut ray = *ray;
-                let mut ray_path = RayPath::default();
-                ray_path.push_point(ray.origin);
-
-                for _n in 0..reflection_limit {
-                    intersections_scratch.clear();
-                    self.mirror.append_intersecting_points(
-                        &ray,
-                        util::List::new(&mut intersections_scratch),
-                    );
-
-                    if let Some((distance, tangent)) = intersections_scratch
-                        .iter()
-                        .filter_map(|tangent| {
-                            let d = tangent
-                                .try_ray_intersection(&ray)
-                                .expect("a mirror returned a plane parallel to the ray: aborting");
-                            (d > Float::EPSILON * 64.0).then_some((d, tangent))
-                        })
-                        .min_by(|(d1, _), (d2, _)| {
-                            d1.partial_cmp(d2)
-                                .expect("NaN found in intersection distances: aborting")
-                        })
-                    {
-                        ray.advance(distance);
-                        if !ray_path.try_push_point(ray.origin, Float::EPSILON * 16.0) {
-                            break;
+                self.trace(
+                    *ray,
+                    reflection_limit,
+                    ray.power * MIN_BRANCH_POWER,
+                    rng,
+                    &mut intersections_scratch,
+                )
+            })
+            .collect()
+    }
+
+    /// Traces the path of `ray`, and of the branches split from it
+    /// whose power is at least `min_branch_power`.
+    fn trace(
+        &self,
+        mut ray: mirror::Ray<D>,
+        reflection_limit: usize,
+        min_branch_power: Float,
+        rng: &mut (impl rand::Rng + ?Sized),
+        intersections_scratch: &mut Vec<mirror::TangentPlane<D>>,
+    ) -> RayPath<D> {
+        let mut ray_path = RayPath::default();
+        ray_path.push_point(ray.origin);
+        ray_path.bounces.push((0, ray));
+
+        let mut outgoing_rays = vec![];
+
+        for n in 0..reflection_limit {
+            let Some(tangent) = self.next_hit(&mut ray, &mut ray_path, intersections_scratch)
+            else {
+                break;
+            };
+
+            if !ray_path.try_push_point(ray.origin, Float::EPSILON * 16.0) {
+                break;
+            }
+
+            let hit = ray.origin;
+
+            if let mirror::Surface::Refractive { relative_index } = tangent.surface {
+                if !(relative_index > 0.0 && relative_index.is_finite()) {
+                    ray_path.termination = Termination::NonPositiveIndex;
+                    break;
+                }
+            }
+
+            outgoing_rays.clear();
+            tangent.append_outgoing_rays(&ray, rng, util::List::new(&mut outgoing_rays));
+
+            if outgoing_rays.len() > 1 {
+                outgoing_rays.retain(|outgoing| outgoing.power >= min_branch_power);
+            }
+
+            let mut outgoing_rays = outgoing_rays.drain(..);
+
+            if let Some(outgoing) = outgoing_rays.next() {
+                ray = outgoing;
+            } else {
+                // absorbed
+                ray.power = 0.0;
+                ray_path.termination = Termination::Absorbed;
+                break;
+            }
+
+            for branch in outgoing_rays {
+                let branch_path = self.trace(
+                    branch,
+                    reflection_limit - n - 1,
+                    min_branch_power,
+                    rng,
+                    intersections_scratch,
+                );
+                ray_path.branches.push(branch_path);
+            }
+
+            // the ray went through a portal
+            if ray.origin != hit && !ray_path.try_push_teleport(ray.origin, Float::EPSILON * 16.0) {
+                break;
+            }
+
+            ray_path.bounces.push((ray_path.points.len() - 1, ray));
+        }
+
+        ray_path.final_ray = Some(ray);
+        ray_path
+    }
+
+    /// Returns the closest surface `ray` hits, in front of it, and it's distance
+    fn closest_hit(
+        &self,
+        ray: &mirror::Ray<D>,
+        intersections_scratch: &mut Vec<mirror::TangentPlane<D>>,
+    ) -> Option<(Float, mirror::TangentPlane<D>)> {
+        intersections_scratch.clear();
+        self.mirror
+            .append_intersecting_points(ray, util::List::new(intersections_scratch));
+
+        intersections_scratch
+            .iter()
+            .filter_map(|tangent| {
+                let d = tangent
+                    .try_ray_intersection(ray)
+                    .expect("a mirror returned a plane parallel to the ray: aborting");
+                (d > Float::EPSILON * 64.0).then_some((d, *tangent))
+            })
+            .min_by(|(d1, _), (d2, _)| {
+                d1.partial_cmp(d2)
+                    .expect("NaN found in intersection distances: aborting")
+            })
+    }
+
+    /// Moves `ray` forward, to the next surface it hits, and returns that surface.
+    ///
+    /// Inside gradient-index regions, the ray follows a curved path, (see
+    /// [`mirror::grin::CurvedRay`]) whose sampled points are pushed to `ray_path`.
+    ///
+    /// Returns `None` if the ray doesn't hit anything, after recording
+    /// where it goes in `ray_path`, (a divergence direction, or a loop).
+    fn next_hit(
+        &self,
+        ray: &mut mirror::Ray<D>,
+        ray_path: &mut RayPath<D>,
+        intersections_scratch: &mut Vec<mirror::TangentPlane<D>>,
+    ) -> Option<mirror::TangentPlane<D>> {
+        match self.propagation {
+            Propagation::Straight => {}
+            Propagation::Magnetic { larmor_radius } => {
+                let arc = mirror::arc::Arc::larmor(ray, larmor_radius);
+                return self.next_arc_hit(ray, ray_path, &arc, None);
+            }
+            Propagation::Hyperbolic => {
+                return self.next_geodesic_hit(ray, ray_path, intersections_scratch)
+            }
+        }
+
+        let mut curved = None;
+        let mut wraps = 0;
+
+        for _ in 0..MAX_CURVED_STEPS {
+            // look slightly ahead, rays often start on the boundary of the region
+            let ahead = ray.at(Float::EPSILON * 64.0 * (1.0 + ray.origin.norm()));
+
+            let index = self.mirror.refractive_index_at(&ahead);
+
+            if index.is_some_and(|(n, _)| !(n > 0.0 && n.is_finite())) {
+                ray_path.termination = Termination::NonPositiveIndex;
+                return None;
+            }
+
+            let Some((n, _)) = index else {
+                let hit = self.closest_hit(ray, intersections_scratch);
+
+                if let Some(periodic) = &self.periodic {
+                    let (distance, entry) = periodic.exit(ray);
+
+                    if hit.filter(|(d, _)| *d <= distance).is_none() {
+                        let exit = ray.at(distance);
+                        ray.origin = entry;
+                        ray.time += distance;
+                        wraps += 1;
+
+                        if wraps > MAX_WRAPS
+                            || !ray_path.try_push_wrap(exit, entry, Float::EPSILON * 16.0)
+                        {
+                            return None;
                         }
-                        ray.reflect_dir(&tangent.direction)
-                    } else {
-                        ray_path.set_divergence_direction(ray.direction);
-                        break;
+
+                        continue;
+                    }
+                }
+
+                if let Some(world) = &self.world {
+                    let distance = world.exit_distance(ray);
+
+                    if hit.filter(|(d, _)| *d <= distance).is_none() {
+                        ray.advance(distance);
+                        ray_path.push_curve_point(ray.origin);
+                        ray_path.termination = Termination::WorldBoundary;
+                        return None;
                     }
                 }
-                ray_path
+
+                let Some((distance, tangent)) = hit else {
+                    ray_path.set_divergence_direction(ray.direction);
+                    ray_path.termination = Termination::Escaped;
+                    return None;
+                };
+                ray.advance(distance);
+                return Some(tangent);
+            };
+
+            let curved = curved.get_or_insert_with(|| mirror::grin::CurvedRay::new(ray, n));
+
+            let step = curved.advance(|p| {
+                self.mirror
+                    .refractive_index_at(p)
+                    .unwrap_or((1.0, SVector::zeros()))
+            });
+
+            // the step went through a point where the index isn't positive
+            if !curved.position().iter().all(|x| x.is_finite()) {
+                ray_path.termination = Termination::NonPositiveIndex;
+                return None;
+            }
+
+            // look for hits along the chord of the step
+            let chord = curved.position() - ray.origin;
+            let length = chord.norm();
+            if let Some(direction) = Unit::try_new(chord, Float::EPSILON) {
+                ray.direction = direction;
+
+                if let Some((distance, tangent)) = self
+                    .closest_hit(ray, intersections_scratch)
+                    .filter(|(distance, _)| *distance <= length)
+                {
+                    ray.advance(distance);
+                    return Some(tangent);
+                }
+
+                if let Some(distance) = self
+                    .world
+                    .map(|world| world.exit_distance(ray))
+                    .filter(|distance| *distance <= length)
+                {
+                    ray.advance(distance);
+                    ray_path.push_curve_point(ray.origin);
+                    ray_path.termination = Termination::WorldBoundary;
+                    return None;
+                }
+            }
+
+            ray.origin = *curved.position();
+            ray.direction = curved.direction();
+            ray.time += step;
+            ray_path.push_curve_point(ray.origin);
+        }
+
+        // the ray is trapped in a gradient-index region, it's path ends at the last point reached
+        ray_path.termination = Termination::Limit;
+        None
+    }
+
+    /// Moves `ray` forward along `arc`, to the next surface it hits, and returns
+    /// that surface, pushing points sampled along the way to `ray_path`.
+    ///
+    /// If it doesn't hit anything before reaching angle `end`, (see [`mirror::arc::Arc::at`])
+    /// the ray stops there, and `None` is returned. Without an `end`, the ray goes around
+    /// `arc` forever, which is pushed to `ray_path`, as a loop.
+    fn next_arc_hit(
+        &self,
+        ray: &mut mirror::Ray<D>,
+        ray_path: &mut RayPath<D>,
+        arc: &mirror::arc::Arc<D>,
+        end: Option<Float>,
+    ) -> Option<mirror::TangentPlane<D>> {
+        let mut intersections = vec![];
+        self.mirror
+            .append_arc_intersections(arc, util::List::new(&mut intersections));
+
+        let hit = intersections
+            .into_iter()
+            .filter(|(angle, _)| {
+                *angle > Float::EPSILON * 64.0 && *angle < end.unwrap_or(Float::INFINITY)
             })
-            .collect()
+            .min_by(|(a1, _), (a2, _)| {
+                a1.partial_cmp(a2)
+                    .expect("NaN found in intersection angles: aborting")
+            });
+
+        let (angle, tangent) = match (hit, end) {
+            (Some((angle, tangent)), _) => (angle, Some(tangent)),
+            (None, Some(end)) => {
+                ray_path.termination = Termination::Escaped;
+                (end, None)
+            }
+            (None, None) => {
+                ray_path.loop_start = ray_path.vertices.last().copied();
+                ray_path.termination = Termination::Loop;
+                arc.sample(core::f64::consts::TAU)
+                    .for_each(|pt| ray_path.push_curve_point(pt));
+                ray_path.push_curve_point(ray.origin);
+                return None;
+            }
+        };
+
+        arc.sample(angle)
+            .for_each(|pt| ray_path.push_curve_point(pt));
+
+        let rotation = arc.rotation(angle);
+        ray.origin = arc.at(angle);
+        ray.time += angle * arc.radius();
+        ray.direction = Unit::new_normalize(rotation * ray.direction.as_ref());
+        ray.polarization = ray.polarization.map(|p| p.rotated(&rotation));
+
+        if tangent.is_none() {
+            ray_path.push_curve_point(ray.origin);
+        }
+
+        tangent
+    }
+
+    /// Moves `ray` forward along it's geodesic in the Poincaré disk model, (see
+    /// [`mirror::hyperbolic::geodesic`]) to the next surface it hits, and
+    /// returns that surface, pushing points sampled along the way to `ray_path`.
+    ///
+    /// If it doesn't hit anything, the ray stops on the unit circle, and `None` is returned.
+    fn next_geodesic_hit(
+        &self,
+        ray: &mut mirror::Ray<D>,
+        ray_path: &mut RayPath<D>,
+        intersections_scratch: &mut Vec<mirror::TangentPlane<D>>,
+    ) -> Option<mirror::TangentPlane<D>> {
+        if let Some(arc) = mirror::hyperbolic::geodesic(ray) {
+            let end = arc
+                .sphere_intersections(&SVector::zeros(), 1.0)
+                .filter(|angle| *angle > Float::EPSILON * 64.0)
+                .min_by(Float::total_cmp);
+
+            return self.next_arc_hit(ray, ray_path, &arc, end);
+        }
+
+        // the ray travels along a diameter of the disk
+        let end = mirror::hyperbolic::boundary_distance(ray);
+
+        if let Some((distance, tangent)) = self
+            .closest_hit(ray, intersections_scratch)
+            .filter(|(distance, _)| *distance < end)
+        {
+            ray.advance(distance);
+            return Some(tangent);
+        }
+
+        ray.advance(end);
+        ray_path.push_curve_point(ray.origin);
+        ray_path.termination = Termination::Escaped;
+        None
     }
 }
 
@@ -225,7 +858,8 @@ impl<T: mirror::Mirror<3>> Simulation<T, 3> {
     ) -> Vec<render::RayRenderData<3>> {
         self.get_ray_paths(reflection_limit)
             .into_iter()
-            .map(|ray_path| {
+            .zip(&self.rays)
+            .map(|(ray_path, ray)| {
                 // we'll change this to a square or circle that's doesn't get scaled by the projection matrix
                 // use Sphere for 3D, and Circle for 2D
                 let [x, y, z] = ray_path
@@ -248,6 +882,13 @@ impl<T: mirror::Mirror<3>> Simulation<T, 3> {
                     ),
                     non_loop_path,
                     loop_path,
+                    branches: Vec::from_iter(
+                        ray_path
+                            .all_branches()
+                            .skip(1)
+                            .map(|branch| branch.path_vertices(display)),
+                    ),
+                    color: ray.wavelength.map(render::wavelength_color),
                 }
             })
             .collect()
@@ -262,7 +903,8 @@ impl<T: mirror::Mirror<2>> Simulation<T, 2> {
     ) -> Vec<render::RayRenderData<2>> {
         self.get_ray_paths(reflection_limit)
             .into_iter()
-            .map(|ray_path| {
+            .zip(&self.rays)
+            .map(|(ray_path, ray)| {
                 // we'll change this to a square or circle that's doesn't get scaled by the projection matrix
                 // use Sphere for 3D, and Circle for 2D
                 let center = ray_path
@@ -274,12 +916,25 @@ impl<T: mirror::Mirror<2>> Simulation<T, 2> {
 
                 let (non_loop_path, loop_path) = ray_path.path_vertices(display);
 
+                // the Poincaré disk is small
+                let radius = match self.propagation {
+                    Propagation::Hyperbolic => 0.01,
+                    _ => 0.1,
+                };
+
                 render::RayRenderData {
                     origin: Box::new(render::FilledCircle::from(render::Circle::new(
-                        center, 0.1, display,
+                        center, radius, display,
                     ))),
                     non_loop_path,
                     loop_path,
+                    branches: Vec::from_iter(
+                        ray_path
+                            .all_branches()
+                            .skip(1)
+                            .map(|branch| branch.path_vertices(display)),
+                    ),
+                    color: ray.wavelength.map(render::wavelength_color),
                 }
             })
             .collect()
@@ -296,9 +951,24 @@ impl<T: mirror::Mirror<2> + render::OpenGLRenderable> Simulation<T, 2> {
         )
         .unwrap();
 
+        let mut mirror_render_data = self.mirror_render_data(display);
+
+        if self.propagation == Propagation::Hyperbolic {
+            // the boundary of the Poincaré disk
+            mirror_render_data.push(Box::new(render::Circle::new([0.0, 0.0], 1.0, display)));
+        }
+
+        if let Some(periodic) = &self.periodic {
+            periodic.append_render_data(display, util::List::from(&mut mirror_render_data));
+        }
+
+        if let Some(world) = &self.world {
+            world.append_render_data(display, util::List::from(&mut mirror_render_data));
+        }
+
         DrawableSimulation::new(
             self.ray_render_data(reflection_limit, display),
-            self.mirror_render_data(display),
+            mirror_render_data,
             program,
         )
     }
@@ -346,9 +1016,19 @@ impl<T: mirror::Mirror<3> + render::OpenGLRenderable> Simulation<T, 3> {
         )
         .unwrap();
 
+        let mut mirror_render_data = self.mirror_render_data(display);
+
+        if let Some(periodic) = &self.periodic {
+            periodic.append_render_data(display, util::List::from(&mut mirror_render_data));
+        }
+
+        if let Some(world) = &self.world {
+            world.append_render_data(display, util::List::from(&mut mirror_render_data));
+        }
+
         DrawableSimulation::new(
             self.ray_render_data(reflection_limit, display),
-            self.mirror_render_data(display),
+            mirror_render_data,
             program,
         )
     }
@@ -391,6 +1071,81 @@ pub mod util {
         })
     }
 
+    /// Generates a random unit vector, by normalizing random vectors until it works
+    pub fn rand_unit_vect<const D: usize>(
+        rng: &mut (impl rand::Rng + ?Sized),
+    ) -> Unit<SVector<Float, D>> {
+        loop {
+            if let Some(v) = Unit::try_new(rand_vect(rng, 1.0), Float::EPSILON * 8.0) {
+                break v;
+            }
+        }
+    }
+
+    /// Returns an orthonormal basis of the whole space whose first vector is `v`.
+    ///
+    /// The remaining `D - 1` vectors thus form an orthonormal basis of `v`'s orthogonal complement.
+    pub fn basis_from_vector<const D: usize>(
+        v: &Unit<SVector<Float, D>>,
+    ) -> [SVector<Float, D>; D] {
+        let mut basis = [SVector::zeros(); D];
+        basis[0] = v.into_inner();
+
+        let mut len = 1;
+        // complete the family with the canonical basis, (Gram-Schmidt)
+        for i in 0..D {
+            if len == D {
+                break;
+            }
+
+            let mut e = SVector::<Float, D>::from_fn(|j, _| if i == j { 1.0 } else { 0.0 });
+            for b in &basis[..len] {
+                e -= e.dot(b) * b;
+            }
+
+            // the canonical basis vector (almost) belongs to the span of the previous ones
+            if let Some(e) = Unit::try_new(e, 1e-6) {
+                basis[len] = e.into_inner();
+                len += 1;
+            }
+        }
+
+        basis
+    }
+
+    /// Returns the determinant of `m`, computed with gaussian elimination, since
+    /// nalgebra only provides it for matrices of known dimensions
+    pub fn determinant<const D: usize>(mut m: SMatrix<Float, D, D>) -> Float {
+        let mut det = 1.0;
+
+        for j in 0..D {
+            // partial pivoting
+            let pivot = (j..D)
+                .max_by(|&a, &b| m[(a, j)].abs().total_cmp(&m[(b, j)].abs()))
+                .unwrap();
+
+            if m[(pivot, j)] == 0.0 {
+                return 0.0;
+            }
+
+            if pivot != j {
+                m.swap_rows(pivot, j);
+                det = -det;
+            }
+
+            det *= m[(j, j)];
+
+            for i in j + 1..D {
+                let factor = m[(i, j)] / m[(j, j)];
+                for k in j..D {
+                    m[(i, k)] -= factor * m[(j, k)];
+                }
+            }
+        }
+
+        det
+    }
+
     /// This is essentially `try_into` then `try_map` but the latter is nightly-only
     pub fn json_array_to_float_array<const D: usize>(
         json_array: &[serde_json::Value],
@@ -1,10 +1,32 @@
 use core::ops::Deref;
 
+use nalgebra::Complex;
+
 use super::*;
 
+pub mod aperture;
+pub mod arc;
+pub mod cone;
+pub mod csg;
 pub mod cylinder;
+pub mod disk;
+pub mod grating;
+pub mod grin;
+pub mod heightfield;
+pub mod hyperbolic;
+pub mod implicit;
+pub mod material;
+pub mod motion;
+pub mod optics;
 pub mod plane;
+pub mod polarization;
+pub mod portal;
+pub mod scattering;
+pub mod simplex;
 pub mod sphere;
+pub mod spherical_cap;
+pub mod torus;
+pub mod transform;
 
 use util::List;
 
@@ -15,17 +37,39 @@ pub struct Ray<const D: usize> {
     pub origin: SVector<Float, D>,
     /// the direction of the half-line
     pub direction: Unit<SVector<Float, D>>,
+    /// The wavelength of the light carried by this ray, in nanometres, if it has one
+    pub wavelength: Option<Float>,
+    /// The power carried by this ray, in arbitrary units, surfaces can absorb part of it
+    pub power: Float,
+    /// The polarization state of this ray, if it is tracked
+    pub polarization: Option<polarization::Polarization<D>>,
+    /// The time at which this ray is at `origin`. Rays travel at unit speed, so it
+    /// grows by the distance travelled, used by moving mirrors (see [`motion::Moving`])
+    pub time: Float,
 }
 
 impl<const D: usize> Ray<D> {
+    /// Create a new ray, without a wavelength, with a power of `1.0`, at time `0.0`
+    pub fn new(origin: SVector<Float, D>, direction: Unit<SVector<Float, D>>) -> Self {
+        Self {
+            origin,
+            direction,
+            wavelength: None,
+            power: 1.0,
+            polarization: None,
+            time: 0.0,
+        }
+    }
+
     /// Reflect the ray's direction with respect to the given hyperplane
     pub fn reflect_dir(&mut self, tangent: &TangentSpace<D>) {
         self.direction = tangent.reflect_unit(self.direction);
     }
 
-    /// Move the ray's position forward (or backward if t < 0.0) by `t`
+    /// Move the ray's position forward (or backward if t < 0.0) by `t`, and it's time as well
     pub fn advance(&mut self, t: Float) {
         self.origin += t * self.direction.into_inner();
+        self.time += t;
     }
 
     /// Get the point at distance `t` (can be negative) from the ray's origin
@@ -138,6 +182,22 @@ impl<const D: usize> AffineHyperPlaneOrtho<D> {
         self.basis().iter().map(|e| v.dot(e) * e).sum()
     }
 
+    /// Returns a unit vector orthogonal to this plane's direction hyperplane.
+    pub fn normal(&self) -> Unit<SVector<Float, D>> {
+        // the vector of the canonical basis furthest from the
+        // hyperplane can't belong to it, keep it's orthogonal part
+        let v = (0..D)
+            .map(|i| {
+                let mut e = SVector::zeros();
+                e[i] = 1.0;
+                e - self.orthogonal_projection(e)
+            })
+            .max_by(|v1, v2| v1.norm_squared().total_cmp(&v2.norm_squared()))
+            .unwrap();
+
+        Unit::new_normalize(v)
+    }
+
     /// Returns the point in this plane whose distance with `p` is smallest.
     pub fn orthogonal_point_projection(&self, p: SVector<Float, D>) -> SVector<Float, D> {
         let v0 = self.v0();
@@ -230,14 +290,234 @@ pub enum Intersection<const D: usize> {
     StartingPoint(SVector<Float, D>),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+/// The way a surface changes the direction of the rays hitting it
+pub enum Surface<const D: usize> {
+    /// Rays are orthogonally reflected w.r.t. the tangent hyperplane
+    #[default]
+    Reflective,
+    /// Rays are reflected, but only keep a fraction `reflectivity` of their power
+    Coated { reflectivity: Float },
+    /// Rays are reflected by a metal of complex refractive index `refractive_index`,
+    /// see [`material::fresnel_reflection`]
+    Metallic { refractive_index: Complex<Float> },
+    /// Rays are refracted into a medium whose refractive index, relative
+    /// to the one they come from, is `relative_index`, see [`material::refract`]
+    Refractive { relative_index: Float },
+    /// Rays are diffracted, see [`grating::Grating`]
+    Grating(grating::Grating<D>),
+    /// Rays are scattered in a random direction, see [`scattering::Scattering`]
+    Scattering(scattering::Scattering),
+    /// Rays go through, and are bent as by an ideal thin lens, see [`optics::ThinLens`].
+    ///
+    /// `deflection` is the offset of the hit point from the center of the lens,
+    /// divided by the opposite of it's focal length.
+    Lens { deflection: SVector<Float, D> },
+    /// Rays are split into a reflected ray, carrying a fraction `reflectance`
+    /// of their power, and a transmitted one, see [`optics::BeamSplitter`]
+    BeamSplitter { reflectance: Float },
+    /// Rays are moved, along with their direction, by the isometry `x -> rotation * x + translation`,
+    /// (`rotation` being an orthogonal matrix) to the portal linked to this one, see [`portal::Portals`]
+    Portal {
+        rotation: SMatrix<Float, D, D>,
+        translation: SVector<Float, D>,
+    },
+    /// Rays are absorbed, see [`aperture::Aperture`]
+    Absorbing,
+}
+
+impl<const D: usize> Surface<D> {
+    /// The amplitude coefficients of a perfect mirror, with the conventions of [`polarization::Polarization::transfer`]
+    const PERFECT: [Complex<Float>; 2] = [Complex::new(-1.0, 0.0), Complex::new(1.0, 0.0)];
+    /// The amplitude coefficients of a surface that lets rays through, unchanged
+    const TRANSPARENT: [Complex<Float>; 2] = [Complex::new(1.0, 0.0), Complex::new(1.0, 0.0)];
+
+    /// Returns `ray` after hitting this surface, at a point where it's tangent hyperplane
+    /// has direction `tangent`. Only it's direction, power and polarization change,
+    /// except through portals, which move it elsewhere.
+    ///
+    /// Random choices, (made by scattering surfaces, and beam splitters,
+    /// which send the ray in one of their two directions) are drawn from `rng`.
+    ///
+    /// Returns `None` if the ray is absorbed by the surface.
+    pub fn outgoing_ray(
+        &self,
+        ray: &Ray<D>,
+        tangent: &TangentSpace<D>,
+        rng: &mut (impl rand::Rng + ?Sized),
+    ) -> Option<Ray<D>> {
+        let reflected = tangent.reflect_unit(ray.direction);
+        // d . reflect(d) = 1 - 2 * cos_i²
+        let cos_i = || {
+            ((1.0 - ray.direction.dot(&reflected)) * 0.5)
+                .max(0.0)
+                .sqrt()
+        };
+
+        let (direction, amplitudes) = match self {
+            Self::Reflective => (reflected, Self::PERFECT),
+            Self::Coated { reflectivity } => {
+                (reflected, Self::PERFECT.map(|a| a * reflectivity.sqrt()))
+            }
+            Self::Metallic { refractive_index } => (
+                reflected,
+                material::fresnel_reflection(cos_i(), *refractive_index),
+            ),
+            Self::Refractive { relative_index } => {
+                match material::refract(&ray.direction, tangent, *relative_index) {
+                    Some((direction, transmittances)) => {
+                        (direction, transmittances.map(|t| Complex::from(t.sqrt())))
+                    }
+                    // total internal reflection
+                    None => (
+                        reflected,
+                        material::fresnel_reflection(cos_i(), Complex::from(*relative_index)),
+                    ),
+                }
+            }
+            Self::Grating(grating) => (grating.diffract(ray, tangent)?, Self::PERFECT),
+            Self::Scattering(scattering) => {
+                // scattered light is depolarized
+                return Some(Ray {
+                    direction: scattering.scatter(&ray.direction, tangent, rng)?,
+                    polarization: None,
+                    ..*ray
+                });
+            }
+            Self::Lens { deflection } => (
+                optics::deflect(&ray.direction, tangent, deflection),
+                Self::TRANSPARENT,
+            ),
+            Self::BeamSplitter { reflectance } => {
+                if rng.gen::<Float>() < *reflectance {
+                    (reflected, Self::PERFECT)
+                } else {
+                    (ray.direction, Self::TRANSPARENT)
+                }
+            }
+            Self::Portal {
+                rotation,
+                translation,
+            } => {
+                return Some(Ray {
+                    origin: rotation * ray.origin + translation,
+                    direction: Unit::new_normalize(rotation * ray.direction.as_ref()),
+                    polarization: ray.polarization.map(|p| p.rotated(rotation)),
+                    ..*ray
+                });
+            }
+            Self::Absorbing => return None,
+        };
+
+        Some(Self::bounce(ray, tangent, direction, amplitudes))
+    }
+
+    /// Appends to `list` the rays leaving this surface after `ray` hits it, see [`Self::outgoing_ray`].
+    ///
+    /// Unlike [`Self::outgoing_ray`], which picks one of them at random, beam splitters
+    /// send both a reflected and a transmitted ray, each with it's share of the power.
+    /// Other surfaces append at most one ray.
+    pub fn append_outgoing_rays(
+        &self,
+        ray: &Ray<D>,
+        tangent: &TangentSpace<D>,
+        rng: &mut (impl rand::Rng + ?Sized),
+        mut list: List<Ray<D>>,
+    ) {
+        match self {
+            Self::BeamSplitter { reflectance } => {
+                let (r, t) = (reflectance.sqrt(), (1.0 - reflectance).sqrt());
+
+                list.push(Self::bounce(
+                    ray,
+                    tangent,
+                    tangent.reflect_unit(ray.direction),
+                    Self::PERFECT.map(|a| a * r),
+                ));
+                list.push(Self::bounce(
+                    ray,
+                    tangent,
+                    ray.direction,
+                    Self::TRANSPARENT.map(|a| a * t),
+                ));
+            }
+            _ => list.extend(self.outgoing_ray(ray, tangent, rng)),
+        }
+    }
+
+    /// Returns `ray` leaving in `direction`, with the components of it's electric
+    /// field multiplied by `amplitudes`, see [`polarization::Polarization::transfer`]
+    fn bounce(
+        ray: &Ray<D>,
+        tangent: &TangentSpace<D>,
+        direction: Unit<SVector<Float, D>>,
+        amplitudes: [Complex<Float>; 2],
+    ) -> Ray<D> {
+        let mut outgoing = Ray { direction, ..*ray };
+
+        outgoing.power *= match &mut outgoing.polarization {
+            Some(polarization) => {
+                polarization.transfer(&ray.direction, &direction, tangent, amplitudes)
+            }
+            // unpolarized light is an even mix of s and p polarized light
+            None => 0.5 * amplitudes.iter().map(Complex::norm_sqr).sum::<Float>(),
+        };
+
+        outgoing
+    }
+
+    /// Applies `rotation` (an orthogonal matrix) to the vectors this surface is parametrized with
+    pub fn rotated(&self, rotation: &SMatrix<Float, D, D>) -> Self {
+        match self {
+            Self::Grating(grating) => Self::Grating(grating.rotated(rotation)),
+            Self::Lens { deflection } => Self::Lens {
+                deflection: rotation * deflection,
+            },
+            // conjugate the isometry of the portal by the rotation
+            Self::Portal {
+                rotation: isometry,
+                translation,
+            } => Self::Portal {
+                rotation: rotation * isometry * rotation.transpose(),
+                translation: rotation * translation,
+            },
+            _ => *self,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 /// Different ways of representing an _affine_ hyperplane in `D`-dimensional euclidean space
 pub struct TangentPlane<const D: usize> {
     pub intersection: Intersection<D>,
     pub direction: TangentSpace<D>,
+    /// How rays bounce off this plane
+    pub surface: Surface<D>,
 }
 
 impl<const D: usize> TangentPlane<D> {
+    /// Returns `ray` after bouncing off this plane, or `None`
+    /// if it is absorbed, see [`Surface::outgoing_ray`]
+    pub fn outgoing_ray(
+        &self,
+        ray: &Ray<D>,
+        rng: &mut (impl rand::Rng + ?Sized),
+    ) -> Option<Ray<D>> {
+        self.surface.outgoing_ray(ray, &self.direction, rng)
+    }
+
+    /// Appends to `list` the rays leaving this plane after
+    /// `ray` hits it, see [`Surface::append_outgoing_rays`]
+    pub fn append_outgoing_rays(
+        &self,
+        ray: &Ray<D>,
+        rng: &mut (impl rand::Rng + ?Sized),
+        list: List<Ray<D>>,
+    ) {
+        self.surface
+            .append_outgoing_rays(ray, &self.direction, rng, list)
+    }
+
     /// Reflect a vector w.r.t this tangent plane's direction hyperplane
     pub fn reflect(&self, v: SVector<Float, D>) -> SVector<Float, D> {
         self.direction.reflect(v)
@@ -269,7 +549,7 @@ impl<const D: usize> TangentPlane<D> {
 ///
 /// Some mirrors, (see [`Plane`][plane::PlaneMirror] and [`Sphere`][sphere::EuclideanSphereMirror])
 /// exist and are easy to implement in all dimensions. Hence why they implement [`Mirror<D>`]
-/// for all (non-zero) `D`. Others, (like [`Cylinder`][cylinder::CylindricalMirror]) are only
+/// for all (non-zero) `D`. Others, (like [`Torus`][torus::TorusMirror]) are only
 /// implemented in _some_ dimensions.
 ///
 /// However, note that, currently, only simulations in dimensions `2` and `3` can be rendered
@@ -285,7 +565,8 @@ pub trait Mirror<const D: usize> {
     /// Here, "bounce" refers to the process of:
     ///     - Moving forward to the intersection.
     ///     - Then, orthogonally reflecting it's direction vector with
-    ///       respect to the direction hyperplane.
+    ///       respect to the direction hyperplane (or, more generally, changing
+    ///       it according to the plane's [`Surface`]).
     ///
     /// Appends nothing if the ray doesn't intersect with the mirror that `self` represents.
     ///
@@ -295,6 +576,56 @@ pub trait Mirror<const D: usize> {
     /// This method is deterministic, i. e. not random: for some `ray`, it always has
     /// the same behavior for that `ray`, regardless of other circumstances/external state.
     fn append_intersecting_points(&self, ray: &Ray<D>, list: List<TangentPlane<D>>);
+
+    /// Returns the refractive index at `p`, and it's gradient, if `p` lies inside
+    /// a gradient-index region of this mirror, (see [`grin::GradientIndex`]) where
+    /// simulations trace rays along curved paths, instead of straight lines.
+    ///
+    /// The default implementation returns `None`. This method is deterministic as well.
+    fn refractive_index_at(&self, _p: &SVector<Float, D>) -> Option<(Float, SVector<Float, D>)> {
+        None
+    }
+
+    /// Appends to `list` the points where the circle `arc` crosses this mirror, in no particular
+    /// order, as pairs of the angle at which `arc` reaches them, (see [`arc::Arc::at`]) in
+    /// `[0 ; 2π]`, and the tangent plane there, used by simulations where rays travel
+    /// along circles (see [`crate::Propagation`]).
+    ///
+    /// The default implementation approximates the circle with
+    /// [`arc::CHORDS_PER_TURN`] chords, intersected with [`Self::append_intersecting_points`].
+    fn append_arc_intersections(
+        &self,
+        arc: &arc::Arc<D>,
+        mut list: List<(Float, TangentPlane<D>)>,
+    ) {
+        let step = core::f64::consts::TAU / arc::CHORDS_PER_TURN as Float;
+        let mut hits = vec![];
+
+        for k in 0..arc::CHORDS_PER_TURN {
+            let start = arc.at(k as Float * step);
+            let chord = arc.at((k + 1) as Float * step) - start;
+            let length = chord.norm();
+
+            let Some(direction) = Unit::try_new(chord, Float::EPSILON) else {
+                return;
+            };
+            let ray = Ray::new(start, direction);
+
+            hits.clear();
+            self.append_intersecting_points(&ray, List::from(&mut hits));
+
+            list.extend(hits.iter().filter_map(|tangent| {
+                let t = tangent.try_ray_intersection(&ray)?;
+                (0.0..=length).contains(&t).then(|| {
+                    let tangent = TangentPlane {
+                        intersection: Intersection::StartingPoint(ray.at(t)),
+                        ..*tangent
+                    };
+                    ((k as Float + t / length) * step, tangent)
+                })
+            }));
+        }
+    }
 }
 
 impl<const D: usize, T: Mirror<D>> Mirror<D> for [T] {
@@ -302,6 +633,19 @@ impl<const D: usize, T: Mirror<D>> Mirror<D> for [T] {
         self.iter()
             .for_each(|mirror| mirror.append_intersecting_points(ray, list.reborrow()))
     }
+
+    fn refractive_index_at(&self, p: &SVector<Float, D>) -> Option<(Float, SVector<Float, D>)> {
+        self.iter().find_map(|mirror| mirror.refractive_index_at(p))
+    }
+
+    fn append_arc_intersections(
+        &self,
+        arc: &arc::Arc<D>,
+        mut list: List<(Float, TangentPlane<D>)>,
+    ) {
+        self.iter()
+            .for_each(|mirror| mirror.append_arc_intersections(arc, list.reborrow()))
+    }
 }
 
 impl<const D: usize, T: Deref> Mirror<D> for T
@@ -311,6 +655,14 @@ where
     fn append_intersecting_points(&self, ray: &Ray<D>, list: List<TangentPlane<D>>) {
         self.deref().append_intersecting_points(ray, list)
     }
+
+    fn refractive_index_at(&self, p: &SVector<Float, D>) -> Option<(Float, SVector<Float, D>)> {
+        self.deref().refractive_index_at(p)
+    }
+
+    fn append_arc_intersections(&self, arc: &arc::Arc<D>, list: List<(Float, TangentPlane<D>)>) {
+        self.deref().append_arc_intersections(arc, list)
+    }
 }
 
 pub trait JsonType {
@@ -344,10 +696,29 @@ impl<const D: usize> JsonSer for Ray<D> {
     ///
     /// The format of the returned object is explained in [`Self::from_json`]
     fn to_json(&self) -> serde_json::Value {
-        serde_json::json!({
+        let mut json = serde_json::json!({
             "origin": self.origin.as_slice(),
             "direction": self.direction.as_ref().as_slice(),
-        })
+        });
+
+        if let Some(wavelength) = self.wavelength {
+            json["wavelength"] = wavelength.into();
+        }
+
+        if self.power != 1.0 {
+            json["power"] = self.power.into();
+        }
+
+        if let Some(polarization) = &self.polarization {
+            json["polarization"] =
+                serde_json::json!(polarization.jones(&self.direction).map(|z| [z.re, z.im]));
+        }
+
+        if self.time != 0.0 {
+            json["time"] = self.time.into();
+        }
+
+        json
     }
 }
 
@@ -384,6 +755,11 @@ impl<const D: usize> JsonDes for Ray<D> {
     /// {
     ///     "origin": [9., 8., 7., ...], // (an array of D floats)
     ///     "direction": [9., 8., 7., ...], // (an array of D floats, must have at least one non-zero value)
+    ///     "wavelength": 532., // (optional, in nanometres, must be positive)
+    ///     "power": 1., // (optional, defaults to 1, must not be negative)
+    ///     "polarization": [[1., 0.], [0., 1.]], // (optional, 3D only, a Jones vector: two complex
+    ///                                           // numbers, as [re, im] pairs, see `Polarization`)
+    ///     "time": 0., // (optional, defaults to 0, the time at which the ray leaves it's origin)
     /// }
     /// ```
     fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
@@ -404,7 +780,63 @@ impl<const D: usize> JsonDes for Ray<D> {
         let direction =
             Unit::try_new(direction, Float::EPSILON).ok_or("Unable to normalize ray direction")?;
 
-        Ok(Self { origin, direction })
+        let wavelength = json
+            .get("wavelength")
+            .map(|value| {
+                value
+                    .as_f64()
+                    .filter(|wavelength| *wavelength > 0.0)
+                    .ok_or("wavelength must be a positive number")
+            })
+            .transpose()?;
+
+        let power = json
+            .get("power")
+            .map(|value| {
+                value
+                    .as_f64()
+                    .filter(|power| *power >= 0.0)
+                    .ok_or("power must be a non-negative number")
+            })
+            .transpose()?
+            .unwrap_or(1.0);
+
+        let polarization = json
+            .get("polarization")
+            .map(|value| -> Result<_, Box<dyn Error>> {
+                if D != 3 {
+                    return Err("polarization is only supported in 3D".into());
+                }
+
+                let jones = util::map_json_array(value, |z| {
+                    z.as_array()
+                        .map(Vec::as_slice)
+                        .and_then(util::json_array_to_float_array)
+                        .map(|[re, im]| Complex::new(re, im))
+                        .ok_or("Invalid polarization component".into())
+                })?
+                .try_into()
+                .map_err(|_| "polarization must be a Jones vector: two [re, im] pairs")?;
+
+                polarization::Polarization::from_jones(jones, &direction)
+                    .ok_or("polarization must not be zero".into())
+            })
+            .transpose()?;
+
+        let time = json
+            .get("time")
+            .map(|value| value.as_f64().ok_or("time must be a number"))
+            .transpose()?
+            .unwrap_or(0.0);
+
+        Ok(Self {
+            origin,
+            direction,
+            wavelength,
+            power,
+            polarization,
+            time,
+        })
     }
 }
 
@@ -426,12 +858,14 @@ pub trait Random {
 impl<const D: usize> Random for Ray<D> {
     fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
         let origin = util::rand_vect(rng, 7.0);
-
-        let direction = loop {
-            if let Some(v) = Unit::try_new(util::rand_vect(rng, 1.0), Float::EPSILON * 8.0) {
-                break v;
-            }
-        };
-        Self { origin, direction }
+        let direction = util::rand_unit_vect(rng);
+        let wavelength = rng
+            .gen_bool(0.5)
+            .then(|| rng.gen_range(material::VISIBLE_WAVELENGTHS));
+
+        Self {
+            wavelength,
+            ..Self::new(origin, direction)
+        }
     }
 }
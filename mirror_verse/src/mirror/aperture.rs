@@ -0,0 +1,345 @@
+use super::*;
+
+/// A hole of an [`Aperture`]: the points of the aperture closer than `radius` to `center`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hole<const D: usize> {
+    pub center: SVector<Float, D>,
+    pub radius: Float,
+}
+
+/// An opaque screen, with the shape of a [`plane::PlaneMirror`], absorbing the rays hitting
+/// it, (see [`Surface::Absorbing`]) except those going through one of it's holes.
+///
+/// Without holes, it is a stop, or beam block, with one, an iris.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aperture<const D: usize> {
+    plane: plane::PlaneMirror<D>,
+    holes: Vec<Hole<D>>,
+}
+
+impl<const D: usize> Aperture<D> {
+    /// Returns `None` if the radius of one of the holes isn't positive.
+    pub fn new(plane: plane::PlaneMirror<D>, holes: Vec<Hole<D>>) -> Option<Self> {
+        holes
+            .iter()
+            .all(|hole| hole.radius > 0.0)
+            .then_some(Self { plane, holes })
+    }
+
+    pub fn plane(&self) -> &plane::PlaneMirror<D> {
+        &self.plane
+    }
+
+    pub fn holes(&self) -> &[Hole<D>] {
+        self.holes.as_slice()
+    }
+
+    fn in_hole(&self, p: &SVector<Float, D>) -> bool {
+        self.holes
+            .iter()
+            .any(|hole| (p - hole.center).norm_squared() < hole.radius * hole.radius)
+    }
+}
+
+impl<const D: usize> Mirror<D> for Aperture<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        list.extend(self.plane.intersection(ray).and_then(|tangent| {
+            let p = ray.at(tangent.try_ray_intersection(ray)?);
+
+            (!self.in_hole(&p)).then_some(TangentPlane {
+                surface: Surface::Absorbing,
+                ..tangent
+            })
+        }));
+    }
+}
+
+impl<const D: usize> JsonType for Aperture<D> {
+    fn json_type() -> String {
+        "aperture".into()
+    }
+}
+
+impl<const D: usize> JsonDes for Aperture<D> {
+    /// Deserialize a new aperture from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "center": [1., 2., 3., ...], // (an array of D floats)
+    ///     "basis": [ // (an array of D - 1 arrays of D floats, see `PlaneMirror`)
+    ///         [1., 0., 0., ...],
+    ///         [0., 1., 0., ...],
+    ///         ...
+    ///     ],
+    ///     "holes": [ // (optional, defaults to none)
+    ///         {
+    ///             "center": [1., 2., 3., ...], // (an array of D floats, on the plane)
+    ///             "radius": 0.5, // (must be positive)
+    ///         },
+    ///         ...
+    ///     ],
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let plane = plane::PlaneMirror::from_json(json)?;
+
+        let holes = match json.get("holes") {
+            Some(holes) => util::map_json_array(holes, |hole| {
+                let center = hole
+                    .get("center")
+                    .and_then(serde_json::Value::as_array)
+                    .map(Vec::as_slice)
+                    .and_then(util::json_array_to_vector)
+                    .ok_or("Failed to parse hole center")?;
+
+                let radius = hole
+                    .get("radius")
+                    .and_then(serde_json::Value::as_f64)
+                    .ok_or("Failed to parse hole radius")?;
+
+                Ok(Hole { center, radius })
+            })?,
+            None => vec![],
+        };
+
+        Self::new(plane, holes).ok_or("the radii of holes must be positive".into())
+    }
+}
+
+impl<const D: usize> JsonSer for Aperture<D> {
+    /// Serialize an aperture into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = self.plane.to_json();
+        json["holes"] = Vec::from_iter(self.holes.iter().map(|hole| {
+            serde_json::json!({
+                "center": hole.center.as_slice(),
+                "radius": hole.radius,
+            })
+        }))
+        .into();
+        json
+    }
+}
+
+impl render::OpenGLRenderable for Aperture<2> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        // the segment is `center + s * v`, for `s` in `[-1 ; 1]`, cut it
+        // where it goes through holes, and draw the remaining pieces
+        let (center, v) = (
+            self.plane.inner_plane().v0(),
+            self.plane.inner_plane().basis()[0],
+        );
+
+        let mut cuts = Vec::from_iter(self.holes.iter().filter_map(|hole| {
+            // |center + s * v - hole.center|^2 < radius^2
+            let w = center - hole.center;
+            let (a, b, c) = (
+                v.norm_squared(),
+                v.dot(&w),
+                w.norm_squared() - hole.radius.powi(2),
+            );
+            let delta = b * b - a * c;
+            (delta > 0.0).then(|| ((-b - delta.sqrt()) / a, (-b + delta.sqrt()) / a))
+        }));
+        cuts.sort_by(|(s1, _), (s2, _)| s1.total_cmp(s2));
+
+        let mut start = -1.0;
+        let mut pieces = vec![];
+        for (s_in, s_out) in cuts.into_iter().chain([(1.0, 1.0)]) {
+            if s_in > start {
+                pieces.extend([start, s_in.min(1.0)]);
+            }
+            start = start.max(s_out);
+        }
+
+        let vertices = Vec::from_iter(
+            pieces
+                .into_iter()
+                .map(|s| render::Vertex::<2>::from(center + s * v)),
+        );
+
+        list.push(Box::new(plane::PlaneRenderData {
+            vertices: gl::VertexBuffer::new(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+/// The outlines of the holes of an [`Aperture<3>`], as a list of segments
+struct HolesRenderData {
+    vertices: gl::VertexBuffer<render::Vertex3D>,
+}
+
+impl render::RenderData for HolesRenderData {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: gl::index::PrimitiveType::LinesList,
+        }
+    }
+}
+
+impl render::OpenGLRenderable for Aperture<3> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        const NUM_POINTS: usize = 360;
+
+        use core::f64::consts::TAU;
+
+        self.plane.append_render_data(display, list.reborrow());
+
+        let plane = self.plane.orthonormalised();
+        let [u, v] = [plane.basis()[0], plane.basis()[1]];
+
+        // the points of the parallelogram are `v_0 + s * b_1 + t * b_2`, with `s` and `t`
+        // in `[-1 ; 1]`, and `(s, t)` is found by inverting the gram matrix of `(b_1, b_2)`
+        let basis = self.plane.inner_plane().basis();
+        let gram = SMatrix::<Float, 2, 2>::from_fn(|i, j| basis[i].dot(&basis[j]));
+        // SAFETY: the basis of a plane mirror is free
+        let gram_inv = gram.try_inverse().unwrap();
+        let on_mirror = |p: &SVector<Float, 3>| {
+            let w = p - plane.v0();
+            let coords = gram_inv * SVector::<Float, 2>::from_fn(|i, _| basis[i].dot(&w));
+            coords.amax() <= 1.0
+        };
+
+        let mut vertices = vec![];
+        for hole in &self.holes {
+            // the hole cuts a disk in the plane, centered on the projection of it's center
+            let center = plane.orthogonal_point_projection(hole.center);
+            let radius_squared = hole.radius.powi(2) - (hole.center - center).norm_squared();
+            if radius_squared <= 0.0 {
+                continue;
+            }
+            let radius = radius_squared.sqrt();
+
+            let points = Vec::from_iter((0..=NUM_POINTS).map(|i| {
+                let (sin, cos) = (i as Float / NUM_POINTS as Float * TAU).sin_cos();
+                center + (cos * u + sin * v) * radius
+            }));
+
+            // only outline the parts of the hole that are on the mirror
+            vertices.extend(
+                points
+                    .windows(2)
+                    .filter(|w| w.iter().all(on_mirror))
+                    .flatten()
+                    .copied()
+                    .map(render::Vertex3D::from),
+            );
+        }
+
+        if !vertices.is_empty() {
+            list.push(Box::new(HolesRenderData {
+                vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+            }))
+        }
+    }
+}
+
+impl<const D: usize> Random for Aperture<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        let plane = plane::PlaneMirror::random(rng);
+        let center = *plane.inner_plane().v0();
+
+        let holes = Vec::from_iter((0..rng.gen_range(0..3)).map(|_| Hole {
+            center,
+            radius: rng.gen_range(0.5..3.0),
+        }));
+
+        // SAFETY: the radii are positive
+        Self::new(plane, holes).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ray(origin: [Float; 2], direction: [Float; 2]) -> Ray<2> {
+        Ray::new(origin.into(), Unit::new_normalize(direction.into()))
+    }
+
+    /// The segment x = 2, y in [-3 ; 3], with a hole in [-1 ; 1]
+    fn iris() -> Aperture<2> {
+        Aperture::from_json(&json!({
+            "center": [2., 0.],
+            "basis": [[0., 3.]],
+            "holes": [{ "center": [2., 0.], "radius": 1. }],
+        }))
+        .expect("json error")
+    }
+
+    #[test]
+    fn test_holes() {
+        let iris = iris();
+
+        let hits = |origin: [Float; 2]| {
+            let mut intersections = vec![];
+            iris.append_intersecting_points(&ray(origin, [1., 0.]), List::from(&mut intersections));
+            intersections
+        };
+
+        assert!(hits([0., 0.5]).is_empty());
+        let [tangent] = hits([0., 2.])[..] else {
+            panic!("the ray must hit the aperture");
+        };
+        assert_eq!(tangent.surface, Surface::Absorbing);
+        assert!(hits([0., 4.]).is_empty());
+
+        assert!(Aperture::<2>::from_json(&json!({
+            "center": [0., 0.],
+            "basis": [[0., 1.]],
+            "holes": [{ "center": [0., 0.], "radius": 0. }],
+        }))
+        .is_err());
+
+        let json = iris.to_json();
+        assert_eq!(Aperture::from_json(&json).ok().as_ref(), Some(&iris));
+    }
+
+    #[test]
+    fn test_beam_block() {
+        // the ray going through the hole bounces off the wall behind the aperture,
+        // and is then stopped by it, the other one is stopped straight away
+        let wall = plane::PlaneMirror::<2>::try_new([[5., 0.].into(), [0., 5.].into()]).unwrap();
+
+        let simulation = crate::Simulation {
+            rays: vec![ray([0., 0.], [1., 0.2]), ray([0., 2.], [1., 0.])],
+            mirror: vec![
+                Box::new(iris()) as Box<dyn Mirror<2>>,
+                Box::new(wall) as Box<dyn Mirror<2>>,
+            ],
+            seed: 0,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let paths = simulation.get_ray_paths(100);
+
+        assert_eq!(paths[0].all_points_raw().len(), 3);
+        assert!((paths[0].all_points_raw()[2] - SVector::from([2., 1.6])).norm() < 1e-12);
+        assert_eq!(paths[1].all_points_raw().len(), 2);
+
+        for path in &paths {
+            assert_eq!(path.termination(), crate::Termination::Absorbed);
+            assert!(path.divergence_direction().is_none());
+            assert_eq!(path.final_ray().unwrap().power, 0.);
+        }
+    }
+}
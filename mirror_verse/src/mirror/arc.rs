@@ -0,0 +1,269 @@
+use core::f64::consts::TAU;
+
+use super::*;
+
+/// The number of chords the default implementation of
+/// [`Mirror::append_arc_intersections`] approximates a whole circle with
+pub const CHORDS_PER_TURN: usize = 512;
+
+/// The number of points sampled on a whole circle by [`Arc::sample`]
+pub const SAMPLES_PER_TURN: usize = 128;
+
+/// A circle, travelled by a ray, used when rays don't move along straight
+/// lines between two surfaces (see [`crate::Propagation`]).
+///
+/// The ray starts at angle `0`, and the point it reaches at angle `θ`
+/// is `center + cos(θ) * u + sin(θ) * w`, (see [`Self::at`]) where `u`
+/// and `w` are orthogonal vectors, whose length is the radius of the circle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arc<const D: usize> {
+    center: SVector<Float, D>,
+    /// The vector from the center to the starting point
+    u: SVector<Float, D>,
+    /// The direction of the ray at the starting point, scaled to the length of `u`
+    w: SVector<Float, D>,
+}
+
+impl<const D: usize> Arc<D> {
+    /// Returns `None` if `u` and `w` aren't orthogonal vectors of the same, non-zero, length.
+    pub fn new(
+        center: SVector<Float, D>,
+        u: SVector<Float, D>,
+        w: SVector<Float, D>,
+    ) -> Option<Self> {
+        let r2 = u.norm_squared();
+
+        (r2 > Float::EPSILON
+            && (w.norm_squared() - r2).abs() <= r2 * 1e-9
+            && u.dot(&w).abs() <= r2 * 1e-9)
+            .then_some(Self { center, u, w })
+    }
+
+    /// The circle followed by a charged particle, starting at `ray.origin` in direction
+    /// `ray.direction`, in a uniform magnetic field orthogonal to the plane.
+    ///
+    /// It's radius is `larmor_radius.abs()`, and it is travelled counterclockwise
+    /// if `larmor_radius` is positive, clockwise otherwise.
+    ///
+    /// # Panics
+    ///
+    /// If `D != 2`, or `larmor_radius` is zero.
+    pub fn larmor(ray: &Ray<D>, larmor_radius: Float) -> Self {
+        assert_eq!(D, 2, "larmor orbits are only supported in 2D");
+        assert!(larmor_radius != 0.0, "the larmor radius must be non-zero");
+
+        // the direction, rotated by a quarter turn counterclockwise
+        let mut left = SVector::zeros();
+        left[0] = -ray.direction[1];
+        left[1] = ray.direction[0];
+
+        Self {
+            center: ray.origin + left * larmor_radius,
+            u: -left * larmor_radius,
+            w: ray.direction.as_ref() * larmor_radius.abs(),
+        }
+    }
+
+    pub fn center(&self) -> &SVector<Float, D> {
+        &self.center
+    }
+
+    pub fn radius(&self) -> Float {
+        self.u.norm()
+    }
+
+    /// Returns the point of the circle at angle `angle` from the starting point
+    pub fn at(&self, angle: Float) -> SVector<Float, D> {
+        let (sin, cos) = angle.sin_cos();
+        self.center + self.u * cos + self.w * sin
+    }
+
+    /// Returns the rotation mapping the direction of the ray at the
+    /// starting point to it's direction at angle `angle`
+    pub fn rotation(&self, angle: Float) -> SMatrix<Float, D, D> {
+        let (sin, cos) = angle.sin_cos();
+        let r2 = self.u.norm_squared();
+        let (u, w) = (self.u / r2.sqrt(), self.w / r2.sqrt());
+
+        SMatrix::identity()
+            + (u * u.transpose() + w * w.transpose()) * (cos - 1.0)
+            + (w * u.transpose() - u * w.transpose()) * sin
+    }
+
+    /// Returns the direction of the ray when it reaches angle `angle`
+    pub fn direction_at(&self, angle: Float) -> Unit<SVector<Float, D>> {
+        let (sin, cos) = angle.sin_cos();
+        Unit::new_normalize(self.w * cos - self.u * sin)
+    }
+
+    /// Maps this circle with a similarity, given by the image of points
+    /// (`point`) and the (linear) image of vectors (`vector`)
+    pub fn mapped(
+        &self,
+        point: impl Fn(&SVector<Float, D>) -> SVector<Float, D>,
+        vector: impl Fn(&SVector<Float, D>) -> SVector<Float, D>,
+    ) -> Self {
+        Self {
+            center: point(&self.center),
+            u: vector(&self.u),
+            w: vector(&self.w),
+        }
+    }
+
+    /// Returns the points of the circle, sampled between angles `0` and `angle`,
+    /// both excluded, at regular intervals, [`SAMPLES_PER_TURN`] per whole turn.
+    pub fn sample(&self, angle: Float) -> impl Iterator<Item = SVector<Float, D>> + '_ {
+        let n = (angle / TAU * SAMPLES_PER_TURN as Float).ceil().max(1.0) as usize;
+        (1..n).map(move |k| self.at(angle * k as Float / n as Float))
+    }
+
+    /// Returns the angles, in `[0 ; 2π)`, at which `a * cos(θ) + b * sin(θ) = c`,
+    /// ignoring the tangential solutions
+    fn solve(a: Float, b: Float, c: Float) -> impl Iterator<Item = Float> {
+        let rho = a.hypot(b);
+        let ratio = c / rho;
+
+        let solutions = (rho > Float::EPSILON && ratio.abs() < 1.0).then(|| {
+            let phi = b.atan2(a);
+            let delta = ratio.acos();
+            [phi - delta, phi + delta].map(|angle| angle.rem_euclid(TAU))
+        });
+
+        solutions.into_iter().flatten()
+    }
+
+    /// Returns the angles, in `[0 ; 2π)`, at which the circle crosses
+    /// the hyperplane going through `p`, orthogonal to `normal`
+    pub fn hyperplane_intersections(
+        &self,
+        p: &SVector<Float, D>,
+        normal: &SVector<Float, D>,
+    ) -> impl Iterator<Item = Float> {
+        // normal . (center + cos(θ) * u + sin(θ) * w - p) = 0
+        Self::solve(
+            normal.dot(&self.u),
+            normal.dot(&self.w),
+            normal.dot(&(p - self.center)),
+        )
+    }
+
+    /// Returns the angles, in `[0 ; 2π)`, at which the circle crosses
+    /// the sphere of center `center` and radius `radius`
+    pub fn sphere_intersections(
+        &self,
+        center: &SVector<Float, D>,
+        radius: Float,
+    ) -> impl Iterator<Item = Float> {
+        // since u and w are orthogonal and have the same length, r:
+        // ||v + cos(θ) * u + sin(θ) * w||^2 = ||v||^2 + r^2 + 2 * cos(θ) * v.u + 2 * sin(θ) * v.w
+        let v = self.center - center;
+
+        Self::solve(
+            2.0 * v.dot(&self.u),
+            2.0 * v.dot(&self.w),
+            radius * radius - v.norm_squared() - self.u.norm_squared(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f64::consts::FRAC_PI_2;
+
+    fn ray(origin: [Float; 2], direction: [Float; 2]) -> Ray<2> {
+        Ray::new(origin.into(), Unit::new_normalize(direction.into()))
+    }
+
+    #[test]
+    fn test_larmor() {
+        let ray = ray([0., 0.], [1., 0.]);
+
+        let counterclockwise = Arc::larmor(&ray, 2.);
+        assert_eq!(counterclockwise.center(), &SVector::from([0., 2.]));
+        assert!((counterclockwise.at(FRAC_PI_2) - SVector::from([2., 2.])).norm() < 1e-12);
+        assert!((counterclockwise.direction_at(FRAC_PI_2).y - 1.).abs() < 1e-12);
+
+        let clockwise = Arc::larmor(&ray, -2.);
+        assert_eq!(clockwise.center(), &SVector::from([0., -2.]));
+        assert!((clockwise.at(FRAC_PI_2) - SVector::from([2., -2.])).norm() < 1e-12);
+
+        for angle in [0.3, 2., 5.] {
+            let rotated = clockwise.rotation(angle) * ray.direction.as_ref();
+            assert!((rotated - clockwise.direction_at(angle).as_ref()).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_arc_intersections() {
+        let arc = Arc::larmor(&ray([0., 0.], [1., 0.]), 1.);
+
+        // the circle of center (0, 1), and radius 1, crosses the line y = 1 at (1, 1) and (-1, 1)
+        let mut angles =
+            Vec::from_iter(arc.hyperplane_intersections(&[5., 1.].into(), &[0., 3.].into()));
+        angles.sort_by(Float::total_cmp);
+        assert_eq!(angles.len(), 2);
+        assert!((angles[0] - FRAC_PI_2).abs() < 1e-12);
+        assert!((angles[1] - 3. * FRAC_PI_2).abs() < 1e-12);
+
+        // it only touches the line y = 2
+        assert_eq!(
+            arc.hyperplane_intersections(&[0., 2.].into(), &[0., 1.].into())
+                .count(),
+            0
+        );
+
+        for angle in arc.sphere_intersections(&[1., 2.].into(), 1.) {
+            let p = arc.at(angle);
+            assert!(((p - SVector::from([1., 2.])).norm() - 1.).abs() < 1e-12);
+            assert!(((p - arc.center()).norm() - 1.).abs() < 1e-12);
+        }
+        assert_eq!(arc.sphere_intersections(&[1., 2.].into(), 1.).count(), 2);
+        assert_eq!(arc.sphere_intersections(&[0., 1.].into(), 3.).count(), 0);
+    }
+
+    #[test]
+    fn test_magnetic_billiard() {
+        // a particle, circling clockwise, with a radius of 1, inside a circular table of radius 3
+        let table = sphere::EuclideanSphereMirror::<2>::new([0., 0.].into(), 3.).unwrap();
+        let wall = plane::PlaneMirror::<2>::try_new([[0., 2.].into(), [3., 0.].into()]).unwrap();
+
+        let simulation = crate::Simulation {
+            rays: vec![ray([0., 0.], [0., 1.])],
+            mirror: vec![
+                Box::new(table) as Box<dyn Mirror<2>>,
+                Box::new(wall) as Box<dyn Mirror<2>>,
+            ],
+            seed: 0,
+            propagation: crate::Propagation::Magnetic { larmor_radius: -1. },
+            periodic: None,
+            world: None,
+        };
+
+        let path = simulation.get_ray_paths(20).remove(0);
+
+        // it's circle, of center (1, 0), meets neither the wall nor the
+        // table, so the particle goes around it forever
+        let (non_loop, looping) = path.all_points();
+        assert!(non_loop.is_empty());
+        assert!(looping.len() > 10);
+        assert!(looping
+            .iter()
+            .all(|p| ((p - SVector::from([1., 0.])).norm() - 1.).abs() < 1e-9));
+
+        // with a larger radius, it bounces off the wall, and the table
+        let simulation = crate::Simulation {
+            propagation: crate::Propagation::Magnetic { larmor_radius: -4. },
+            ..simulation
+        };
+
+        let path = simulation.get_ray_paths(20).remove(0);
+        let hits = path.vertices.iter().map(|&i| path.all_points_raw()[i]);
+        for (k, hit) in hits.enumerate().skip(1) {
+            let on_table = (hit.norm() - 3.).abs() < 1e-9;
+            let on_wall = (hit.y - 2.).abs() < 1e-9 && hit.x.abs() < 3.;
+            assert!(on_table || on_wall, "hit {k} at {hit} isn't on a mirror");
+        }
+        assert!(path.vertices.len() > 5);
+    }
+}
@@ -0,0 +1,366 @@
+use super::*;
+
+/// An open, truncated cone-shaped (frustum) mirror, whose radius varies linearly
+/// along a line segment. One of the radii can be zero, making it a proper cone.
+///
+/// In 2D, this is a pair of segments, symmetric with respect to the line segment.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConicalMirror<const D: usize> {
+    start: SVector<Float, D>,
+    dist: SVector<Float, D>,
+    inv_norm_dist_squared: Float,
+    start_radius: Float,
+    end_radius: Float,
+}
+
+impl<const D: usize> ConicalMirror<D> {
+    /// Create a new cone from a line segment and the radii at each of it's ends.
+    ///
+    /// Returns `None` if the segment is too short, if a radius is negative,
+    /// or if both radii are (close to) zero.
+    pub fn new(
+        line_segment: [SVector<Float, D>; 2],
+        start_radius: Float,
+        end_radius: Float,
+    ) -> Option<Self> {
+        const E: Float = Float::EPSILON * 8.0;
+
+        let [start, end] = line_segment;
+        let dist = end - start;
+        let dist_sq = dist.norm_squared();
+
+        (dist_sq.sqrt() > E
+            && start_radius >= 0.0
+            && end_radius >= 0.0
+            && start_radius.max(end_radius) > E)
+            .then(|| Self {
+                start,
+                dist,
+                inv_norm_dist_squared: dist_sq.recip(),
+                start_radius,
+                end_radius,
+            })
+    }
+
+    pub fn segment_length(&self) -> SVector<Float, D> {
+        self.dist
+    }
+
+    pub fn line_segment(&self) -> [SVector<Float, D>; 2] {
+        [self.start, self.start + self.dist]
+    }
+
+    pub fn start_radius(&self) -> Float {
+        self.start_radius
+    }
+
+    pub fn end_radius(&self) -> Float {
+        self.end_radius
+    }
+}
+
+impl<const D: usize> Mirror<D> for ConicalMirror<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        // same idea as the cylinder (see cylinder.rs), except the radius
+        // is now an affine function of the coordinate along the line segment:
+        // ||perp(V - S)||^2 = (r_0 + (r_1 - r_0) * line_coord(V - S))^2
+        let line_coord = |v: SVector<Float, D>| self.dist.dot(&v) * self.inv_norm_dist_squared;
+
+        let slope = self.end_radius - self.start_radius;
+
+        let m = ray.origin - self.start;
+        let d = ray.direction.into_inner();
+
+        let (m_coord, d_coord) = (line_coord(m), line_coord(d));
+
+        // perp(V - S) = perp_m + t * perp_d
+        let perp_m = m - m_coord * self.dist;
+        let perp_d = d - d_coord * self.dist;
+
+        // radius = r_m + t * r_d
+        let r_m = self.start_radius + slope * m_coord;
+        let r_d = slope * d_coord;
+
+        let a = perp_d.norm_squared() - r_d * r_d;
+        let b = perp_m.dot(&perp_d) - r_m * r_d;
+        let c = perp_m.norm_squared() - r_m * r_m;
+
+        let roots = if a.abs() > Float::EPSILON {
+            let delta = b * b - a * c;
+            if delta > Float::EPSILON {
+                let root_delta = delta.sqrt();
+                let neg_b = -b;
+                [(neg_b - root_delta) / a, (neg_b + root_delta) / a]
+            } else {
+                return;
+            }
+        } else if b.abs() > Float::EPSILON {
+            // the ray is parallel to the cone's side, only one intersection
+            [-c / (2.0 * b), Float::NAN]
+        } else {
+            return;
+        };
+
+        let axis_slope = slope * self.inv_norm_dist_squared.sqrt();
+        let axis = self.dist * self.inv_norm_dist_squared.sqrt();
+
+        for t in roots {
+            let coord = m_coord + t * d_coord;
+
+            // (also filters out NaNs)
+            if (0.0..=1.0).contains(&coord) {
+                let radial = perp_m + t * perp_d;
+
+                // on the other nappe of the cone
+                if r_m + t * r_d < 0.0 {
+                    continue;
+                }
+
+                // the radius grows by `axis_slope` per unit of length along the axis,
+                // the normal is tilted back accordingly
+                if let Some(normal) = Unit::try_new(radial, Float::EPSILON)
+                    .and_then(|e| Unit::try_new(e.into_inner() - axis_slope * axis, Float::EPSILON))
+                {
+                    list.push(TangentPlane {
+                        intersection: Intersection::Distance(t),
+                        direction: TangentSpace::Normal(normal),
+                        surface: Surface::Reflective,
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl<const D: usize> JsonType for ConicalMirror<D> {
+    fn json_type() -> String {
+        "cone".into()
+    }
+}
+
+impl<const D: usize> JsonDes for ConicalMirror<D> {
+    /// Deserialize a new conical mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "start": [1.0, 2.0, 3.0, ...], // (an array of D floats)
+    ///     "end": [4.0, 5.0, 6.0, ...], // (an array of D floats)
+    ///     "start_radius": 2.0, // (must be positive)
+    ///     "end_radius": 0.5, // (must be positive, can be zero, but not both)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let start = json
+            .get("start")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse start")?;
+
+        let end = json
+            .get("end")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse end")?;
+
+        let start_radius = json
+            .get("start_radius")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse start_radius")? as Float;
+
+        let end_radius = json
+            .get("end_radius")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse end_radius")? as Float;
+
+        Self::new([start, end], start_radius, end_radius).ok_or(
+            "radii must be positive and not both zero, and start and end vectors must not be too close".into(),
+        )
+    }
+}
+
+impl<const D: usize> JsonSer for ConicalMirror<D> {
+    /// Serialize a conical mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let [start, end] = self.line_segment();
+
+        serde_json::json!({
+            "start": start.as_slice(),
+            "end": end.as_slice(),
+            "start_radius": self.start_radius(),
+            "end_radius": self.end_radius(),
+        })
+    }
+}
+
+struct ConeRenderData<const D: usize> {
+    vertices: gl::VertexBuffer<render::Vertex<D>>,
+}
+
+impl<const D: usize> render::RenderData for ConeRenderData<D> {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: match D {
+                0 => unreachable!("dimension must not be zero"),
+                1 | 2 => gl::index::PrimitiveType::LinesList,
+                _ => gl::index::PrimitiveType::TriangleStrip,
+            },
+        }
+    }
+}
+
+impl render::OpenGLRenderable for ConicalMirror<2> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let [start, end] = self.line_segment();
+        let normal = SVector::from([-self.dist.y, self.dist.x]).normalize();
+
+        let vertices = [
+            start + normal * self.start_radius,
+            end + normal * self.end_radius,
+            start - normal * self.start_radius,
+            end - normal * self.end_radius,
+        ]
+        .map(render::Vertex::from);
+
+        list.push(Box::new(ConeRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl render::OpenGLRenderable for ConicalMirror<3> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        const NUM_POINTS: usize = 360;
+
+        use core::f64::consts::TAU;
+
+        let [start, end] = self.line_segment();
+        let [_, u, v] = util::basis_from_vector(&Unit::new_normalize(self.dist));
+
+        let vertices: Vec<_> = (0..=NUM_POINTS)
+            .flat_map(|i| {
+                let (sin, cos) = (i as Float / NUM_POINTS as Float * TAU).sin_cos();
+                let radial = cos * u + sin * v;
+                [
+                    start + radial * self.start_radius,
+                    end + radial * self.end_radius,
+                ]
+            })
+            .map(render::Vertex3D::from)
+            .collect();
+
+        let vertices = gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap();
+
+        list.push(Box::new(ConeRenderData { vertices }))
+    }
+}
+
+impl<const D: usize> Random for ConicalMirror<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self
+    where
+        Self: Sized,
+    {
+        loop {
+            if let Some(mirror) = Self::new(
+                [util::rand_vect(rng, 10.0), util::rand_vect(rng, 10.0)],
+                rng.gen::<Float>() * 4.0,
+                rng.gen::<Float>() * 4.0,
+            ) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_frustum() {
+        let mirror = ConicalMirror::<3>::from_json(&json!({
+            "start": [0., 0., 0.],
+            "end": [0., 0., 2.],
+            "start_radius": 2.,
+            "end_radius": 1.,
+        }))
+        .expect("json error");
+
+        // at z = 1, the radius is 1.5
+        let ray = Ray::new(
+            [-5., 0., 1.].into(),
+            Unit::new_normalize([1., 0., 0.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let mut hits = Vec::from_iter(
+            intersections
+                .iter()
+                .map(|tangent| (tangent.try_ray_intersection(&ray).unwrap(), tangent)),
+        );
+        hits.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+
+        let [(t1, tangent), (t2, _)] = hits.as_slice() else {
+            panic!("there must be two intersections");
+        };
+
+        assert!((t1 - 3.5).abs() < Float::EPSILON * 64.0);
+        assert!((t2 - 6.5).abs() < Float::EPSILON * 64.0);
+
+        // the side has a slope of 1/2, the normal is (-2, 0, 1) / sqrt(5)
+        let TangentSpace::Normal(normal) = tangent.direction else {
+            panic!("expected a normal");
+        };
+        let expected = SVector::from([-2., 0., 1.]).normalize();
+        assert!((normal.into_inner() - expected).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_outside_segment() {
+        let mirror = ConicalMirror::<2>::new([[0., 0.].into(), [2., 0.].into()], 0., 1.).unwrap();
+
+        // misses the cone, but would hit the other nappe, or the cone's extension
+        let mut intersections = vec![];
+        for origin in [[-1., -5.], [3., -5.]] {
+            let ray = Ray::new(origin.into(), Unit::new_normalize([0., 1.].into()));
+            mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+        }
+        assert!(intersections.is_empty());
+
+        let ray = Ray::new([1., -5.].into(), Unit::new_normalize([0., 1.].into()));
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+        assert_eq!(intersections.len(), 2);
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror =
+            ConicalMirror::<3>::new([[1., 2., 3.].into(), [4., 5., 6.].into()], 0.5, 2.).unwrap();
+
+        let mirror2 = ConicalMirror::<3>::from_json(&mirror.to_json()).expect("json error");
+
+        assert_eq!(mirror.line_segment(), mirror2.line_segment());
+        assert_eq!(mirror.start_radius(), mirror2.start_radius());
+        assert_eq!(mirror.end_radius(), mirror2.end_radius());
+    }
+}
@@ -0,0 +1,542 @@
+use super::*;
+
+/// A mirror that is the boundary of a closed region of space (a "solid"),
+/// that can tell whether a point is inside of it.
+///
+/// Solids can be combined with [`Csg`].
+pub trait Solid<const D: usize>: Mirror<D> {
+    /// Whether `p` is inside (or on the boundary of) this solid
+    fn contains(&self, p: &SVector<Float, D>) -> bool;
+}
+
+impl<const D: usize, T: Deref> Solid<D> for T
+where
+    T::Target: Solid<D>,
+{
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        self.deref().contains(p)
+    }
+}
+
+/// All points on one side of a hyperplane: those `p` such that `(p - point) . normal <= 0`
+///
+/// As a mirror, it is the (infinite) hyperplane itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HalfSpace<const D: usize> {
+    point: SVector<Float, D>,
+    normal: Unit<SVector<Float, D>>,
+}
+
+/// Radius of the disk drawn to represent a half-space's boundary
+const HALF_SPACE_RENDER_RADIUS: Float = 50.0;
+
+impl<const D: usize> HalfSpace<D> {
+    /// Create a new half-space bounded by the hyperplane going through `point`
+    /// and orthogonal to `normal`, which points outside of it.
+    ///
+    /// Returns `None` if `normal` is too close to zero.
+    pub fn new(point: SVector<Float, D>, normal: SVector<Float, D>) -> Option<Self> {
+        Unit::try_new(normal, Float::EPSILON * 8.0).map(|normal| Self { point, normal })
+    }
+
+    pub fn point(&self) -> &SVector<Float, D> {
+        &self.point
+    }
+
+    pub fn normal(&self) -> &Unit<SVector<Float, D>> {
+        &self.normal
+    }
+}
+
+impl<const D: usize> Mirror<D> for HalfSpace<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let direction = TangentSpace::Normal(self.normal);
+
+        if let Some(t) = direction.try_ray_intersection(&self.point, ray) {
+            list.push(TangentPlane {
+                intersection: Intersection::Distance(t),
+                direction,
+                surface: Surface::Reflective,
+            });
+        }
+    }
+}
+
+impl<const D: usize> Solid<D> for HalfSpace<D> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        (p - self.point).dot(&self.normal) <= 0.0
+    }
+}
+
+impl<const D: usize> JsonType for HalfSpace<D> {
+    fn json_type() -> String {
+        "half_space".into()
+    }
+}
+
+impl<const D: usize> JsonDes for HalfSpace<D> {
+    /// Deserialize a new half-space from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "point": [1., 2., 3., ...], // (an array of D floats)
+    ///     "normal": [0., 0., 1., ...], // (an array of D floats, pointing outwards, must not be the zero vector)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let point = json
+            .get("point")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse point")?;
+
+        let normal = json
+            .get("normal")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse normal")?;
+
+        Self::new(point, normal).ok_or("normal must not be too close to zero".into())
+    }
+}
+
+impl<const D: usize> JsonSer for HalfSpace<D> {
+    /// Serialize a half-space into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "point": self.point.as_slice(),
+            "normal": self.normal.as_slice(),
+        })
+    }
+}
+
+// the boundary is infinite, draw a large disk instead
+impl<const D: usize> render::OpenGLRenderable for HalfSpace<D>
+where
+    disk::DiskMirror<D>: render::OpenGLRenderable,
+{
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        // SAFETY: the normal is a unit vector, and the radius isn't zero
+        disk::DiskMirror::new(
+            self.point,
+            self.normal.into_inner(),
+            HALF_SPACE_RENDER_RADIUS,
+        )
+        .unwrap()
+        .append_render_data(display, list)
+    }
+}
+
+impl<const D: usize> Random for HalfSpace<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        loop {
+            if let Some(half_space) =
+                Self::new(util::rand_vect(rng, 9.0), util::rand_vect(rng, 1.0))
+            {
+                break half_space;
+            }
+        }
+    }
+}
+
+/// A boolean operation on solids
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Points inside of any of the operands
+    Union,
+    /// Points inside of all of the operands
+    Intersection,
+    /// Points inside of the first operand, but not inside of any of the others
+    Difference,
+}
+
+impl Operation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Union => "union",
+            Self::Intersection => "intersection",
+            Self::Difference => "difference",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "union" => Some(Self::Union),
+            "intersection" => Some(Self::Intersection),
+            "difference" => Some(Self::Difference),
+            _ => None,
+        }
+    }
+}
+
+/// A solid made by combining other solids using a boolean [`Operation`]
+/// (constructive solid geometry).
+///
+/// As a mirror, only the parts of the operands' boundaries that are
+/// on the boundary of the combined solid reflect light.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Csg<T, const D: usize> {
+    operation: Operation,
+    operands: Vec<T>,
+    /// If set, the center and half side length of a (hyper)cube that contains
+    /// the solid, used to draw it's boundary, instead of all of the operands'
+    render_bounds: Option<(SVector<Float, D>, Float)>,
+}
+
+impl<T, const D: usize> Csg<T, D> {
+    /// Returns `None` if `operands` is empty
+    pub fn new(operation: Operation, operands: Vec<T>) -> Option<Self> {
+        (!operands.is_empty()).then_some(Self {
+            operation,
+            operands,
+            render_bounds: None,
+        })
+    }
+
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    pub fn operands(&self) -> &[T] {
+        &self.operands
+    }
+
+    pub fn render_bounds(&self) -> Option<&(SVector<Float, D>, Float)> {
+        self.render_bounds.as_ref()
+    }
+
+    /// Returns `false`, and does nothing, if the half side length isn't positive
+    pub fn set_render_bounds(&mut self, bounds: Option<(SVector<Float, D>, Float)>) -> bool {
+        let ok = bounds.is_none_or(|(_, half_extent)| half_extent > 0.0);
+
+        if ok {
+            self.render_bounds = bounds;
+        }
+
+        ok
+    }
+}
+
+impl<const D: usize, T: Solid<D>> Csg<T, D> {
+    /// Whether a point `p` on the boundary of the operand at index `i` is on the boundary of `self`
+    fn is_on_boundary(&self, i: usize, p: &SVector<Float, D>) -> bool {
+        let mut others = self
+            .operands
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(j, operand)| (j, operand.contains(p)));
+
+        match self.operation {
+            Operation::Union => others.all(|(_, inside)| !inside),
+            Operation::Intersection => others.all(|(_, inside)| inside),
+            // p must be inside the first operand, and outside of the others
+            Operation::Difference => others.all(|(j, inside)| inside == (j == 0)),
+        }
+    }
+}
+
+impl<const D: usize, T: Solid<D>> Mirror<D> for Csg<T, D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let mut hits = vec![];
+
+        for (i, operand) in self.operands.iter().enumerate() {
+            hits.clear();
+            operand.append_intersecting_points(ray, List::from(&mut hits));
+
+            list.extend(hits.iter().copied().filter(|tangent| {
+                tangent
+                    .try_ray_intersection(ray)
+                    .is_some_and(|t| self.is_on_boundary(i, &ray.at(t)))
+            }));
+        }
+    }
+}
+
+impl<const D: usize, T: Solid<D>> Solid<D> for Csg<T, D> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        let mut operands = self.operands.iter();
+
+        match self.operation {
+            Operation::Union => operands.any(|o| o.contains(p)),
+            Operation::Intersection => operands.all(|o| o.contains(p)),
+            Operation::Difference => {
+                // SAFETY: there is at least one operand
+                operands.next().unwrap().contains(p) && operands.all(|o| !o.contains(p))
+            }
+        }
+    }
+}
+
+impl<T, const D: usize> JsonType for Csg<T, D> {
+    fn json_type() -> String {
+        "csg".into()
+    }
+}
+
+impl<T: JsonDes, const D: usize> JsonDes for Csg<T, D> {
+    /// Deserialize a new CSG solid from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "operation": "difference", // ("union", "intersection" or "difference")
+    ///     "operands": [
+    ///         // <the first operand's layout>,
+    ///         // <the second operand's layout>,
+    ///         // ... (at least one)
+    ///     ],
+    ///     "render_bounds": { // (optional)
+    ///         "center": [1., 2., 3., ...], // (an array of D floats)
+    ///         "half_extent": 4., // (must be positive)
+    ///     },
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let operation = json
+            .get("operation")
+            .and_then(serde_json::Value::as_str)
+            .and_then(Operation::from_name)
+            .ok_or("Failed to parse operation")?;
+
+        let operands = util::map_json_array(
+            json.get("operands").ok_or("Missing operands")?,
+            T::from_json,
+        )?;
+
+        let mut csg = Self::new(operation, operands).ok_or("there must be at least one operand")?;
+
+        if let Some(bounds) = json.get("render_bounds") {
+            let center = bounds
+                .get("center")
+                .and_then(serde_json::Value::as_array)
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector)
+                .ok_or("Failed to parse render bounds center")?;
+
+            let half_extent = bounds
+                .get("half_extent")
+                .and_then(serde_json::Value::as_f64)
+                .ok_or("Failed to parse render bounds half_extent")?
+                as Float;
+
+            if !csg.set_render_bounds(Some((center, half_extent))) {
+                return Err("render bounds half_extent must be positive".into());
+            }
+        }
+
+        Ok(csg)
+    }
+}
+
+impl<T: JsonSer, const D: usize> JsonSer for Csg<T, D> {
+    /// Serialize a CSG solid into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = serde_json::json!({
+            "operation": self.operation.name(),
+            "operands": Vec::from_iter(self.operands.iter().map(T::to_json)),
+        });
+
+        if let Some((center, half_extent)) = &self.render_bounds {
+            json["render_bounds"] = serde_json::json!({
+                "center": center.as_slice(),
+                "half_extent": half_extent,
+            });
+        }
+
+        json
+    }
+}
+
+impl<T: Solid<D>, const D: usize> Csg<T, D> {
+    /// `-1.0` inside of the solid, `1.0` outside, the boundary is drawn where it changes sign
+    fn inside_indicator(&self, p: &SVector<Float, D>) -> Float {
+        if self.contains(p) {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+}
+
+impl<T: Solid<2> + render::OpenGLRenderable> render::OpenGLRenderable for Csg<T, 2> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let Some((center, half_extent)) = &self.render_bounds else {
+            return self.operands.append_render_data(display, list);
+        };
+
+        // the edges crossing the boundary are bisected with the inside test,
+        // so the contour is on the boundary, up to the grid's resolution
+        let vertices = Vec::from_iter(
+            implicit::contour_2d(|p| self.inside_indicator(p), center, *half_extent)
+                .into_iter()
+                .map(render::Vertex::from),
+        );
+
+        list.push(Box::new(implicit::ContourRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl<T: Solid<3> + render::OpenGLRenderable> render::OpenGLRenderable for Csg<T, 3> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let Some((center, half_extent)) = &self.render_bounds else {
+            return self.operands.append_render_data(display, list);
+        };
+
+        let vertices = Vec::from_iter(
+            implicit::contour_3d(|p| self.inside_indicator(p), center, *half_extent)
+                .into_iter()
+                .map(render::Vertex::from),
+        );
+
+        list.push(Box::new(implicit::ContourRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl<T: Random, const D: usize> Random for Csg<T, D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        let operation = [
+            Operation::Union,
+            Operation::Intersection,
+            Operation::Difference,
+        ][rng.gen_range(0..3)];
+
+        // SAFETY: there are two operands
+        Self::new(operation, vec![T::random(rng), T::random(rng)]).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn distances<const D: usize>(mirror: &impl Mirror<D>, ray: &Ray<D>) -> Vec<Float> {
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(ray, List::from(&mut intersections));
+
+        let mut distances = Vec::from_iter(
+            intersections
+                .iter()
+                .map(|tangent| tangent.try_ray_intersection(ray).unwrap()),
+        );
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        distances
+    }
+
+    #[test]
+    fn test_dome() {
+        // the upper half of a sphere, closed by a flat floor
+        let dome = Csg::new(
+            Operation::Difference,
+            vec![
+                Box::new(sphere::EuclideanSphereMirror::<3>::new(SVector::zeros(), 2.).unwrap())
+                    as Box<dyn Solid<3>>,
+                Box::new(HalfSpace::new(SVector::zeros(), [0., 0., 1.].into()).unwrap()),
+            ],
+        )
+        .unwrap();
+
+        let ray = Ray::new(
+            [0., 0., -5.].into(),
+            Unit::new_normalize([0., 0., 1.].into()),
+        );
+
+        // the bottom of the sphere, and the half-space's plane outside of it, are ignored
+        let d = distances(&dome, &ray);
+        assert_eq!(d.len(), 2);
+        assert!((d[0] - 5.).abs() < 1e-12);
+        assert!((d[1] - 7.).abs() < 1e-12);
+
+        assert!(dome.contains(&[0., 0.5, 1.].into()));
+        assert!(!dome.contains(&[0., 0.5, -1.].into()));
+    }
+
+    #[test]
+    fn test_intersection() {
+        // a cylinder along the x axis, cut by the slab -1 <= x <= 1
+        let solid = Csg::<Box<dyn Solid<2>>, 2>::new(
+            Operation::Intersection,
+            vec![
+                Box::new(
+                    cylinder::CylindricalMirror::<2>::new_capped(
+                        [[-5., 0.].into(), [5., 0.].into()],
+                        1.,
+                    )
+                    .unwrap(),
+                ),
+                Box::new(HalfSpace::new([1., 0.].into(), [1., 0.].into()).unwrap()),
+                Box::new(HalfSpace::new([-1., 0.].into(), [-1., 0.].into()).unwrap()),
+            ],
+        )
+        .unwrap();
+
+        let ray = Ray::new([-10., 0.5].into(), Unit::new_normalize([1., 0.].into()));
+
+        let d = distances(&solid, &ray);
+        assert_eq!(d.len(), 2);
+        assert!((d[0] - 9.).abs() < 1e-12);
+        assert!((d[1] - 11.).abs() < 1e-12);
+
+        // union of two overlapping disks, the inner arcs are hidden
+        let union = Csg::new(
+            Operation::Union,
+            vec![
+                sphere::EuclideanSphereMirror::<2>::new([-1., 0.].into(), 2.).unwrap(),
+                sphere::EuclideanSphereMirror::<2>::new([1., 0.].into(), 2.).unwrap(),
+            ],
+        )
+        .unwrap();
+
+        let ray = Ray::new([-10., 0.].into(), Unit::new_normalize([1., 0.].into()));
+
+        let d = distances(&union, &ray);
+        assert_eq!(d.len(), 2);
+        assert!((d[0] - 7.).abs() < 1e-12);
+        assert!((d[1] - 13.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_json() {
+        let csg = Csg::<HalfSpace<2>, 2>::from_json(&json!({
+            "operation": "intersection",
+            "operands": [
+                { "point": [0., 0.], "normal": [1., 0.] },
+                { "point": [0., 0.], "normal": [0., 1.] },
+            ],
+            "render_bounds": {
+                "center": [0., 0.],
+                "half_extent": 3.,
+            },
+        }))
+        .expect("json error");
+
+        assert!(csg.contains(&[-1., -1.].into()));
+        assert!(!csg.contains(&[1., -1.].into()));
+
+        let csg2 = Csg::<HalfSpace<2>, 2>::from_json(&csg.to_json()).expect("json error");
+
+        assert_eq!(csg, csg2);
+    }
+}
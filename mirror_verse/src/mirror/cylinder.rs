@@ -2,18 +2,25 @@ use self::render::OpenGLRenderable;
 
 use super::*;
 
-/// An open, cylinder-shaped mirror,
-pub struct CylindricalMirror {
-    start: SVector<Float, 3>,
-    dist: SVector<Float, 3>,
+/// A cylinder-shaped mirror: all points at a certain distance (radius) from a line segment,
+/// i. e. a `D-1`-dimensional ball swept along that segment.
+///
+/// It is open-ended by default, but can be closed with flat caps at both ends.
+///
+/// In 2D, this is a pair of parallel segments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CylindricalMirror<const D: usize> {
+    start: SVector<Float, D>,
+    dist: SVector<Float, D>,
     inv_norm_dist_squared: Float,
     radius: Float,
     radius_sq: Float,
+    capped: bool,
 }
 
-impl CylindricalMirror {
-    /// Create a new cylinder from a line segment and a radius
-    pub fn new(line_segment: [SVector<Float, 3>; 2], radius: Float) -> Option<Self> {
+impl<const D: usize> CylindricalMirror<D> {
+    /// Create a new (open) cylinder from a line segment and a radius
+    pub fn new(line_segment: [SVector<Float, D>; 2], radius: Float) -> Option<Self> {
         const E: Float = Float::EPSILON * 8.0;
 
         let [start, end] = line_segment;
@@ -27,24 +34,51 @@ impl CylindricalMirror {
             radius,
             radius_sq: radius * radius,
             inv_norm_dist_squared: dist_sq.recip(),
+            capped: false,
         })
     }
 
-    pub fn segment_length(&self) -> SVector<Float, 3> {
+    /// Create a new cylinder from a line segment and a radius, closed at both ends
+    pub fn new_capped(line_segment: [SVector<Float, D>; 2], radius: Float) -> Option<Self> {
+        Self::new(line_segment, radius).map(|mirror| Self {
+            capped: true,
+            ..mirror
+        })
+    }
+
+    pub fn segment_length(&self) -> SVector<Float, D> {
         self.dist
     }
 
-    pub fn line_segment(&self) -> [SVector<Float, 3>; 2] {
+    pub fn line_segment(&self) -> [SVector<Float, D>; 2] {
         [self.start, self.start + self.dist]
     }
 
     pub fn radius(&self) -> Float {
         self.radius
     }
+
+    /// Whether this cylinder has (flat, `D-1`-ball shaped) caps at both ends
+    pub fn is_capped(&self) -> bool {
+        self.capped
+    }
+
+    pub fn set_capped(&mut self, capped: bool) {
+        self.capped = capped;
+    }
+
+    /// The disks closing this cylinder at the start and end of it's line segment, if it is capped
+    pub fn caps(&self) -> Option<[disk::DiskMirror<D>; 2]> {
+        self.capped.then(|| {
+            // SAFETY: the segment's length and the radius have been checked to not be too small
+            self.line_segment()
+                .map(|center| disk::DiskMirror::new(center, self.dist, self.radius).unwrap())
+        })
+    }
 }
 
-impl Mirror<3> for CylindricalMirror {
-    fn append_intersecting_points(&self, ray: &Ray<3>, mut list: List<TangentPlane<3>>) {
+impl<const D: usize> Mirror<D> for CylindricalMirror<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
         let line_coord = |v| self.dist.dot(&v) * self.inv_norm_dist_squared;
         let p = |v| line_coord(v) * self.dist;
 
@@ -79,29 +113,45 @@ impl Mirror<3> for CylindricalMirror {
                     list.push(TangentPlane {
                         intersection: Intersection::Distance(t),
                         direction: TangentSpace::Normal(normal),
+                        surface: Surface::Reflective,
                     })
                 }
             }
         }
+
+        for cap in self.caps().into_iter().flatten() {
+            cap.append_intersecting_points(ray, list.reborrow());
+        }
     }
 }
 
-impl JsonType for CylindricalMirror {
+/// The solid cylinder, note that it is only closed if the cylinder is capped
+impl<const D: usize> csg::Solid<D> for CylindricalMirror<D> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        let v = p - self.start;
+        let coord = self.dist.dot(&v) * self.inv_norm_dist_squared;
+
+        (0.0..=1.0).contains(&coord) && (v - coord * self.dist).norm_squared() <= self.radius_sq
+    }
+}
+
+impl<const D: usize> JsonType for CylindricalMirror<D> {
     fn json_type() -> String {
         "cylinder".into()
     }
 }
 
-impl JsonDes for CylindricalMirror {
+impl<const D: usize> JsonDes for CylindricalMirror<D> {
     /// Deserialize a new cylindrical mirror from a JSON object.
     ///
     /// The JSON object must follow the following format:
     ///
     /// ```json
     /// {
-    ///     "start": [1.0, 2.0, 3.0],
-    ///     "end": [4.0, 5.0, 6.0],
+    ///     "start": [1.0, 2.0, 3.0, ...], // (an array of D floats)
+    ///     "end": [4.0, 5.0, 6.0, ...], // (an array of D floats)
     ///     "radius": 69.0,
+    ///     "capped": true, // (optional, defaults to false)
     /// }
     /// ```
     fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
@@ -124,12 +174,22 @@ impl JsonDes for CylindricalMirror {
             .and_then(serde_json::Value::as_f64)
             .ok_or("Failed to parse radius")? as Float;
 
-        Self::new([start, end], radius)
-            .ok_or("radius is too small or start and end vectors are too close".into())
+        let capped = json
+            .get("capped")
+            .map(|capped| capped.as_bool().ok_or("Failed to parse capped"))
+            .transpose()?
+            .unwrap_or(false);
+
+        let mut mirror = Self::new([start, end], radius)
+            .ok_or("radius is too small or start and end vectors are too close")?;
+
+        mirror.set_capped(capped);
+
+        Ok(mirror)
     }
 }
 
-impl JsonSer for CylindricalMirror {
+impl<const D: usize> JsonSer for CylindricalMirror<D> {
     /// Serialize a cylindrical mirror into a JSON object.
     ///
     /// The format of the returned object is explained in [`Self::from_json`]
@@ -141,27 +201,52 @@ impl JsonSer for CylindricalMirror {
             "start": start.as_slice(),
             "end": end.as_slice(),
             "radius": radius,
+            "capped": self.is_capped(),
         })
     }
 }
 
-struct CylinderRenderData {
-    vertices: gl::VertexBuffer<render::Vertex3D>,
+struct CylinderRenderData<const D: usize> {
+    vertices: gl::VertexBuffer<render::Vertex<D>>,
+    primitives: gl::index::PrimitiveType,
 }
 
-impl render::RenderData for CylinderRenderData {
+impl<const D: usize> render::RenderData for CylinderRenderData<D> {
     fn vertices(&self) -> gl::vertex::VerticesSource {
         (&self.vertices).into()
     }
 
     fn indices(&self) -> gl::index::IndicesSource {
         gl::index::IndicesSource::NoIndices {
-            primitives: gl::index::PrimitiveType::TriangleStrip,
+            primitives: self.primitives,
+        }
+    }
+}
+
+impl OpenGLRenderable for CylindricalMirror<2> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let [start, end] = self.line_segment();
+        let normal = SVector::from([-self.dist.y, self.dist.x]).normalize() * self.radius;
+
+        let vertices = [start + normal, end + normal, start - normal, end - normal]
+            .map(render::Vertex2D::from);
+
+        list.push(Box::new(CylinderRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+            primitives: gl::index::PrimitiveType::LinesList,
+        }));
+
+        for cap in self.caps().into_iter().flatten() {
+            cap.append_render_data(display, list.reborrow());
         }
     }
 }
 
-impl OpenGLRenderable for CylindricalMirror {
+impl OpenGLRenderable for CylindricalMirror<3> {
     fn append_render_data(
         &self,
         display: &gl::Display,
@@ -196,22 +281,131 @@ impl OpenGLRenderable for CylindricalMirror {
 
         let vertices = gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap();
 
-        list.push(Box::new(CylinderRenderData { vertices }))
+        list.push(Box::new(CylinderRenderData {
+            vertices,
+            primitives: gl::index::PrimitiveType::TriangleStrip,
+        }));
+
+        for cap in self.caps().into_iter().flatten() {
+            cap.append_render_data(display, list.reborrow());
+        }
     }
 }
 
-impl Random for CylindricalMirror {
+impl<const D: usize> Random for CylindricalMirror<D> {
     fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self
     where
         Self: Sized,
     {
         loop {
-            if let Some(mirror) = Self::new(
+            if let Some(mut mirror) = Self::new(
                 [util::rand_vect(rng, 10.0), util::rand_vect(rng, 10.0)],
                 rng.gen::<Float>() * 4.0,
             ) {
+                mirror.set_capped(rng.gen());
                 break mirror;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_caps() {
+        let mirror = CylindricalMirror::<3>::from_json(&json!({
+            "start": [0., 0., 0.],
+            "end": [0., 0., 2.],
+            "radius": 1.,
+            "capped": true,
+        }))
+        .expect("json error");
+
+        let mut ray = Ray::new(
+            [0.5, 0., -1.].into(),
+            Unit::new_normalize([0., 0., 1.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let mut hits = Vec::from_iter(
+            intersections
+                .iter()
+                .map(|tangent| (tangent.try_ray_intersection(&ray).unwrap(), tangent)),
+        );
+        hits.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+
+        let [(t1, tangent), (t2, _)] = hits.as_slice() else {
+            panic!("there must be two intersections");
+        };
+
+        assert!((t1 - 1.).abs() < Float::EPSILON * 4.0);
+        assert!((t2 - 3.).abs() < Float::EPSILON * 4.0);
+
+        ray.advance(*t1);
+        ray.reflect_dir(&tangent.direction);
+
+        assert!((ray.direction.into_inner() - SVector::from([0., 0., -1.])).norm() < 1e-12);
+
+        // the same cylinder, without caps, lets the ray through
+        let mut open = mirror;
+        open.set_capped(false);
+
+        intersections.clear();
+        open.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        assert!(intersections.is_empty());
+    }
+
+    #[test]
+    fn test_2d() {
+        let mirror = CylindricalMirror::<2>::new([[-1., 0.].into(), [1., 0.].into()], 1.).unwrap();
+
+        let ray = Ray::new([0.5, -3.].into(), Unit::new_normalize([0., 1.].into()));
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let mut distances = Vec::from_iter(
+            intersections
+                .iter()
+                .filter_map(|t| t.try_ray_intersection(&ray)),
+        );
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(distances.len(), 2);
+        assert!((distances[0] - 2.).abs() < Float::EPSILON * 16.0);
+        assert!((distances[1] - 4.).abs() < Float::EPSILON * 16.0);
+    }
+
+    #[test]
+    fn test_4d() {
+        let mirror = CylindricalMirror::<4>::new_capped(
+            [[0., 0., 0., -1.].into(), [0., 0., 0., 1.].into()],
+            2.,
+        )
+        .unwrap();
+
+        let ray = Ray::new(
+            [0., 0., 0., 0.].into(),
+            Unit::new_normalize([1., 1., 1., 0.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [tangent] = intersections
+            .iter()
+            .filter(|t| t.try_ray_intersection(&ray).unwrap() > 0.0)
+            .collect::<Vec<_>>()[..]
+        else {
+            panic!("there must be one intersection in front of the ray");
+        };
+
+        assert!((tangent.try_ray_intersection(&ray).unwrap() - 2.).abs() < Float::EPSILON * 16.0);
+    }
+}
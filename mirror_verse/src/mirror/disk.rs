@@ -0,0 +1,265 @@
+use super::*;
+
+/// A flat, round mirror: the intersection of a hyperplane with a ball centered in that hyperplane.
+///
+/// In 3D, this is a disk. In 2D, a line segment, and in general, a `D-1`-dimensional ball.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiskMirror<const D: usize> {
+    center: SVector<Float, D>,
+    normal: Unit<SVector<Float, D>>,
+    radius: Float,
+}
+
+impl<const D: usize> DiskMirror<D> {
+    /// Create a new disk mirror from it's center, a vector normal to it, and it's radius
+    ///
+    /// Returns `None` if `normal` or `radius` are too close to zero.
+    pub fn new(
+        center: SVector<Float, D>,
+        normal: SVector<Float, D>,
+        radius: Float,
+    ) -> Option<Self> {
+        const E: Float = Float::EPSILON * 8.0;
+
+        let normal = Unit::try_new(normal, E)?;
+
+        (radius.abs() > E).then_some(Self {
+            center,
+            normal,
+            radius: radius.abs(),
+        })
+    }
+
+    pub fn center(&self) -> &SVector<Float, D> {
+        &self.center
+    }
+
+    pub fn normal(&self) -> &Unit<SVector<Float, D>> {
+        &self.normal
+    }
+
+    pub fn radius(&self) -> Float {
+        self.radius
+    }
+}
+
+impl<const D: usize> Mirror<D> for DiskMirror<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let direction = TangentSpace::Normal(self.normal);
+
+        if let Some(t) = direction.try_ray_intersection(&self.center, ray) {
+            if (ray.at(t) - self.center).norm_squared() <= self.radius * self.radius {
+                list.push(TangentPlane {
+                    intersection: Intersection::Distance(t),
+                    direction,
+                    surface: Surface::Reflective,
+                });
+            }
+        }
+    }
+}
+
+impl<const D: usize> JsonType for DiskMirror<D> {
+    fn json_type() -> String {
+        "disk".into()
+    }
+}
+
+impl<const D: usize> JsonDes for DiskMirror<D> {
+    /// Deserialize a new disk mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "center": [1., 2., 3., ...], // (an array of D floats)
+    ///     "normal": [0., 0., 1., ...], // (an array of D floats, must not be the zero vector)
+    ///     "radius": 4., // (must be a float of magnitude > Float::EPSILON ~= 10^-16)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let center = json
+            .get("center")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse center")?;
+
+        let normal = json
+            .get("normal")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse normal")?;
+
+        let radius = json
+            .get("radius")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse radius")? as Float;
+
+        Self::new(center, normal, radius)
+            .ok_or("radius and normal must not be too close to zero".into())
+    }
+}
+
+impl<const D: usize> JsonSer for DiskMirror<D> {
+    /// Serialize a disk mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "center": self.center.as_slice(),
+            "normal": self.normal.as_slice(),
+            "radius": self.radius,
+        })
+    }
+}
+
+struct DiskRenderData<const D: usize> {
+    vertices: gl::VertexBuffer<render::Vertex<D>>,
+}
+
+impl<const D: usize> render::RenderData for DiskRenderData<D> {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: match D {
+                0 => unreachable!("dimension must not be zero"),
+                1 | 2 => gl::index::PrimitiveType::LinesList,
+                _ => gl::index::PrimitiveType::TriangleFan,
+            },
+        }
+    }
+}
+
+impl render::OpenGLRenderable for DiskMirror<2> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let [_, u] = util::basis_from_vector(&self.normal);
+
+        let vertices = [self.center + u * self.radius, self.center - u * self.radius]
+            .map(render::Vertex2D::from);
+
+        list.push(Box::new(DiskRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl render::OpenGLRenderable for DiskMirror<3> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        const NUM_POINTS: usize = 360;
+
+        use core::f64::consts::TAU;
+
+        let [_, u, v] = util::basis_from_vector(&self.normal);
+
+        let vertices: Vec<_> = core::iter::once(self.center)
+            .chain((0..=NUM_POINTS).map(|i| {
+                let (sin, cos) = (i as Float / NUM_POINTS as Float * TAU).sin_cos();
+                self.center + (cos * u + sin * v) * self.radius
+            }))
+            .map(render::Vertex3D::from)
+            .collect();
+
+        list.push(Box::new(DiskRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl<const D: usize> Random for DiskMirror<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        const MAX_RADIUS: Float = 4.0;
+
+        loop {
+            if let Some(mirror) = Self::new(
+                util::rand_vect(rng, 9.0),
+                util::rand_vect(rng, 1.0),
+                rng.gen::<Float>() * MAX_RADIUS,
+            ) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_basic_disk() {
+        let mirror = DiskMirror::<3>::from_json(&json!({
+            "center": [0., 0., 1.],
+            "normal": [0., 0., 2.],
+            "radius": 1.,
+        }))
+        .expect("json error");
+
+        let mut ray = Ray::new(
+            [0.5, -0.5, 0.].into(),
+            Unit::new_normalize([0., 1., 1.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [tangent] = intersections.as_slice() else {
+            panic!("there must be one intersection");
+        };
+
+        let t = tangent.try_ray_intersection(&ray).unwrap();
+        assert!((t - Float::sqrt(2.)).abs() < Float::EPSILON * 4.0);
+
+        ray.advance(t);
+        ray.reflect_dir(&tangent.direction);
+
+        assert!((ray.origin - SVector::from([0.5, 0.5, 1.])).norm() < Float::EPSILON * 4.0);
+        assert!(
+            (ray.direction.into_inner() - SVector::from([0., 1., -1.]).normalize()).norm()
+                < Float::EPSILON * 4.0
+        );
+    }
+
+    #[test]
+    fn test_outside_radius() {
+        let mirror = DiskMirror::<3>::new([0., 0., 1.].into(), [0., 0., 1.].into(), 1.).unwrap();
+
+        // would hit the disk's plane at (0.9, 0.9, 1), at distance ~1.27 from it's center, outside the
+        // disk, but inside it's bounding square
+        let ray = Ray::new(
+            [0.9, 0.9, 0.].into(),
+            Unit::new_normalize([0., 0., 1.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        assert!(intersections.is_empty());
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror = DiskMirror::<2>::new([1., 2.].into(), [1., 1.].into(), 3.).unwrap();
+
+        let mirror2 = DiskMirror::<2>::from_json(&mirror.to_json()).expect("json error");
+
+        assert_eq!(mirror.center(), mirror2.center());
+        assert!(
+            (mirror.normal().as_ref() - mirror2.normal().as_ref()).norm() < Float::EPSILON * 4.0
+        );
+        assert_eq!(mirror.radius(), mirror2.radius());
+    }
+}
@@ -0,0 +1,423 @@
+use super::*;
+
+/// The parameters of a (reflective) diffraction grating, see [`Surface::Grating`].
+///
+/// A ray of wavelength `λ` hitting the grating leaves it in the direction
+/// given by the grating equation, for the chosen diffraction order `m`:
+/// the component of it's direction tangent to the surface, along
+/// the direction orthogonal to the grooves, is shifted by `m * λ / spacing`.
+/// Positive orders are shifted towards [`Self::across_grooves`], negative ones away from it.
+///
+/// Rays without a wavelength are reflected as if by a regular mirror (which is the order `0`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Grating<const D: usize> {
+    /// Tangent to the surface, and orthogonal to the grooves
+    across_grooves: Unit<SVector<Float, D>>,
+    /// The distance between two consecutive grooves, in nanometres
+    spacing: Float,
+    order: i32,
+}
+
+impl<const D: usize> Grating<D> {
+    /// Returns `None` if `across_grooves` or `spacing` are too close to zero,
+    /// or if `spacing` is negative.
+    pub fn new(across_grooves: SVector<Float, D>, spacing: Float, order: i32) -> Option<Self> {
+        const E: Float = Float::EPSILON * 8.0;
+
+        let across_grooves = Unit::try_new(across_grooves, E)?;
+
+        (spacing > E).then_some(Self {
+            across_grooves,
+            spacing,
+            order,
+        })
+    }
+
+    pub fn across_grooves(&self) -> &Unit<SVector<Float, D>> {
+        &self.across_grooves
+    }
+
+    pub fn spacing(&self) -> Float {
+        self.spacing
+    }
+
+    pub fn order(&self) -> i32 {
+        self.order
+    }
+
+    /// Returns the direction of the ray after being diffracted by a grating with
+    /// these parameters, whose tangent hyperplane, where it is hit, has direction `tangent`.
+    ///
+    /// Returns `None` if this diffraction order doesn't exist for the ray's
+    /// wavelength and angle of incidence (it would be an evanescent wave).
+    pub fn diffract(
+        &self,
+        ray: &Ray<D>,
+        tangent: &TangentSpace<D>,
+    ) -> Option<Unit<SVector<Float, D>>> {
+        let Some(wavelength) = ray.wavelength else {
+            return Some(tangent.reflect_unit(ray.direction));
+        };
+
+        let d = ray.direction.into_inner();
+        let reflected = tangent.reflect(d);
+        let g = self.across_grooves.into_inner();
+
+        // decompose the direction into it's tangent and normal components
+        let tangential = (d + reflected) * 0.5;
+        let normal = (d - reflected) * 0.5;
+        let g_tangential = (g + tangent.reflect(g)) * 0.5;
+
+        let out_tangential =
+            tangential + g_tangential * (self.order as Float * wavelength / self.spacing);
+
+        let out_normal_sq = 1.0 - out_tangential.norm_squared();
+
+        if out_normal_sq < 0.0 {
+            return None;
+        }
+
+        let normal_norm = normal.norm();
+
+        // the outgoing direction is on the same side of the surface as the incoming ray
+        Unit::try_new(
+            if normal_norm > Float::EPSILON {
+                out_tangential - normal * (out_normal_sq.sqrt() / normal_norm)
+            } else {
+                out_tangential
+            },
+            Float::EPSILON,
+        )
+    }
+
+    /// Applies `rotation` (an orthogonal matrix) to the direction orthogonal to the grooves
+    pub fn rotated(&self, rotation: &SMatrix<Float, D, D>) -> Self {
+        Self {
+            across_grooves: Unit::new_normalize(rotation * self.across_grooves.as_ref()),
+            ..*self
+        }
+    }
+}
+
+/// A parallelotope-shaped reflective diffraction grating, with straight, evenly spaced grooves.
+///
+/// See [`Grating`] for how rays are diffracted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GratingMirror<const D: usize> {
+    plane: plane::PlaneMirror<D>,
+    grating: Grating<D>,
+}
+
+impl<const D: usize> GratingMirror<D> {
+    /// Create a new grating with the shape of `plane`, whose grooves are parallel to the
+    /// `D - 2` vectors in `grooves` (none in 2D, since the grooves are then orthogonal to the plane).
+    ///
+    /// `spacing`, the distance between two consecutive grooves, is in nanometres,
+    /// like ray wavelengths, independently of the simulation's units.
+    ///
+    /// The direction across the grooves, towards which positive orders are diffracted, is
+    /// oriented like the first vector of `plane`'s basis that isn't parallel to the grooves
+    /// (it's first vector, unless the grooves are along it). In 2D, it is the direction
+    /// of `plane`'s only basis vector.
+    ///
+    /// Returns `None` if `grooves` doesn't contain `D - 2` vectors, whose projections
+    /// onto `plane` form a free family, or if `spacing` isn't positive.
+    pub fn new(
+        plane: plane::PlaneMirror<D>,
+        grooves: &[SVector<Float, D>],
+        spacing: Float,
+        order: i32,
+    ) -> Option<Self> {
+        let ortho = plane.orthonormalised();
+
+        if grooves.len() + 2 != D {
+            return None;
+        }
+
+        let mut family = Vec::from_iter(
+            grooves
+                .iter()
+                .map(|v| ortho.orthogonal_projection(*v))
+                .chain(ortho.basis().iter().copied()),
+        );
+
+        if SVector::orthonormalize(&mut family[..grooves.len()]) != grooves.len() {
+            return None;
+        }
+
+        // the grooves are first in the family, the next vector is then orthogonal to them
+        if SVector::orthonormalize(&mut family) != D - 1 {
+            return None;
+        }
+
+        // orient it deterministically, `orthonormalize` only guarantees it's direction
+        let mut across_grooves = family[D - 2];
+        if let Some(dot) = plane
+            .inner_plane()
+            .basis()
+            .iter()
+            .map(|v| across_grooves.dot(v))
+            .find(|dot| dot.abs() > Float::EPSILON * 8.0)
+        {
+            across_grooves *= dot.signum();
+        }
+
+        Grating::new(across_grooves, spacing, order).map(|grating| Self { plane, grating })
+    }
+
+    pub fn plane(&self) -> &plane::PlaneMirror<D> {
+        &self.plane
+    }
+
+    pub fn grating(&self) -> &Grating<D> {
+        &self.grating
+    }
+
+    /// Returns `D - 2` orthonormal vectors, parallel to the grooves
+    pub fn grooves(&self) -> Vec<SVector<Float, D>> {
+        let mut family = Vec::from_iter(
+            iter::once(self.grating.across_grooves.into_inner())
+                .chain(self.plane.orthonormalised().basis().iter().copied()),
+        );
+
+        let rank = SVector::orthonormalize(&mut family);
+        family.truncate(rank);
+        family.remove(0);
+        family
+    }
+}
+
+impl<const D: usize> Mirror<D> for GratingMirror<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        list.extend(self.plane.intersection(ray).map(|tangent| TangentPlane {
+            surface: Surface::Grating(self.grating),
+            ..tangent
+        }));
+    }
+}
+
+impl<const D: usize> JsonType for GratingMirror<D> {
+    fn json_type() -> String {
+        "grating".into()
+    }
+}
+
+impl<const D: usize> JsonDes for GratingMirror<D> {
+    /// Deserialize a new grating from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "center": [1., 2., 3., ...], // (an array of D floats)
+    ///     "basis": [ // (an array of D - 1 arrays of D floats, see `PlaneMirror`)
+    ///         [1., 0., 0., ...],
+    ///         [0., 1., 0., ...],
+    ///         ...
+    ///     ],
+    ///     "grooves": [ // (optional in 2D, an array of D - 2 arrays of D floats)
+    ///         [0., 1., 0., ...],
+    ///         ...
+    ///     ],
+    ///     "spacing": 1000., // (the distance between grooves, in nanometres, must be positive)
+    ///     "order": 1, // (the diffraction order, an integer)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let plane = plane::PlaneMirror::from_json(json)?;
+
+        let grooves = json
+            .get("grooves")
+            .map(|grooves| {
+                util::map_json_array(grooves, |v| {
+                    v.as_array()
+                        .map(Vec::as_slice)
+                        .and_then(util::json_array_to_vector)
+                        .ok_or("Failed to parse groove direction".into())
+                })
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let spacing = json
+            .get("spacing")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse spacing")? as Float;
+
+        let order = json
+            .get("order")
+            .and_then(serde_json::Value::as_i64)
+            .and_then(|order| i32::try_from(order).ok())
+            .ok_or("Failed to parse order")?;
+
+        Self::new(plane, &grooves, spacing, order).ok_or(
+            "there must be D - 2 free groove directions along the plane, and spacing must be positive"
+                .into(),
+        )
+    }
+}
+
+impl<const D: usize> JsonSer for GratingMirror<D> {
+    /// Serialize a grating into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = self.plane.to_json();
+
+        json["grooves"] =
+            serde_json::json!(Vec::from_iter(self.grooves().iter().map(SVector::as_slice)));
+        json["spacing"] = self.grating.spacing.into();
+        json["order"] = self.grating.order.into();
+
+        json
+    }
+}
+
+impl render::OpenGLRenderable for GratingMirror<2> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.plane.append_render_data(display, list)
+    }
+}
+
+impl render::OpenGLRenderable for GratingMirror<3> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.plane.append_render_data(display, list)
+    }
+}
+
+impl<const D: usize> Random for GratingMirror<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        loop {
+            let grooves = Vec::from_iter(
+                iter::repeat_with(|| util::rand_vect(rng, 1.0)).take(D.saturating_sub(2)),
+            );
+
+            if let Some(mirror) = Self::new(
+                plane::PlaneMirror::random(rng),
+                &grooves,
+                rng.gen_range(300.0..3000.0),
+                rng.gen_range(-2..=2),
+            ) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    fn grating_2d(order: i32) -> GratingMirror<2> {
+        GratingMirror::new(
+            plane::PlaneMirror::try_new([[0., 0.].into(), [1., 0.].into()]).unwrap(),
+            &[],
+            1000.,
+            order,
+        )
+        .unwrap()
+    }
+
+    fn bounce<const D: usize>(
+        mirror: &GratingMirror<D>,
+        ray: &Ray<D>,
+    ) -> Option<SVector<Float, D>> {
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(ray, List::from(&mut intersections));
+
+        let [tangent] = intersections.as_slice() else {
+            panic!("expected exactly one intersection")
+        };
+
+        tangent
+            .outgoing_ray(ray, &mut rand::rngs::StdRng::seed_from_u64(0))
+            .map(|ray| ray.direction.into_inner())
+    }
+
+    #[test]
+    fn test_grating_equation() {
+        let ray = Ray {
+            wavelength: Some(500.),
+            ..Ray::new([0., 1.].into(), Unit::new_normalize([0., -1.].into()))
+        };
+
+        // at normal incidence, sin(θ) = m * λ / d
+        let first = bounce(&grating_2d(1), &ray).unwrap();
+        assert!((first - SVector::from([0.5, (0.75 as Float).sqrt()])).norm() < 1e-12);
+
+        let minus_first = bounce(&grating_2d(-1), &ray).unwrap();
+        assert!((minus_first - SVector::from([-0.5, (0.75 as Float).sqrt()])).norm() < 1e-12);
+
+        let zeroth = bounce(&grating_2d(0), &ray).unwrap();
+        assert!((zeroth - SVector::from([0., 1.])).norm() < 1e-12);
+
+        // |m * λ / d| > 1: this order doesn't propagate
+        assert_eq!(bounce(&grating_2d(3), &ray), None);
+    }
+
+    #[test]
+    fn test_no_wavelength() {
+        let ray = Ray::new([-1., 1.].into(), Unit::new_normalize([1., -1.].into()));
+
+        let out = bounce(&grating_2d(2), &ray).unwrap();
+        assert!((out - SVector::from([1., 1.]).normalize()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_grating_3d() {
+        // grooves along y: diffraction happens in the xz plane
+        let grating = GratingMirror::<3>::from_json(&json!({
+            "center": [0., 0., 0.],
+            "basis": [[1., 0., 0.], [0., 1., 0.]],
+            "grooves": [[0., 2., 0.]],
+            "spacing": 2000.,
+            "order": 1,
+        }))
+        .expect("json error");
+
+        // oriented like the first basis vector
+        assert!(
+            (grating.grating().across_grooves().as_ref() - SVector::from([1., 0., 0.])).norm()
+                < 1e-12
+        );
+
+        let ray = Ray {
+            wavelength: Some(600.),
+            ..Ray::new(
+                [0., -1., 1.].into(),
+                Unit::new_normalize([0., 1., -1.].into()),
+            )
+        };
+
+        // the component along the grooves is unchanged, the one across them is shifted
+        // by λ / d = 0.3 towards +x, the normal component completes a unit vector
+        let out = bounce(&grating, &ray).unwrap();
+        let expected = SVector::from([0.3, (0.5 as Float).sqrt(), (0.41 as Float).sqrt()]);
+        assert!((out - expected).norm() < 1e-12);
+
+        // flipping the basis flips the direction across the grooves, and the diffracted ray
+        let flipped = GratingMirror::<3>::from_json(&json!({
+            "center": [0., 0., 0.],
+            "basis": [[-1., 0., 0.], [0., 1., 0.]],
+            "grooves": [[0., 2., 0.]],
+            "spacing": 2000.,
+            "order": 1,
+        }))
+        .expect("json error");
+
+        let out = bounce(&flipped, &ray).unwrap();
+        let expected = SVector::from([-0.3, (0.5 as Float).sqrt(), (0.41 as Float).sqrt()]);
+        assert!((out - expected).norm() < 1e-12);
+
+        let grating2 = GratingMirror::<3>::from_json(&grating.to_json()).expect("json error");
+        assert_eq!(grating.plane(), grating2.plane());
+        assert!(
+            (grating.grating().across_grooves().as_ref()
+                - grating2.grating().across_grooves().as_ref())
+            .norm()
+                < 1e-12
+        );
+    }
+}
@@ -0,0 +1,589 @@
+use super::*;
+
+/// A refractive index field `n(x)`, see [`GradientIndex`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum IndexField<const D: usize> {
+    /// `n(x) = base + gradient · x`
+    Linear {
+        base: Float,
+        gradient: SVector<Float, D>,
+    },
+    /// The parabolic profile of a GRIN lens: `n(x) = n_0 * (1 - (g * r)² / 2)`, `r` being the
+    /// distance from `x` to the line going through `center` in the direction of `axis`.
+    ///
+    /// Rays close, and parallel to the axis oscillate around it with a period of `2π / g`.
+    Radial {
+        center: SVector<Float, D>,
+        axis: Unit<SVector<Float, D>>,
+        n_0: Float,
+        g: Float,
+    },
+    /// An expression of the coordinates, see [`implicit::Expression`],
+    /// whose gradient is estimated with central differences
+    Expression(implicit::Expression<D>),
+}
+
+impl<const D: usize> IndexField<D> {
+    /// The refractive index at `p`, and it's gradient
+    pub fn eval(&self, p: &SVector<Float, D>) -> (Float, SVector<Float, D>) {
+        match self {
+            Self::Linear { base, gradient } => (base + gradient.dot(p), *gradient),
+            Self::Radial {
+                center,
+                axis,
+                n_0,
+                g,
+            } => {
+                let offset = p - center;
+                let r = offset - axis.as_ref() * offset.dot(axis);
+                let g_sq = g * g;
+
+                (
+                    n_0 * (1.0 - 0.5 * g_sq * r.norm_squared()),
+                    r * (-n_0 * g_sq),
+                )
+            }
+            Self::Expression(expression) => {
+                let h = Float::EPSILON.cbrt() * (1.0 + p.norm());
+
+                let gradient = SVector::from_fn(|i, _| {
+                    let mut p1 = *p;
+                    let mut p2 = *p;
+                    p1[i] += h;
+                    p2[i] -= h;
+                    (expression.eval(&p1) - expression.eval(&p2)) / (2.0 * h)
+                });
+
+                (expression.eval(p), gradient)
+            }
+        }
+    }
+}
+
+impl<const D: usize> JsonDes for IndexField<D> {
+    /// Deserialize a refractive index field from a JSON object.
+    ///
+    /// It must follow one of these formats:
+    ///
+    /// ```json
+    /// "1.5 + 0.1 * y" // (an expression, see `Expression` for the syntax)
+    /// ```
+    /// ```json
+    /// { "linear": { "base": 1.5, "gradient": [0., 0.1, ...] } } // (an array of D floats)
+    /// ```
+    /// ```json
+    /// {
+    ///     "radial": {
+    ///         "center": [0., 0., 0., ...], // (an array of D floats)
+    ///         "axis": [1., 0., 0., ...], // (an array of D floats, must not be zero)
+    ///         "n_0": 1.6,
+    ///         "g": 0.3,
+    ///     }
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let get_vector = |json: &serde_json::Value, name: &str| {
+            json.get(name)
+                .and_then(serde_json::Value::as_array)
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector)
+                .ok_or(format!("Failed to parse {name}"))
+        };
+
+        let get_float = |json: &serde_json::Value, name: &str| {
+            json.get(name)
+                .and_then(serde_json::Value::as_f64)
+                .ok_or(format!("Failed to parse {name}"))
+        };
+
+        if let Some(source) = json.as_str() {
+            return Ok(Self::Expression(implicit::Expression::parse(source)?));
+        }
+
+        if let Some(linear) = json.get("linear") {
+            return Ok(Self::Linear {
+                base: get_float(linear, "base")?,
+                gradient: get_vector(linear, "gradient")?,
+            });
+        }
+
+        if let Some(radial) = json.get("radial") {
+            return Ok(Self::Radial {
+                center: get_vector(radial, "center")?,
+                axis: Unit::try_new(get_vector(radial, "axis")?, Float::EPSILON * 8.0)
+                    .ok_or("axis must not be zero")?,
+                n_0: get_float(radial, "n_0")?,
+                g: get_float(radial, "g")?,
+            });
+        }
+
+        Err(
+            "a refractive index field must be an expression, or have a linear or radial field"
+                .into(),
+        )
+    }
+}
+
+impl<const D: usize> JsonSer for IndexField<D> {
+    /// Serialize a refractive index field into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Linear { base, gradient } => serde_json::json!({
+                "linear": {
+                    "base": base,
+                    "gradient": gradient.as_slice(),
+                }
+            }),
+            Self::Radial {
+                center,
+                axis,
+                n_0,
+                g,
+            } => serde_json::json!({
+                "radial": {
+                    "center": center.as_slice(),
+                    "axis": axis.as_slice(),
+                    "n_0": n_0,
+                    "g": g,
+                }
+            }),
+            Self::Expression(expression) => expression.source().into(),
+        }
+    }
+}
+
+/// A solid filled with a medium whose refractive index varies continuously,
+/// surrounded by vacuum (or air).
+///
+/// Rays are refracted on it's boundary, like with [`material::Dielectric`], (using
+/// the index on the boundary) and follow curved paths inside of it, see [`CurvedRay`].
+///
+/// The index field isn't required to be positive everywhere in the solid, rays reaching
+/// a point where it isn't stop there, see [`crate::Termination::NonPositiveIndex`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GradientIndex<T, const D: usize> {
+    inner: T,
+    index: IndexField<D>,
+}
+
+impl<T, const D: usize> GradientIndex<T, D> {
+    pub fn new(inner: T, index: IndexField<D>) -> Self {
+        Self { inner, index }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn index(&self) -> &IndexField<D> {
+        &self.index
+    }
+}
+
+impl<const D: usize, T: csg::Solid<D>> Mirror<D> for GradientIndex<T, D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let mut hits = vec![];
+        self.inner
+            .append_intersecting_points(ray, List::from(&mut hits));
+
+        list.extend(hits.into_iter().filter_map(|tangent| {
+            let t = tangent.try_ray_intersection(ray)?;
+            let (n, _) = self.index.eval(&ray.at(t));
+
+            // the ray is stopped once inside, see `Termination::NonPositiveIndex`
+            if !(n > 0.0 && n.is_finite()) {
+                return None;
+            }
+
+            Some(TangentPlane {
+                surface: Surface::Refractive {
+                    relative_index: material::boundary_relative_index(&self.inner, ray, t, n),
+                },
+                ..tangent
+            })
+        }));
+    }
+
+    fn refractive_index_at(&self, p: &SVector<Float, D>) -> Option<(Float, SVector<Float, D>)> {
+        self.inner.contains(p).then(|| self.index.eval(p))
+    }
+}
+
+impl<const D: usize, T: csg::Solid<D>> csg::Solid<D> for GradientIndex<T, D> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        self.inner.contains(p)
+    }
+}
+
+impl<T, const D: usize> JsonType for GradientIndex<T, D> {
+    fn json_type() -> String {
+        "gradient_index".into()
+    }
+}
+
+impl<T: JsonDes, const D: usize> JsonDes for GradientIndex<T, D> {
+    /// Deserialize a new gradient-index solid from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "index": "1.5 + 0.1 * y", // (see `IndexField::from_json`)
+    ///     "mirror": // <the inner solid's layout>
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let index = IndexField::from_json(json.get("index").ok_or("Missing index")?)?;
+
+        let inner = T::from_json(json.get("mirror").ok_or("Missing mirror")?)?;
+
+        Ok(Self::new(inner, index))
+    }
+}
+
+impl<T: JsonSer, const D: usize> JsonSer for GradientIndex<T, D> {
+    /// Serialize a gradient-index solid into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "index": self.index.to_json(),
+            "mirror": self.inner.to_json(),
+        })
+    }
+}
+
+impl<T: render::OpenGLRenderable, const D: usize> render::OpenGLRenderable for GradientIndex<T, D> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.inner.append_render_data(display, list)
+    }
+}
+
+impl<T: Random, const D: usize> Random for GradientIndex<T, D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        Self::new(
+            T::random(rng),
+            IndexField::Linear {
+                base: rng.gen_range(1.2..1.8),
+                gradient: util::rand_vect(rng, 0.05),
+            },
+        )
+    }
+}
+
+/// The relative tolerance on the local error of each step of a [`CurvedRay`]
+const TOLERANCE: Float = 1e-10;
+/// The bounds on the length of the steps of a [`CurvedRay`],
+/// the upper one limits the distance between the sampled points
+const MIN_STEP: Float = 1e-6;
+const MAX_STEP: Float = 0.25;
+const INITIAL_STEP: Float = 0.01;
+
+/// A ray travelling through a medium with a continuously varying refractive index `n`.
+///
+/// It's path `x(s)`, parametrized by arc length, follows the ray equation
+/// `d/ds (n dx/ds) = ∇n`, and is integrated with an adaptive Runge-Kutta-Fehlberg (RK45) method.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurvedRay<const D: usize> {
+    position: SVector<Float, D>,
+    /// `n dx/ds`, the direction of the ray scaled by the local refractive index
+    optical_direction: SVector<Float, D>,
+    /// The length of the next step to try
+    step: Float,
+}
+
+/// The derivative of the state `[x, n dx/ds]` of a curved ray, w.r.t arc length
+fn derivative<const D: usize>(
+    index: &impl Fn(&SVector<Float, D>) -> (Float, SVector<Float, D>),
+    [x, t]: &[SVector<Float, D>; 2],
+) -> [SVector<Float, D>; 2] {
+    let (n, gradient) = index(x);
+    [t / n, gradient]
+}
+
+impl<const D: usize> CurvedRay<D> {
+    /// Starts a curved ray from `ray`, in a medium of refractive index `n` at it's origin
+    pub fn new(ray: &Ray<D>, n: Float) -> Self {
+        Self {
+            position: ray.origin,
+            optical_direction: ray.direction.as_ref() * n,
+            step: INITIAL_STEP,
+        }
+    }
+
+    pub fn position(&self) -> &SVector<Float, D> {
+        &self.position
+    }
+
+    pub fn direction(&self) -> Unit<SVector<Float, D>> {
+        Unit::new_normalize(self.optical_direction)
+    }
+
+    /// Moves this ray forward by one step, whose length is chosen so the estimated local error
+    /// stays under the tolerance, and returns it. `index` returns the refractive index, and it's gradient.
+    pub fn advance(
+        &mut self,
+        index: impl Fn(&SVector<Float, D>) -> (Float, SVector<Float, D>),
+    ) -> Float {
+        // the Butcher tableau of the Runge-Kutta-Fehlberg method
+        const A: [&[Float]; 5] = [
+            &[1.0 / 4.0],
+            &[3.0 / 32.0, 9.0 / 32.0],
+            &[1932.0 / 2197.0, -7200.0 / 2197.0, 7296.0 / 2197.0],
+            &[439.0 / 216.0, -8.0, 3680.0 / 513.0, -845.0 / 4104.0],
+            &[
+                -8.0 / 27.0,
+                2.0,
+                -3544.0 / 2565.0,
+                1859.0 / 4104.0,
+                -11.0 / 40.0,
+            ],
+        ];
+        const B_5: [Float; 6] = [
+            16.0 / 135.0,
+            0.0,
+            6656.0 / 12825.0,
+            28561.0 / 56430.0,
+            -9.0 / 50.0,
+            2.0 / 55.0,
+        ];
+        const B_4: [Float; 6] = [
+            25.0 / 216.0,
+            0.0,
+            1408.0 / 2565.0,
+            2197.0 / 4104.0,
+            -1.0 / 5.0,
+            0.0,
+        ];
+
+        let y = [self.position, self.optical_direction];
+        let combine = |weights: &[Float], k: &[[SVector<Float, D>; 2]], h: Float| {
+            [0, 1].map(|i| {
+                weights
+                    .iter()
+                    .zip(k)
+                    .fold(y[i], |acc, (w, k)| acc + k[i] * (w * h))
+            })
+        };
+
+        loop {
+            let h = self.step;
+
+            let mut k = [[SVector::zeros(); 2]; 6];
+            k[0] = derivative(&index, &y);
+            for (i, a) in A.iter().enumerate() {
+                k[i + 1] = derivative(&index, &combine(a, &k, h));
+            }
+
+            let y_5 = combine(&B_5, &k, h);
+            let y_4 = combine(&B_4, &k, h);
+
+            let scale = 1.0 + y[0].norm() + y[1].norm();
+            let error = ((y_5[0] - y_4[0]).norm() + (y_5[1] - y_4[1]).norm()) / scale;
+
+            if error <= TOLERANCE || h <= MIN_STEP {
+                let [position, optical_direction] = y_5;
+                self.position = position;
+                // keep `|n dx/ds| = n` from drifting
+                self.optical_direction = optical_direction.normalize() * index(&position).0;
+
+                let growth = 0.9 * (TOLERANCE / error.max(Float::MIN_POSITIVE)).powf(0.2);
+                self.step = (h * growth.min(5.0)).clamp(MIN_STEP, MAX_STEP);
+
+                break h;
+            }
+
+            let shrink = 0.9 * (TOLERANCE / error).powf(0.25);
+            self.step = (h * shrink.max(0.1)).max(MIN_STEP);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_linear_gradient() {
+        // the component of `n dx/ds` orthogonal to the gradient is conserved (Snell's law)
+        let index = IndexField::<2>::from_json(&json!({
+            "linear": { "base": 1.5, "gradient": [0., 0.1] }
+        }))
+        .expect("json error");
+
+        let ray = Ray::new([0., 0.].into(), Unit::new_normalize([1., -0.2].into()));
+        let (n, _) = index.eval(&ray.origin);
+        let invariant = n * ray.direction.x;
+
+        let mut curved = CurvedRay::new(&ray, n);
+        let mut length = 0.;
+        while length < 10. {
+            length += curved.advance(|p| index.eval(p));
+
+            let (n, _) = index.eval(curved.position());
+            assert!((n * curved.direction().x - invariant).abs() < 1e-9);
+        }
+
+        // the ray bends towards higher indices
+        assert!(curved.direction().y > 0.);
+    }
+
+    #[test]
+    fn test_grin_lens() {
+        let g = 0.5;
+        let index = IndexField::<3>::Radial {
+            center: SVector::zeros(),
+            axis: Unit::new_normalize([0., 0., 1.].into()),
+            n_0: 1.6,
+            g,
+        };
+
+        // a paraxial ray parallel to the axis crosses it after a quarter of a period
+        let ray = Ray::new(
+            [1e-3, 0., 0.].into(),
+            Unit::new_normalize([0., 0., 1.].into()),
+        );
+        let mut curved = CurvedRay::new(&ray, index.eval(&ray.origin).0);
+
+        while curved.position().x > 0. {
+            curved.advance(|p| index.eval(p));
+        }
+
+        let quarter_period = core::f64::consts::FRAC_PI_2 / g;
+        assert!((curved.position().z - quarter_period).abs() < MAX_STEP);
+
+        let index2 = IndexField::<3>::from_json(&index.to_json()).unwrap();
+        assert_eq!(index, index2);
+    }
+
+    #[test]
+    fn test_curved_paths() {
+        // a slab, whose index grows along y, deflects a ray going through it
+        let slab = GradientIndex::<csg::Csg<csg::HalfSpace<2>, 2>, 2>::from_json(&json!({
+            "index": "1.5 + 0.1 * y",
+            "mirror": {
+                "operation": "intersection",
+                "operands": [
+                    { "point": [0., 0.], "normal": [-1., 0.] },
+                    { "point": [5., 0.], "normal": [1., 0.] },
+                ],
+            },
+        }))
+        .expect("json error");
+
+        let simulation = crate::Simulation {
+            rays: vec![Ray::new(
+                [-1., 0.].into(),
+                Unit::new_normalize([1., 0.].into()),
+            )],
+            mirror: slab,
+            seed: 0,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let [path] = &simulation.get_ray_paths(10)[..] else {
+            panic!("expected exactly one path")
+        };
+
+        // the curved part of the path is sampled
+        assert!(path.all_points_raw().len() > 10);
+
+        let last = path.all_points_raw().last().unwrap();
+        assert!((last.x - 5.).abs() < 1e-9 && last.y > 0.);
+
+        // out of the slab, the ray is straight, and leaves at the angle given by Snell's law
+        let direction = path.divergence_direction().unwrap();
+        let n = 1.5 + 0.1 * last.y;
+        let sin_inside = (1. - (1.5 / n) * (1.5 / n)).sqrt();
+        assert!((direction.y - n * sin_inside).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_trapped_ray() {
+        use crate::Termination::*;
+
+        // an endless fibre, along the x axis, whose index decreases away from it
+        let fibre = GradientIndex::<csg::Csg<csg::HalfSpace<2>, 2>, 2>::from_json(&json!({
+            "index": { "radial": { "center": [0., 0.], "axis": [1., 0.], "n_0": 1.5, "g": 0.5 } },
+            "mirror": {
+                "operation": "intersection",
+                "operands": [
+                    { "point": [0., -1.], "normal": [0., -1.] },
+                    { "point": [0., 1.], "normal": [0., 1.] },
+                ],
+            },
+        }))
+        .expect("json error");
+
+        let simulation = crate::Simulation {
+            rays: vec![Ray::new(
+                [0., 0.].into(),
+                Unit::new_normalize([1., 0.2].into()),
+            )],
+            mirror: fibre,
+            seed: 0,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        // the ray never leaves the fibre, it's path stops after the maximum number of steps
+        let [path] = &simulation.get_ray_paths(10)[..] else {
+            panic!("expected exactly one path")
+        };
+        assert_eq!(path.termination(), Limit);
+        assert!(path.divergence_direction().is_none());
+        assert!(path.all_points_raw().iter().all(|p| p.y.abs() < 1.));
+    }
+
+    #[test]
+    fn test_non_positive_index() {
+        use crate::{RayPath, Termination::*};
+
+        // the linear index reaches 0 at x = 2.5, the ray going along y bends back towards x = 0,
+        // the radial one reaches 0 at r = √2 / g = 2, the ray on the axis crosses the slab
+        for (index, terminations) in [
+            (json!("0.5 - 0.2 * x"), [NonPositiveIndex, Escaped]),
+            (
+                json!({ "radial": { "center": [0., 0.], "axis": [1., 0.], "n_0": 1.5, "g": 0.5 } }),
+                [Escaped, NonPositiveIndex],
+            ),
+        ] {
+            let slab = GradientIndex::<csg::Csg<csg::HalfSpace<2>, 2>, 2>::from_json(&json!({
+                "index": index,
+                "mirror": {
+                    "operation": "intersection",
+                    "operands": [
+                        { "point": [0., 0.], "normal": [-1., 0.] },
+                        { "point": [5., 0.], "normal": [1., 0.] },
+                    ],
+                },
+            }))
+            .expect("json error");
+
+            let simulation = crate::Simulation {
+                rays: vec![
+                    Ray::new([-1., 0.].into(), Unit::new_normalize([1., 0.].into())),
+                    Ray::new([1., 0.].into(), Unit::new_normalize([0., 1.].into())),
+                ],
+                mirror: slab,
+                seed: 0,
+                propagation: crate::Propagation::Straight,
+                periodic: None,
+                world: None,
+            };
+
+            let paths = simulation.get_ray_paths(10);
+            assert!(paths.iter().map(RayPath::termination).eq(terminations));
+
+            for point in paths.iter().flat_map(|path| path.all_points_raw()) {
+                assert!(point.iter().all(|x| x.is_finite()));
+            }
+        }
+    }
+}
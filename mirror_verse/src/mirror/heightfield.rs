@@ -0,0 +1,868 @@
+use std::{fs::File, path::Path};
+
+use super::*;
+
+/// How the height of a heightfield is computed between it's samples
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Continuous, but creased along the grid lines. Normals are interpolated
+    /// from those at the samples, to smooth reflections out.
+    Bilinear,
+    /// Catmull-Rom bicubic interpolation, smooth everywhere.
+    /// Normals are the exact normals of the interpolated surface.
+    ///
+    /// The surface can overshoot the samples, and intersections are found by sampling
+    /// the ray at [`BICUBIC_SEARCH_STEPS`] points per cell, so a ray that enters and
+    /// leaves the surface between two of them (grazing a thin ridge) misses it.
+    Bicubic,
+}
+
+impl Interpolation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bilinear => "bilinear",
+            Self::Bicubic => "bicubic",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bilinear" => Some(Self::Bilinear),
+            "bicubic" => Some(Self::Bicubic),
+            _ => None,
+        }
+    }
+}
+
+/// A surface above a rectangle, whose height (along the rectangle's normal)
+/// is interpolated from a regular grid of samples.
+///
+/// The samples are indexed by `(i, j)`, where sample `(0, 0)` is at the rectangle's origin,
+/// `i` increases along it's first side, and `j` along the second.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeightfieldMirror {
+    origin: SVector<Float, 3>,
+    sides: [SVector<Float, 3>; 2],
+    normal: Unit<SVector<Float, 3>>,
+    /// The number of samples along each side
+    size: [usize; 2],
+    /// The sample `(i, j)` is at index `j * size[0] + i`
+    heights: Vec<Float>,
+    interpolation: Interpolation,
+    /// Bounds of the interpolated height, not only of the samples
+    height_range: [Float; 2],
+}
+
+/// Number of points where the height is sampled along the part of the ray in a
+/// grid cell, when looking for intersections with a bicubic heightfield.
+///
+/// Only sign changes between consecutive points are detected, so pairs of
+/// intersections closer than `1 / BICUBIC_SEARCH_STEPS` of the ray's extent in the cell
+/// may be missed.
+pub const BICUBIC_SEARCH_STEPS: usize = 16;
+
+/// The most the Catmull-Rom interpolant can exceed the range of the samples, relative to
+/// it's width: the negative weights of one axis sum to at least `-1/8` (at `t = 1/2`),
+/// so the negative products of the 2D weights sum to at least `-2 * 1/8 * 9/8`
+const BICUBIC_OVERSHOOT: Float = 0.28125;
+
+/// Catmull-Rom weights of the 4 samples around `t` in `[0 ; 1]`, and their derivatives
+fn catmull_rom_weights(t: Float) -> ([Float; 4], [Float; 4]) {
+    let (t2, t3) = (t * t, t * t * t);
+    (
+        [
+            0.5 * (-t3 + 2.0 * t2 - t),
+            0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+            0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+            0.5 * (t3 - t2),
+        ],
+        [
+            0.5 * (-3.0 * t2 + 4.0 * t - 1.0),
+            0.5 * (9.0 * t2 - 10.0 * t),
+            0.5 * (-9.0 * t2 + 8.0 * t + 1.0),
+            0.5 * (3.0 * t2 - 2.0 * t),
+        ],
+    )
+}
+
+impl HeightfieldMirror {
+    /// Create a new heightfield over the rectangle spanned by `sides` from `origin`
+    /// from a `size[0] x size[1]` grid of samples, laid out as explained in [`Self`].
+    ///
+    /// Returns `None` if `sides` aren't orthogonal, or are too close to zero, if
+    /// there are fewer than 2 samples along a side, if the number of samples doesn't
+    /// match `size` or if some of the heights are infinite or NaN.
+    pub fn new(
+        origin: SVector<Float, 3>,
+        sides: [SVector<Float, 3>; 2],
+        size: [usize; 2],
+        heights: Vec<Float>,
+        interpolation: Interpolation,
+    ) -> Option<Self> {
+        const E: Float = Float::EPSILON * 8.0;
+
+        let [u, v] = sides;
+        let (u_norm, v_norm) = (u.norm(), v.norm());
+
+        let valid = u_norm > E
+            && v_norm > E
+            && u.dot(&v).abs() <= 1e-9 * u_norm * v_norm
+            && size.iter().all(|&n| n >= 2)
+            && heights.len() == size[0] * size[1]
+            && heights.iter().all(|h| h.is_finite());
+
+        valid.then(|| {
+            let [min, max] = heights
+                .iter()
+                .fold([Float::INFINITY, Float::NEG_INFINITY], |[min, max], &h| {
+                    [min.min(h), max.max(h)]
+                });
+
+            let overshoot = match interpolation {
+                Interpolation::Bilinear => 0.0,
+                Interpolation::Bicubic => (max - min) * BICUBIC_OVERSHOOT,
+            };
+            let height_range = [min - overshoot, max + overshoot];
+
+            Self {
+                origin,
+                sides,
+                normal: Unit::new_normalize(u.cross(&v)),
+                size,
+                heights,
+                interpolation,
+                height_range,
+            }
+        })
+    }
+
+    /// Same as [`Self::new`], except the heights are read from a PNG image, whose pixel
+    /// `(i, j)`, `j` going downwards, is the sample `(i, j)`.
+    ///
+    /// The height is the brightness of the pixel (the mean of it's color channels,
+    /// ignoring transparency) mapped to `[0 ; height_scale]`
+    ///
+    /// A relative `path` is resolved against the current directory of the process.
+    pub fn from_png(
+        origin: SVector<Float, 3>,
+        sides: [SVector<Float, 3>; 2],
+        path: impl AsRef<Path>,
+        height_scale: Float,
+        interpolation: Interpolation,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut decoder = png::Decoder::new(File::open(path)?);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        let (color_type, _) = reader.output_color_type();
+        let channels = color_type.samples();
+        // don't count the alpha channel
+        let color_channels = match color_type {
+            png::ColorType::GrayscaleAlpha | png::ColorType::Rgba => channels - 1,
+            _ => channels,
+        };
+
+        let heights = Vec::from_iter(buffer[..info.buffer_size()].chunks_exact(channels).map(
+            |pixel| {
+                let sum = pixel[..color_channels]
+                    .iter()
+                    .map(|&c| c as Float)
+                    .sum::<Float>();
+                sum / (color_channels as Float * 255.0) * height_scale
+            },
+        ));
+
+        let size = [info.width as usize, info.height as usize];
+
+        Self::new(origin, sides, size, heights, interpolation).ok_or(
+            "sides must be orthogonal and non-zero, and the image must be at least 2x2".into(),
+        )
+    }
+
+    pub fn origin(&self) -> &SVector<Float, 3> {
+        &self.origin
+    }
+
+    pub fn sides(&self) -> &[SVector<Float, 3>; 2] {
+        &self.sides
+    }
+
+    pub fn normal(&self) -> &Unit<SVector<Float, 3>> {
+        &self.normal
+    }
+
+    pub fn size(&self) -> [usize; 2] {
+        self.size
+    }
+
+    pub fn heights(&self) -> &[Float] {
+        &self.heights
+    }
+
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// The vectors joining consecutive samples along each side
+    fn cell_sides(&self) -> [SVector<Float, 3>; 2] {
+        [0, 1].map(|k| self.sides[k] / (self.size[k] - 1) as Float)
+    }
+
+    /// The sample `(i, j)`, the indices are clamped to the grid's bounds
+    fn sample(&self, i: isize, j: isize) -> Float {
+        let i = i.clamp(0, self.size[0] as isize - 1) as usize;
+        let j = j.clamp(0, self.size[1] as isize - 1) as usize;
+        self.heights[j * self.size[0] + i]
+    }
+
+    /// The derivatives of the height, with respect to the grid coordinates, at the sample `(i, j)`
+    /// using central differences (or one-sided differences on the grid's boundary)
+    fn sample_gradient(&self, i: isize, j: isize) -> [Float; 2] {
+        let [n_i, n_j] = self.size.map(|n| n as isize - 1);
+
+        let (i_0, i_1) = ((i - 1).max(0), (i + 1).min(n_i));
+        let (j_0, j_1) = ((j - 1).max(0), (j + 1).min(n_j));
+
+        [
+            (self.sample(i_1, j) - self.sample(i_0, j)) / (i_1 - i_0) as Float,
+            (self.sample(i, j_1) - self.sample(i, j_0)) / (j_1 - j_0) as Float,
+        ]
+    }
+
+    /// The cell containing the point at grid coordinates `(x, y)`, and the coordinates inside of it
+    fn cell(&self, x: Float, y: Float) -> ([isize; 2], [Float; 2]) {
+        let [c_x, c_y] = [(x, 0), (y, 1)]
+            .map(|(c, k)| c.floor().clamp(0.0, (self.size[k] - 2) as Float) as isize);
+
+        ([c_x, c_y], [x - c_x as Float, y - c_y as Float])
+    }
+
+    /// Returns the height at grid coordinates `(x, y)` and it's
+    /// derivatives, with respect to those coordinates.
+    ///
+    /// With bilinear interpolation, the derivatives are interpolated from the samples'.
+    pub fn height_and_gradient(&self, x: Float, y: Float) -> (Float, [Float; 2]) {
+        let ([c_x, c_y], [f_x, f_y]) = self.cell(x, y);
+
+        match self.interpolation {
+            Interpolation::Bilinear => {
+                let corners = [(0, 0), (1, 0), (0, 1), (1, 1)];
+                let weights = [
+                    (1.0 - f_x) * (1.0 - f_y),
+                    f_x * (1.0 - f_y),
+                    (1.0 - f_x) * f_y,
+                    f_x * f_y,
+                ];
+
+                corners.into_iter().zip(weights).fold(
+                    (0.0, [0.0; 2]),
+                    |(h, [h_x, h_y]), ((d_x, d_y), w)| {
+                        let (i, j) = (c_x + d_x, c_y + d_y);
+                        let [g_x, g_y] = self.sample_gradient(i, j);
+                        (h + w * self.sample(i, j), [h_x + w * g_x, h_y + w * g_y])
+                    },
+                )
+            }
+            Interpolation::Bicubic => {
+                let (w_x, dw_x) = catmull_rom_weights(f_x);
+                let (w_y, dw_y) = catmull_rom_weights(f_y);
+
+                let mut result = (0.0, [0.0; 2]);
+
+                for (d_j, (w_j, dw_j)) in w_y.into_iter().zip(dw_y).enumerate() {
+                    for (d_i, (w_i, dw_i)) in w_x.into_iter().zip(dw_x).enumerate() {
+                        let h = self.sample(c_x + d_i as isize - 1, c_y + d_j as isize - 1);
+                        let (height, [h_x, h_y]) = &mut result;
+                        *height += w_i * w_j * h;
+                        *h_x += dw_i * w_j * h;
+                        *h_y += w_i * dw_j * h;
+                    }
+                }
+
+                result
+            }
+        }
+    }
+
+    /// The height at grid coordinates `(x, y)`, bilinear interpolation is computed
+    /// exactly, without interpolating the gradient.
+    fn height(&self, x: Float, y: Float) -> Float {
+        match self.interpolation {
+            Interpolation::Bilinear => {
+                let ([c_x, c_y], [f_x, f_y]) = self.cell(x, y);
+                let [h_00, h_10, h_01, h_11] =
+                    [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(i, j)| self.sample(c_x + i, c_y + j));
+
+                h_00 * (1.0 - f_x) * (1.0 - f_y)
+                    + h_10 * f_x * (1.0 - f_y)
+                    + h_01 * (1.0 - f_x) * f_y
+                    + h_11 * f_x * f_y
+            }
+            Interpolation::Bicubic => self.height_and_gradient(x, y).0,
+        }
+    }
+
+    /// The point of the surface at grid coordinates `(x, y)`
+    pub fn point(&self, x: Float, y: Float) -> SVector<Float, 3> {
+        let [c_u, c_v] = self.cell_sides();
+        self.origin + x * c_u + y * c_v + self.height(x, y) * self.normal.as_ref()
+    }
+
+    /// Returns the roots of `t -> z(t) - height(x(t), y(t))`, in `[a ; b[` (or `[a ; b]`
+    /// if `include_end` is true) where `(x, y, z)(t) = o + t * d`, and `[a ; b]` stays in the cell `cell`
+    fn cell_intersections(
+        &self,
+        [c_x, c_y]: [isize; 2],
+        (o, d): (SVector<Float, 3>, SVector<Float, 3>),
+        [a, b]: [Float; 2],
+        include_end: bool,
+        mut push: impl FnMut(Float),
+    ) {
+        match self.interpolation {
+            Interpolation::Bilinear => {
+                // the height is a (bilinear) polynomial of the coordinates
+                // which are affine functions of t, solve the resulting quadratic
+                let [h_00, h_10, h_01, h_11] =
+                    [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(i, j)| self.sample(c_x + i, c_y + j));
+
+                // height = h_00 + k_x * f_x + k_y * f_y + k_xy * f_x * f_y
+                let (k_x, k_y, k_xy) = (h_10 - h_00, h_01 - h_00, h_00 - h_10 - h_01 + h_11);
+                let (p_x, p_y) = (o.x - c_x as Float, o.y - c_y as Float);
+
+                let g_2 = -k_xy * d.x * d.y;
+                let g_1 = d.z - k_x * d.x - k_y * d.y - k_xy * (p_x * d.y + p_y * d.x);
+                let g_0 = o.z - h_00 - k_x * p_x - k_y * p_y - k_xy * p_x * p_y;
+
+                let roots = if g_2.abs() > Float::EPSILON * (g_1.abs() + g_0.abs()) {
+                    torus::solve_quadratic(g_1 / g_2, g_0 / g_2)
+                        .map(Vec::from)
+                        .unwrap_or_default()
+                } else if g_1.abs() > Float::EPSILON {
+                    vec![-g_0 / g_1]
+                } else {
+                    vec![]
+                };
+
+                roots
+                    .into_iter()
+                    .filter(|&t| (a..b).contains(&t) || include_end && t == b)
+                    .for_each(push);
+            }
+            Interpolation::Bicubic => {
+                let g = |t: Float| {
+                    let p = o + t * d;
+                    p.z - self.height(p.x, p.y)
+                };
+
+                let step = (b - a) / BICUBIC_SEARCH_STEPS as Float;
+                let mut t_0 = a;
+                let mut g_0 = g(a);
+
+                for k in 1..=BICUBIC_SEARCH_STEPS {
+                    let t_1 = if k == BICUBIC_SEARCH_STEPS {
+                        b
+                    } else {
+                        a + k as Float * step
+                    };
+                    let g_1 = g(t_1);
+
+                    if g_0 == 0.0 {
+                        push(t_0);
+                    } else if (g_0 < 0.0) != (g_1 < 0.0) && g_1 != 0.0 {
+                        push(implicit::bisect(g, t_0, g_0, t_1));
+                    }
+
+                    (t_0, g_0) = (t_1, g_1);
+                }
+
+                if include_end && g_0 == 0.0 {
+                    push(t_0);
+                }
+            }
+        }
+    }
+}
+
+impl Mirror<3> for HeightfieldMirror {
+    fn append_intersecting_points(&self, ray: &Ray<3>, mut list: List<TangentPlane<3>>) {
+        // work in "grid coordinates", where the samples are at integer x and y
+        // and z is the height, the parameter `t` along the ray is unchanged
+        let [c_u, c_v] = self.cell_sides();
+        let [inv_u, inv_v] = [c_u, c_v].map(|c| c / c.norm_squared());
+        let n = self.normal.as_ref();
+
+        let to_grid =
+            |v: SVector<Float, 3>| SVector::from([v.dot(&inv_u), v.dot(&inv_v), v.dot(n)]);
+
+        let o = to_grid(ray.origin - self.origin);
+        let d = to_grid(ray.direction.into_inner());
+
+        // clip the ray to the heightfield's bounding box (slab method)
+        let [min_height, max_height] = self.height_range;
+        let bounds = [
+            [0.0, (self.size[0] - 1) as Float],
+            [0.0, (self.size[1] - 1) as Float],
+            [min_height, max_height],
+        ];
+
+        let (mut t_start, mut t_end) = (Float::NEG_INFINITY, Float::INFINITY);
+
+        for ((o, d), [min, max]) in o.iter().zip(d.iter()).zip(bounds) {
+            if d.abs() > Float::EPSILON {
+                let (t_1, t_2) = ((min - o) / d, (max - o) / d);
+                t_start = t_start.max(t_1.min(t_2));
+                t_end = t_end.min(t_1.max(t_2));
+            } else if *o < min || *o > max {
+                return;
+            }
+        }
+
+        if t_start > t_end {
+            return;
+        }
+
+        // walk along the grid: split `[t_start ; t_end]` where the ray crosses grid lines
+        let mut crossings = vec![t_start, t_end];
+
+        for k in 0..2 {
+            if d[k].abs() > Float::EPSILON {
+                let [c_0, c_1] = [t_start, t_end].map(|t| o[k] + t * d[k]);
+                let first = c_0.min(c_1).ceil() as isize;
+                let last = c_0.max(c_1).floor() as isize;
+
+                crossings.extend(
+                    (first..=last)
+                        .map(|line| (line as Float - o[k]) / d[k])
+                        .filter(|t| (t_start..=t_end).contains(t)),
+                );
+            }
+        }
+
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        crossings.dedup();
+
+        let mut hits = vec![];
+
+        for (i, interval) in crossings.windows(2).enumerate() {
+            let [a, b] = [interval[0], interval[1]];
+
+            let m = o + 0.5 * (a + b) * d;
+            let (cell, _) = self.cell(m.x, m.y);
+
+            // the end of the last interval is included
+            let is_last = i == crossings.len() - 2;
+
+            self.cell_intersections(cell, (o, d), [a, b], is_last, |t| hits.push(t));
+        }
+
+        list.extend(hits.into_iter().filter_map(|t| {
+            let p = o + t * d;
+            let (_, [h_x, h_y]) = self.height_and_gradient(p.x, p.y);
+
+            // the tangent vectors along x and y are `c_u + h_x * n` and `c_v + h_y * n`
+            Unit::try_new(n - h_x * inv_u - h_y * inv_v, Float::EPSILON).map(|normal| {
+                TangentPlane {
+                    intersection: Intersection::Distance(t),
+                    direction: TangentSpace::Normal(normal),
+                    surface: Surface::Reflective,
+                }
+            })
+        }));
+    }
+}
+
+impl JsonType for HeightfieldMirror {
+    fn json_type() -> String {
+        "heightfield".into()
+    }
+}
+
+impl JsonDes for HeightfieldMirror {
+    /// Deserialize a new heightfield mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "origin": [1., 2., 3.],
+    ///     "sides": [
+    ///         [10., 0., 0.], // (two orthogonal arrays of 3 floats)
+    ///         [0., 10., 0.],
+    ///     ],
+    ///     "heights": [
+    ///         [0., 1., 0.5], // (the samples (0, 0), (1, 0), (2, 0), ...)
+    ///         [1., 2., 1.5], // (the samples (0, 1), (1, 1), (2, 1), ...)
+    ///         ...            // (at least 2 arrays of the same length, at least 2)
+    ///     ],
+    ///     "interpolation": "bilinear", // (optional, "bilinear" (default) or "bicubic")
+    /// }
+    /// ```
+    ///
+    /// Instead of `"heights"`, the samples can be read from a PNG image (see [`Self::from_png`]):
+    ///
+    /// ```json
+    /// {
+    ///     ...
+    ///     "image": "path/to/image.png", // (relative to the current directory, not to the JSON file)
+    ///     "height_scale": 2., // (optional, defaults to 1)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let origin = json
+            .get("origin")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse origin")?;
+
+        let sides_json = json
+            .get("sides")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .filter(|l| l.len() == 2)
+            .ok_or("Failed to parse sides")?;
+
+        let mut sides = [SVector::zeros(); 2];
+
+        for (value, side) in sides_json.iter().zip(&mut sides) {
+            *side = value
+                .as_array()
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector)
+                .ok_or("Failed to parse side")?;
+        }
+
+        let interpolation = match json.get("interpolation") {
+            Some(value) => value
+                .as_str()
+                .and_then(Interpolation::from_name)
+                .ok_or("Failed to parse interpolation")?,
+            None => Interpolation::Bilinear,
+        };
+
+        if let Some(image) = json.get("image") {
+            let path = image.as_str().ok_or("Failed to parse image path")?;
+
+            let height_scale = match json.get("height_scale") {
+                Some(value) => value.as_f64().ok_or("Failed to parse height_scale")? as Float,
+                None => 1.0,
+            };
+
+            return Self::from_png(origin, sides, path, height_scale, interpolation);
+        }
+
+        let rows = util::map_json_array(
+            json.get("heights").ok_or("Failed to parse heights")?,
+            |row| {
+                util::map_json_array(row, |h| {
+                    h.as_f64()
+                        .map(|h| h as Float)
+                        .ok_or("Failed to parse height".into())
+                })
+            },
+        )?;
+
+        let size = [rows.first().map_or(0, Vec::len), rows.len()];
+
+        if rows.iter().any(|row| row.len() != size[0]) {
+            return Err("all rows of heights must have the same length".into());
+        }
+
+        Self::new(origin, sides, size, rows.concat(), interpolation).ok_or(
+            "sides must be orthogonal and non-zero, there must be at least 2x2 finite heights"
+                .into(),
+        )
+    }
+}
+
+impl JsonSer for HeightfieldMirror {
+    /// Serialize a heightfield mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`],
+    /// the heights are always written inline.
+    fn to_json(&self) -> serde_json::Value {
+        let heights = Vec::from_iter(self.heights.chunks_exact(self.size[0]));
+
+        serde_json::json!({
+            "origin": self.origin.as_slice(),
+            "sides": self.sides.each_ref().map(SVector::as_slice),
+            "heights": heights,
+            "interpolation": self.interpolation.name(),
+        })
+    }
+}
+
+struct HeightfieldRenderData {
+    vertices: gl::VertexBuffer<render::Vertex3D>,
+}
+
+impl render::RenderData for HeightfieldRenderData {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: gl::index::PrimitiveType::TrianglesList,
+        }
+    }
+}
+
+impl render::OpenGLRenderable for HeightfieldMirror {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        // subdivide cells, for the curvature of bicubic patches to show
+        let subdivisions = match self.interpolation {
+            Interpolation::Bilinear => 1,
+            Interpolation::Bicubic => 3,
+        };
+
+        let [n_x, n_y] = self.size.map(|n| (n - 1) * subdivisions);
+        let step = (subdivisions as Float).recip();
+
+        let point = |i: usize, j: usize| self.point(i as Float * step, j as Float * step);
+
+        let vertices: Vec<_> = (0..n_x)
+            .flat_map(|i| (0..n_y).map(move |j| (i, j)))
+            .flat_map(|(i, j)| {
+                let [a, b, c, d] =
+                    [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)].map(|(i, j)| point(i, j));
+                [a, b, c, a, c, d]
+            })
+            .map(render::Vertex3D::from)
+            .collect();
+
+        list.push(Box::new(HeightfieldRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl Random for HeightfieldMirror {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        const SIZE: usize = 16;
+        const MAX_SIDE: Float = 8.0;
+        const MAX_AMPLITUDE: Float = 1.0;
+        const NUM_WAVES: usize = 3;
+
+        use core::f64::consts::TAU;
+
+        loop {
+            let [_, u, v] = util::basis_from_vector(&util::rand_unit_vect(rng));
+
+            let waves: Vec<_> = (0..NUM_WAVES)
+                .map(|_| {
+                    let amplitude = rng.gen::<Float>() * MAX_AMPLITUDE;
+                    let frequency = util::rand_vect::<2>(rng, 0.5);
+                    let phase = rng.gen::<Float>() * TAU;
+                    (amplitude, frequency, phase)
+                })
+                .collect();
+
+            let heights = Vec::from_iter((0..SIZE * SIZE).map(|k| {
+                let p = SVector::from([(k % SIZE) as Float, (k / SIZE) as Float]);
+                waves
+                    .iter()
+                    .map(|(a, f, phase)| a * (f.dot(&p) + phase).sin())
+                    .sum::<Float>()
+            }));
+
+            let interpolation = if rng.gen() {
+                Interpolation::Bilinear
+            } else {
+                Interpolation::Bicubic
+            };
+
+            if let Some(mirror) = Self::new(
+                util::rand_vect(rng, 9.0),
+                [
+                    u * rng.gen::<Float>() * MAX_SIDE,
+                    v * rng.gen::<Float>() * MAX_SIDE,
+                ],
+                [SIZE; 2],
+                heights,
+                interpolation,
+            ) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn hits(mirror: &HeightfieldMirror, ray: &Ray<3>) -> Vec<(Float, SVector<Float, 3>)> {
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(ray, List::from(&mut intersections));
+
+        let mut hits = Vec::from_iter(intersections.iter().map(|tangent| {
+            let TangentSpace::Normal(normal) = tangent.direction else {
+                panic!("expected a normal");
+            };
+            (
+                tangent.try_ray_intersection(ray).unwrap(),
+                normal.into_inner(),
+            )
+        }));
+
+        hits.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+        hits
+    }
+
+    #[test]
+    fn test_ramp() {
+        // z = x / 2, over [0 ; 4] x [0 ; 2]
+        for interpolation in ["bilinear", "bicubic"] {
+            let mirror = HeightfieldMirror::from_json(&json!({
+                "origin": [0., 0., 0.],
+                "sides": [[4., 0., 0.], [0., 2., 0.]],
+                "heights": [
+                    [0., 0.5, 1., 1.5, 2.],
+                    [0., 0.5, 1., 1.5, 2.],
+                    [0., 0.5, 1., 1.5, 2.],
+                ],
+                "interpolation": interpolation,
+            }))
+            .expect("json error");
+
+            let ray = Ray::new(
+                [2.3, 0.7, 10.].into(),
+                Unit::new_normalize([0., 0., -1.].into()),
+            );
+
+            let [(t, normal)] = hits(&mirror, &ray)[..] else {
+                panic!("there must be one intersection");
+            };
+
+            assert!((t - (10. - 1.15)).abs() < 1e-9);
+            assert!((normal - SVector::from([-0.5, 0., 1.]).normalize()).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_bump() {
+        // a single bump in the middle, the bilinear heightfield is a pyramid
+        let mirror = HeightfieldMirror::new(
+            [-1., -1., 0.].into(),
+            [[2., 0., 0.].into(), [0., 2., 0.].into()],
+            [3, 3],
+            vec![0., 0., 0., 0., 1., 0., 0., 0., 0.],
+            Interpolation::Bilinear,
+        )
+        .unwrap();
+
+        // grazes the pyramid, entering and exiting it, through several cells
+        let ray = Ray::new(
+            [-1., 0., 0.25].into(),
+            Unit::new_normalize([1., 0., 0.].into()),
+        );
+
+        let [(t_1, _), (t_2, _)] = hits(&mirror, &ray)[..] else {
+            panic!("there must be two intersections");
+        };
+
+        assert!((t_1 - 0.25).abs() < 1e-9);
+        assert!((t_2 - 1.75).abs() < 1e-9);
+
+        // passes above it
+        let ray = Ray::new(
+            [-1., 0., 1.5].into(),
+            Unit::new_normalize([1., 0.3, 0.].into()),
+        );
+
+        assert!(hits(&mirror, &ray).is_empty());
+    }
+
+    #[test]
+    fn test_overshoot() {
+        // the samples 0, 1, 1, 0 along x, the bicubic surface peaks at 1.125 at x = 1.5
+        let mirror = HeightfieldMirror::new(
+            [0., 0., 0.].into(),
+            [[3., 0., 0.].into(), [0., 1., 0.].into()],
+            [4, 2],
+            vec![0., 1., 1., 0., 0., 1., 1., 0.],
+            Interpolation::Bicubic,
+        )
+        .unwrap();
+
+        // hits the top of the bump, above the highest sample
+        let ray = Ray::new(
+            [1.5, 0.5, 10.].into(),
+            Unit::new_normalize([0., 0., -1.].into()),
+        );
+
+        let [(t, normal)] = hits(&mirror, &ray)[..] else {
+            panic!("there must be one intersection");
+        };
+
+        assert!((t - (10. - 1.125)).abs() < 1e-9);
+        assert!((normal - SVector::from([0., 0., 1.])).norm() < 1e-9);
+
+        // crosses the bump horizontally, entirely above the highest sample
+        let ray = Ray::new(
+            [-1., 0.5, 1.1].into(),
+            Unit::new_normalize([1., 0., 0.].into()),
+        );
+
+        assert_eq!(hits(&mirror, &ray).len(), 2);
+    }
+
+    #[test]
+    fn test_png() {
+        // unique, so that concurrent test runs don't overwrite each other's image
+        let path = std::env::temp_dir().join(format!(
+            "mirror_verse_heightfield_test_{}.png",
+            std::process::id()
+        ));
+
+        // a 3x2 grayscale image
+        let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 3, 2);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer
+            .write_image_data(&[0, 51, 102, 153, 204, 255])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let mirror = HeightfieldMirror::from_json(&json!({
+            "origin": [0., 0., 0.],
+            "sides": [[2., 0., 0.], [0., 1., 0.]],
+            "image": path.to_str().unwrap(),
+            "height_scale": 5.,
+        }))
+        .expect("json error");
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mirror.size(), [3, 2]);
+        for (h, expected) in mirror.heights().iter().zip([0., 1., 2., 3., 4., 5.]) {
+            assert!((h - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror = HeightfieldMirror::new(
+            [1., 2., 3.].into(),
+            [[0., 2., 0.].into(), [0., 0., 3.].into()],
+            [2, 3],
+            vec![0., 1., 2., 3., 4., 5.],
+            Interpolation::Bicubic,
+        )
+        .unwrap();
+
+        let mirror2 = HeightfieldMirror::from_json(&mirror.to_json()).expect("json error");
+
+        assert_eq!(mirror, mirror2);
+    }
+}
@@ -0,0 +1,363 @@
+use super::*;
+
+/// Geodesics of a larger (euclidean) radius than this, are
+/// considered to be straight lines (diameters of the disk)
+const MAX_GEODESIC_RADIUS: Float = 1e6;
+
+/// Returns the circle along which `ray` travels in the Poincaré disk model of the
+/// hyperbolic plane: the one tangent to `ray`, and orthogonal to the unit circle,
+/// or `None` if `ray` travels along a diameter of the disk.
+///
+/// # Panics
+///
+/// If `D != 2`.
+pub fn geodesic<const D: usize>(ray: &Ray<D>) -> Option<arc::Arc<D>> {
+    assert_eq!(D, 2, "the Poincaré disk model is only supported in 2D");
+
+    // the center of the circle is `p + t * n`, `n` being the direction, rotated by a quarter
+    // turn, and it is orthogonal to the unit circle: `|p + t * n|^2 = 1 + t^2`, that is:
+    // `2 * t * p.n = 1 - |p|^2`, (see `Arc::larmor`, `t` is the signed radius of the circle)
+    let p = &ray.origin;
+    let p_dot_n = p[1] * ray.direction[0] - p[0] * ray.direction[1];
+    let radius = (1.0 - p.norm_squared()) / (2.0 * p_dot_n);
+
+    (radius.abs() <= MAX_GEODESIC_RADIUS).then(|| arc::Arc::larmor(ray, radius))
+}
+
+/// Returns the distance `t` such that `ray.at(t)` lies on the unit
+/// sphere, assuming `ray.origin` is inside of it
+pub fn boundary_distance<const D: usize>(ray: &Ray<D>) -> Float {
+    let b = ray.origin.dot(&ray.direction);
+    let c = ray.origin.norm_squared() - 1.0;
+    (b * b - c).max(0.0).sqrt() - b
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Geodesic {
+    Diameter(plane::PlaneMirror<2>),
+    Circle(sphere::EuclideanSphereMirror<2>),
+}
+
+/// A mirror of the Poincaré disk model of the hyperbolic plane: the segment of the
+/// geodesic between two points of the unit disk, that is, the arc, between them, of
+/// the circle orthogonal to the unit circle going through them, or a segment of a diameter.
+///
+/// Reflection being the same in the hyperbolic plane as in the model, (which preserves
+/// angles) these are regular reflective mirrors. See [`crate::Propagation::Hyperbolic`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeodesicMirror {
+    start: SVector<Float, 2>,
+    end: SVector<Float, 2>,
+    geodesic: Geodesic,
+}
+
+impl GeodesicMirror {
+    /// Returns `None` if `start` and `end` are equal, or not both inside the unit disk.
+    pub fn new(start: SVector<Float, 2>, end: SVector<Float, 2>) -> Option<Self> {
+        if start.norm_squared() >= 1.0 || end.norm_squared() >= 1.0 {
+            return None;
+        }
+
+        // the center `c` of the circle satisfies `|c - p|^2 = |c|^2 - 1`,
+        // that is, `2 * c.p = |p|^2 + 1`, for `p` in `{start, end}`
+        let circle = SMatrix::<Float, 2, 2>::from_rows(&[start.transpose(), end.transpose()])
+            .try_inverse()
+            .map(|m| {
+                m * SVector::from([start.norm_squared() + 1.0, end.norm_squared() + 1.0]) / 2.0
+            })
+            .and_then(|center| {
+                let radius = (center.norm_squared() - 1.0).sqrt();
+                (radius <= MAX_GEODESIC_RADIUS)
+                    .then(|| sphere::EuclideanSphereMirror::new(center, radius))
+                    .flatten()
+            });
+
+        let geodesic = match circle {
+            Some(circle) => Geodesic::Circle(circle),
+            None => Geodesic::Diameter(plane::PlaneMirror::try_new([
+                (start + end) / 2.0,
+                (end - start) / 2.0,
+            ])?),
+        };
+
+        Some(Self {
+            start,
+            end,
+            geodesic,
+        })
+    }
+
+    pub fn start(&self) -> &SVector<Float, 2> {
+        &self.start
+    }
+
+    pub fn end(&self) -> &SVector<Float, 2> {
+        &self.end
+    }
+
+    /// Whether `p`, a point of the whole geodesic, lies between `self.start()` and `self.end()`
+    fn contains(&self, p: &SVector<Float, 2>) -> bool {
+        // the part of the circle inside the disk is less than a half circle, so the arc
+        // between the ends is the minor one, on which they are seen at an obtuse angle
+        (self.start - p).dot(&(self.end - p)) < 0.0
+    }
+
+    /// Returns the points of this segment, from `self.start()` to `self.end()`
+    pub fn points(&self) -> Vec<SVector<Float, 2>> {
+        let Geodesic::Circle(circle) = self.geodesic else {
+            return vec![self.start, self.end];
+        };
+
+        let (u, v) = (self.start - circle.center, self.end - circle.center);
+
+        // `w` is `u` rotated by a quarter turn, towards `v`
+        let w = SVector::from([-u.y, u.x]) * u.perp(&v).signum();
+        let angle = (u.dot(&v) / (u.norm() * v.norm())).clamp(-1.0, 1.0).acos();
+
+        // SAFETY: `u` and `w` are orthogonal vectors of the same, non-zero, length
+        let arc = arc::Arc::new(circle.center, u, w).unwrap();
+
+        Vec::from_iter(
+            iter::once(self.start)
+                .chain(arc.sample(angle))
+                .chain(iter::once(self.end)),
+        )
+    }
+}
+
+impl Mirror<2> for GeodesicMirror {
+    fn append_intersecting_points(&self, ray: &Ray<2>, mut list: List<TangentPlane<2>>) {
+        match &self.geodesic {
+            Geodesic::Diameter(plane) => plane.append_intersecting_points(ray, list),
+            Geodesic::Circle(circle) => {
+                let mut hits = vec![];
+                circle.append_intersecting_points(ray, List::from(&mut hits));
+
+                list.extend(hits.into_iter().filter(|tangent| {
+                    let p = match tangent.intersection {
+                        Intersection::Distance(t) => ray.at(t),
+                        Intersection::StartingPoint(p) => p,
+                    };
+                    self.contains(&p)
+                }));
+            }
+        }
+    }
+
+    fn append_arc_intersections(
+        &self,
+        arc: &arc::Arc<2>,
+        mut list: List<(Float, TangentPlane<2>)>,
+    ) {
+        match &self.geodesic {
+            Geodesic::Diameter(plane) => plane.append_arc_intersections(arc, list),
+            Geodesic::Circle(circle) => {
+                let mut hits = vec![];
+                circle.append_arc_intersections(arc, List::from(&mut hits));
+
+                list.extend(
+                    hits.into_iter()
+                        .filter(|(angle, _)| self.contains(&arc.at(*angle))),
+                );
+            }
+        }
+    }
+}
+
+impl JsonType for GeodesicMirror {
+    fn json_type() -> String {
+        "geodesic".into()
+    }
+}
+
+impl JsonDes for GeodesicMirror {
+    /// Deserialize a new geodesic segment from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "start": [0.5, -0.5], // (inside the unit disk)
+    ///     "end": [0.5, 0.5], // (inside the unit disk, and different from `start`)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let get_point = |name: &str| {
+            json.get(name)
+                .and_then(serde_json::Value::as_array)
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector)
+                .ok_or(format!("Missing or invalid {name}"))
+        };
+
+        Self::new(get_point("start")?, get_point("end")?)
+            .ok_or("the ends of a geodesic must be distinct points of the unit disk".into())
+    }
+}
+
+impl JsonSer for GeodesicMirror {
+    /// Serialize a geodesic segment into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "start": self.start.as_slice(),
+            "end": self.end.as_slice(),
+        })
+    }
+}
+
+struct GeodesicRenderData {
+    vertices: gl::VertexBuffer<render::Vertex2D>,
+}
+
+impl render::RenderData for GeodesicRenderData {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: gl::index::PrimitiveType::LineStrip,
+        }
+    }
+}
+
+impl render::OpenGLRenderable for GeodesicMirror {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let vertices: Vec<_> = self
+            .points()
+            .into_iter()
+            .map(render::Vertex2D::from)
+            .collect();
+
+        list.push(Box::new(GeodesicRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl Random for GeodesicMirror {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        loop {
+            if let Some(mirror) = Self::new(util::rand_vect(rng, 1.0), util::rand_vect(rng, 1.0)) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ray(origin: [Float; 2], direction: [Float; 2]) -> Ray<2> {
+        Ray::new(origin.into(), Unit::new_normalize(direction.into()))
+    }
+
+    #[test]
+    fn test_geodesics() {
+        let moving = ray([0.3, 0.2], [1., 2.]);
+        let arc = geodesic(&moving).unwrap();
+
+        // orthogonal to the unit circle, and tangent to the ray
+        let (c, r) = (arc.center(), arc.radius());
+        assert!((c.norm_squared() - 1. - r * r).abs() < 1e-12);
+        assert!((arc.at(0.) - moving.origin).norm() < 1e-12);
+        assert!((arc.direction_at(0.).into_inner() - moving.direction.into_inner()).norm() < 1e-12);
+
+        assert!(geodesic(&ray([0.5, 0.], [-1., 0.])).is_none());
+        assert!((moving.at(boundary_distance(&moving)).norm() - 1.).abs() < 1e-12);
+
+        let mirror = GeodesicMirror::new([0.5, -0.2].into(), [-0.1, 0.6].into()).unwrap();
+        let Geodesic::Circle(circle) = mirror.geodesic else {
+            panic!("expected a circle");
+        };
+        let (c, r) = (circle.center, *circle.radius());
+        assert!((c.norm_squared() - 1. - r * r).abs() < 1e-12);
+        for p in mirror.points() {
+            assert!(((p - c).norm() - r).abs() < 1e-12);
+        }
+
+        assert!(matches!(
+            GeodesicMirror::new([0.5, 0.5].into(), [-0.2, -0.2].into())
+                .unwrap()
+                .geodesic,
+            Geodesic::Diameter(_),
+        ));
+        assert!(GeodesicMirror::new([0.5, 0.5].into(), [0.8, 0.8].into()).is_none());
+    }
+
+    #[test]
+    fn test_segment_bounds() {
+        let mirror = GeodesicMirror::new([0.5, -0.5].into(), [0.5, 0.5].into()).unwrap();
+
+        let mut hits = vec![];
+        mirror.append_intersecting_points(&ray([0., 0.], [1., 0.]), List::from(&mut hits));
+        assert_eq!(hits.len(), 1);
+
+        // the ray crosses the circle, of center (1.5, 0), beyond the end of the segment
+        hits.clear();
+        mirror.append_intersecting_points(&ray([0., 0.6], [1., 0.]), List::from(&mut hits));
+        assert!(hits.is_empty());
+
+        let mut hits = vec![];
+        let arc = geodesic(&ray([0., 0.1], [1., 0.])).unwrap();
+        mirror.append_arc_intersections(&arc, List::from(&mut hits));
+        assert_eq!(hits.len(), 1);
+        assert!(mirror.contains(&arc.at(hits[0].0)));
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror = GeodesicMirror::new([0.5, -0.2].into(), [-0.1, 0.6].into()).unwrap();
+        let mirror2 = GeodesicMirror::from_json(&mirror.to_json()).expect("json error");
+        assert_eq!(mirror, mirror2);
+    }
+
+    #[test]
+    fn test_hyperbolic_billiard() {
+        let mirror = GeodesicMirror::new([0.5, -0.5].into(), [0.5, 0.5].into()).unwrap();
+
+        // the ray hits the geodesic at a right angle, and comes back along the diameter
+        let simulation = crate::Simulation {
+            rays: vec![ray([0., 0.], [1., 0.])],
+            mirror,
+            seed: 0,
+            propagation: crate::Propagation::Hyperbolic,
+            periodic: None,
+            world: None,
+        };
+
+        let path = simulation.get_ray_paths(10).remove(0);
+        let points = path.all_points_raw();
+        assert_eq!(points.len(), 3);
+        assert!((points[1] - SVector::from([1.5 - 1.25f64.sqrt(), 0.])).norm() < 1e-12);
+        assert!((points[2] - SVector::from([-1., 0.])).norm() < 1e-12);
+
+        // a square of geodesics traps the rays
+        let corners = [[0.5, 0.5], [-0.5, 0.5], [-0.5, -0.5], [0.5, -0.5]].map(SVector::from);
+        let square = Vec::from_iter(
+            (0..4).map(|i| GeodesicMirror::new(corners[i], corners[(i + 1) % 4]).unwrap()),
+        );
+
+        let simulation = crate::Simulation {
+            rays: vec![ray([0.1, 0.2], [1., 0.3]), ray([-0.3, 0.], [0.2, -1.])],
+            mirror: square,
+            seed: 0,
+            propagation: crate::Propagation::Hyperbolic,
+            periodic: None,
+            world: None,
+        };
+
+        for path in simulation.get_ray_paths(50) {
+            assert!(path.divergence_direction().is_none());
+            assert!(path.all_points_raw().iter().all(|p| p.norm() < 1.));
+            assert!(path.vertices.len() > 10);
+        }
+    }
+}
@@ -0,0 +1,569 @@
+use super::*;
+
+pub mod expr;
+
+pub use expr::Expression;
+
+/// A function returning the signed distance from a point to a surface:
+/// negative inside of it, positive outside.
+///
+/// The returned value doesn't need to be the exact distance, but it must never
+/// exceed it (once divided by the mirror's lipschitz constant), or intersections may be missed.
+pub trait SignedDistance<const D: usize> {
+    fn signed_distance(&self, p: &SVector<Float, D>) -> Float;
+}
+
+impl<const D: usize, F: Fn(&SVector<Float, D>) -> Float> SignedDistance<D> for F {
+    fn signed_distance(&self, p: &SVector<Float, D>) -> Float {
+        self(p)
+    }
+}
+
+/// A mirror whose surface is the zero level set of a signed distance function (SDF),
+/// restricted to a bounding sphere.
+///
+/// Intersections are found by sphere tracing the SDF along the ray, then refined with bisection.
+/// Normals are obtained from the numerical gradient of the SDF.
+///
+/// The SDF can be any closure `Fn(&SVector<Float, D>) -> Float`, or, (the default) an
+/// [`Expression`], which can be (de)serialized to/from JSON.
+pub struct ImplicitMirror<const D: usize, F = Expression<D>> {
+    sdf: F,
+    center: SVector<Float, D>,
+    bounding_radius: Float,
+    lipschitz: Float,
+}
+
+/// Number of sphere tracing steps after which the march is aborted
+const MAX_STEPS: usize = 4096;
+/// Smallest step, relative to the bounding radius, taken when marching.
+/// Surface features thinner than this can be missed.
+const MIN_STEP: Float = 1e-3;
+/// Hits closer than this to the ray's origin, relative to the bounding radius,
+/// are considered to be the point the ray has just been reflected off.
+const SELF_HIT_TOLERANCE: Float = 1e-9;
+
+impl<const D: usize, F> ImplicitMirror<D, F> {
+    /// Create a new implicit mirror from a signed distance function, with a surface
+    /// contained in the sphere of radius `bounding_radius` centered at `center`.
+    ///
+    /// Returns `None` if `bounding_radius` is too close to `0.0`
+    pub fn new(sdf: F, center: SVector<Float, D>, bounding_radius: Float) -> Option<Self> {
+        (bounding_radius.abs() >= Float::EPSILON).then_some(Self {
+            sdf,
+            center,
+            bounding_radius: bounding_radius.abs(),
+            lipschitz: 1.0,
+        })
+    }
+
+    pub fn sdf(&self) -> &F {
+        &self.sdf
+    }
+
+    pub fn center(&self) -> &SVector<Float, D> {
+        &self.center
+    }
+
+    pub fn bounding_radius(&self) -> Float {
+        self.bounding_radius
+    }
+
+    /// An upper bound of the norm of the SDF's gradient. Steps taken when
+    /// marching are divided by this value. Defaults to `1.0` (exact SDFs).
+    pub fn lipschitz(&self) -> Float {
+        self.lipschitz
+    }
+
+    pub fn set_lipschitz(&mut self, k: Float) -> bool {
+        let ok = k >= Float::EPSILON;
+
+        if ok {
+            self.lipschitz = k;
+        }
+
+        ok
+    }
+}
+
+impl<const D: usize, F: SignedDistance<D>> ImplicitMirror<D, F> {
+    /// The gradient of the SDF at `p`, estimated with central differences
+    pub fn gradient(&self, p: &SVector<Float, D>) -> SVector<Float, D> {
+        let h = Float::EPSILON.cbrt() * self.bounding_radius.max(1.0);
+
+        SVector::from_fn(|i, _| {
+            let mut p1 = *p;
+            let mut p2 = *p;
+            p1[i] += h;
+            p2[i] -= h;
+            (self.sdf.signed_distance(&p1) - self.sdf.signed_distance(&p2)) / (2.0 * h)
+        })
+    }
+}
+
+/// Finds the point in `[a ; b]` where `f` changes sign, given that `f(a)` has the sign of `f_a`
+/// and `a <= b`
+pub(crate) fn bisect(
+    f: impl Fn(Float) -> Float,
+    mut a: Float,
+    mut f_a: Float,
+    mut b: Float,
+) -> Float {
+    loop {
+        let m = 0.5 * (a + b);
+        // the interval can't be split any further
+        if m <= a || m >= b {
+            break m;
+        }
+
+        let f_m = f(m);
+        if (f_m < 0.0) == (f_a < 0.0) {
+            a = m;
+            f_a = f_m;
+        } else {
+            b = m;
+        }
+    }
+}
+
+impl<const D: usize, F: SignedDistance<D>> Mirror<D> for ImplicitMirror<D, F> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        // clip the ray to the bounding sphere, (see sphere.rs)
+        let v = ray.origin - self.center;
+        let b = v.dot(&ray.direction);
+        let r = self.bounding_radius;
+        let delta = b * b - (v.norm_squared() - r * r);
+
+        if delta <= Float::EPSILON {
+            return;
+        }
+
+        let root_delta = delta.sqrt();
+        let (t_start, t_end) = (-b - root_delta, -b + root_delta);
+
+        let f = |t| self.sdf.signed_distance(&ray.at(t));
+        let min_step = MIN_STEP * r;
+
+        let mut t = t_start;
+        let mut f_t = f(t);
+
+        for _ in 0..MAX_STEPS {
+            if t >= t_end {
+                break;
+            }
+
+            // sphere tracing: the SDF guarantees that the surface isn't any closer than `|f_t|`
+            let next_t = (t + (f_t.abs() / self.lipschitz).max(min_step)).min(t_end);
+            let f_next = f(next_t);
+
+            if (f_t < 0.0) != (f_next < 0.0) {
+                let hit = bisect(f, t, f_t, next_t);
+
+                if hit.abs() > SELF_HIT_TOLERANCE * r {
+                    if let Some(normal) = Unit::try_new(self.gradient(&ray.at(hit)), Float::EPSILON)
+                    {
+                        list.push(TangentPlane {
+                            intersection: Intersection::Distance(hit),
+                            direction: TangentSpace::Normal(normal),
+                            surface: Surface::Reflective,
+                        });
+                    }
+                }
+            }
+
+            t = next_t;
+            f_t = f_next;
+        }
+    }
+}
+
+impl<const D: usize, F: SignedDistance<D>> csg::Solid<D> for ImplicitMirror<D, F> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        (p - self.center).norm_squared() <= self.bounding_radius * self.bounding_radius
+            && self.sdf.signed_distance(p) <= 0.0
+    }
+}
+
+impl<const D: usize> JsonType for ImplicitMirror<D> {
+    fn json_type() -> String {
+        "implicit".into()
+    }
+}
+
+impl<const D: usize> JsonDes for ImplicitMirror<D> {
+    /// Deserialize a new implicit mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "sdf": "length(x, y, z) - 1", // (see `Expression` for the syntax)
+    ///     "center": [1., 2., 3., ...], // (an array of D floats)
+    ///     "bounding_radius": 4., // (must be a float of magnitude > Float::EPSILON ~= 10^-16)
+    ///     "lipschitz": 1., // (optional, a positive float, defaults to 1.0)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let sdf = json
+            .get("sdf")
+            .and_then(serde_json::Value::as_str)
+            .ok_or("Failed to parse sdf")?;
+
+        let sdf = Expression::parse(sdf)?;
+
+        let center = json
+            .get("center")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse center")?;
+
+        let bounding_radius = json
+            .get("bounding_radius")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse bounding_radius")? as Float;
+
+        let mut mirror = Self::new(sdf, center, bounding_radius)
+            .ok_or("bounding_radius must not be too close to 0.0")?;
+
+        if let Some(lipschitz) = json.get("lipschitz") {
+            let lipschitz = lipschitz.as_f64().ok_or("Failed to parse lipschitz")? as Float;
+            if !mirror.set_lipschitz(lipschitz) {
+                return Err("lipschitz must be positive".into());
+            }
+        }
+
+        Ok(mirror)
+    }
+}
+
+impl<const D: usize> JsonSer for ImplicitMirror<D> {
+    /// Serialize an implicit mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "sdf": self.sdf().source(),
+            "center": self.center().as_slice(),
+            "bounding_radius": self.bounding_radius(),
+            "lipschitz": self.lipschitz(),
+        })
+    }
+}
+
+/// Number of grid cells along each axis used when extracting a contour
+const CONTOUR_RESOLUTION_2D: usize = 256;
+const CONTOUR_RESOLUTION_3D: usize = 64;
+
+/// Finds the point on the segment `[a ; b]` where `f` changes sign
+fn contour_edge_point<const D: usize>(
+    f: &impl Fn(&SVector<Float, D>) -> Float,
+    (a, f_a): (SVector<Float, D>, Float),
+    (b, _): (SVector<Float, D>, Float),
+) -> SVector<Float, D> {
+    let t = bisect(|t| f(&a.lerp(&b, t)), 0.0, f_a, 1.0);
+    a.lerp(&b, t)
+}
+
+/// Evaluates `f` on a regular grid of `(n + 1)^D` points covering the
+/// (hyper)cube of center `center` and half side length `half_extent`
+fn sample_grid<const D: usize>(
+    f: &impl Fn(&SVector<Float, D>) -> Float,
+    center: &SVector<Float, D>,
+    half_extent: Float,
+    n: usize,
+) -> impl Fn([usize; D]) -> (SVector<Float, D>, Float) {
+    let cell = 2.0 * half_extent / n as Float;
+    let corner = center.add_scalar(-half_extent);
+
+    let point = move |index: [usize; D]| corner + SVector::from(index.map(|i| i as Float)) * cell;
+
+    let values = Vec::from_iter((0..(n + 1).pow(D as u32)).map(|flat| {
+        let mut index = [0; D];
+        let mut flat = flat;
+        for i in index.iter_mut() {
+            *i = flat % (n + 1);
+            flat /= n + 1;
+        }
+        f(&point(index))
+    }));
+
+    move |index: [usize; D]| {
+        let flat = index.iter().rev().fold(0, |acc, i| acc * (n + 1) + i);
+        (point(index), values[flat])
+    }
+}
+
+/// Approximates the zero level set of `f` inside a square by a list of segments, returned
+/// as consecutive pairs of points, using marching triangles.
+pub(crate) fn contour_2d(
+    f: impl Fn(&SVector<Float, 2>) -> Float,
+    center: &SVector<Float, 2>,
+    half_extent: Float,
+) -> Vec<SVector<Float, 2>> {
+    let n = CONTOUR_RESOLUTION_2D;
+    let grid = sample_grid(&f, center, half_extent, n);
+
+    let mut segments = vec![];
+
+    for i in 0..n {
+        for j in 0..n {
+            let [a, b, c, d] = [[i, j], [i + 1, j], [i + 1, j + 1], [i, j + 1]].map(&grid);
+
+            for triangle in [[a, b, c], [a, c, d]] {
+                let inside = triangle.map(|(_, v)| v < 0.0);
+                // the vertex on it's own side of the contour
+                let Some(k) = (0..3).find(|&k| {
+                    inside[k] != inside[(k + 1) % 3] && inside[k] != inside[(k + 2) % 3]
+                }) else {
+                    continue;
+                };
+
+                let lone = triangle[k];
+                segments.push(contour_edge_point(&f, lone, triangle[(k + 1) % 3]));
+                segments.push(contour_edge_point(&f, lone, triangle[(k + 2) % 3]));
+            }
+        }
+    }
+
+    segments
+}
+
+/// Approximates the zero level set of `f` inside a cube by a list of triangles, returned
+/// as consecutive triplets of points, using marching tetrahedra.
+pub(crate) fn contour_3d(
+    f: impl Fn(&SVector<Float, 3>) -> Float,
+    center: &SVector<Float, 3>,
+    half_extent: Float,
+) -> Vec<SVector<Float, 3>> {
+    // the 6 tetrahedra sharing the cube's main diagonal, one for each permutation of the axes
+    const PERMUTATIONS: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+
+    let n = CONTOUR_RESOLUTION_3D;
+    let grid = sample_grid(&f, center, half_extent, n);
+
+    let mut triangles = vec![];
+
+    for i in 0..n {
+        for j in 0..n {
+            for k in 0..n {
+                for [a0, a1, _] in PERMUTATIONS {
+                    let mut corner = [i, j, k];
+                    let v0 = grid(corner);
+                    corner[a0] += 1;
+                    let v1 = grid(corner);
+                    corner[a1] += 1;
+                    let v2 = grid(corner);
+                    let v3 = grid([i + 1, j + 1, k + 1]);
+
+                    let tetrahedron = [v0, v1, v2, v3];
+                    let (inside, outside): (Vec<_>, Vec<_>) =
+                        tetrahedron.into_iter().partition(|(_, v)| *v < 0.0);
+
+                    let edge = |a, b| contour_edge_point(&f, a, b);
+
+                    match (inside.as_slice(), outside.as_slice()) {
+                        (&[lone], &[a, b, c]) | (&[a, b, c], &[lone]) => {
+                            triangles.extend([edge(lone, a), edge(lone, b), edge(lone, c)]);
+                        }
+                        (&[a, b], &[c, d]) => {
+                            let [ac, ad, bd, bc] = [edge(a, c), edge(a, d), edge(b, d), edge(b, c)];
+                            triangles.extend([ac, ad, bd, ac, bd, bc]);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    triangles
+}
+
+pub(crate) struct ContourRenderData<const D: usize> {
+    pub vertices: gl::VertexBuffer<render::Vertex<D>>,
+}
+
+impl<const D: usize> render::RenderData for ContourRenderData<D> {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: match D {
+                0 => unreachable!("dimension must not be zero"),
+                1 | 2 => gl::index::PrimitiveType::LinesList,
+                _ => gl::index::PrimitiveType::TrianglesList,
+            },
+        }
+    }
+}
+
+impl<F: SignedDistance<2>> render::OpenGLRenderable for ImplicitMirror<2, F> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let vertices = Vec::from_iter(
+            contour_2d(
+                |p| self.sdf.signed_distance(p),
+                &self.center,
+                self.bounding_radius,
+            )
+            .into_iter()
+            .map(render::Vertex::from),
+        );
+
+        list.push(Box::new(ContourRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl<F: SignedDistance<3>> render::OpenGLRenderable for ImplicitMirror<3, F> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let vertices = Vec::from_iter(
+            contour_3d(
+                |p| self.sdf.signed_distance(p),
+                &self.center,
+                self.bounding_radius,
+            )
+            .into_iter()
+            .map(render::Vertex::from),
+        );
+
+        list.push(Box::new(ContourRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl<const D: usize> Random for ImplicitMirror<D> {
+    /// A smooth blend of two spheres
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        const MAX_RADIUS: Float = 3.0;
+        const MAX_BLEND: Float = 2.0;
+
+        loop {
+            let [c1, c2] = [(); 2].map(|_| util::rand_vect::<D>(rng, 6.0));
+            let [r1, r2] = [(); 2].map(|_| rng.gen::<Float>() * MAX_RADIUS + 0.1);
+            let k = rng.gen::<Float>() * MAX_BLEND + 0.1;
+
+            let sphere = |c: SVector<Float, D>, r: Float| {
+                let coords =
+                    Vec::from_iter(c.iter().enumerate().map(|(i, c)| format!("x{i} - {c}")));
+                format!("length({}) - {r}", coords.join(", "))
+            };
+
+            let source = format!("smin({}, {}, {k})", sphere(c1, r1), sphere(c2, r2));
+
+            let center = (c1 + c2) / 2.0;
+            let bounding_radius = (c1 - c2).norm() / 2.0 + r1.max(r2) + k;
+
+            if let Some(mirror) = Expression::parse(&source)
+                .ok()
+                .and_then(|sdf| Self::new(sdf, center, bounding_radius))
+            {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sphere_sdf() {
+        let mirror = ImplicitMirror::<3>::from_json(&json!({
+            "sdf": "length(x, y, z) - 1",
+            "center": [0., 0., 0.],
+            "bounding_radius": 2.,
+        }))
+        .expect("json error");
+
+        let mut ray = Ray::new(
+            [-3., 0., 0.].into(),
+            Unit::new_normalize([1., 0., 0.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [first, second] = intersections.as_slice() else {
+            panic!("there must be two intersections");
+        };
+
+        let t = first.try_ray_intersection(&ray).unwrap();
+        assert!((t - 2.).abs() < 1e-9);
+        assert!((second.try_ray_intersection(&ray).unwrap() - 4.).abs() < 1e-9);
+
+        ray.advance(t);
+        ray.reflect_dir(&first.direction);
+
+        assert!((ray.direction.into_inner() - SVector::from([-1., 0., 0.])).norm() < 1e-6);
+    }
+
+    #[test]
+    fn test_closure_torus() {
+        let torus = |p: &SVector<Float, 3>| {
+            let q = SVector::<Float, 2>::from([p.xy().norm() - 2.0, p.z]);
+            q.norm() - 0.5
+        };
+
+        let mirror = ImplicitMirror::new(torus, SVector::zeros(), 3.).unwrap();
+
+        let ray = Ray::new(
+            [-5., 0., 0.].into(),
+            Unit::new_normalize([1., 0., 0.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let mut distances = Vec::from_iter(
+            intersections
+                .iter()
+                .filter_map(|t| t.try_ray_intersection(&ray)),
+        );
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(distances.len(), 4);
+        for (t, expected) in distances.into_iter().zip([2.5, 3.5, 6.5, 7.5]) {
+            assert!((t - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror = ImplicitMirror::<2>::from_json(&json!({
+            "sdf": "abs(x) + abs(y) - 1",
+            "center": [0., 1.],
+            "bounding_radius": 3.,
+            "lipschitz": 1.5,
+        }))
+        .expect("json error");
+
+        let mirror2 = ImplicitMirror::<2>::from_json(&mirror.to_json()).expect("json error");
+
+        assert_eq!(mirror.sdf(), mirror2.sdf());
+        assert_eq!(mirror.center(), mirror2.center());
+        assert_eq!(mirror.bounding_radius(), mirror2.bounding_radius());
+        assert_eq!(mirror.lipschitz(), mirror2.lipschitz());
+    }
+}
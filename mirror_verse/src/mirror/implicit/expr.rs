@@ -0,0 +1,436 @@
+use core::{iter::Peekable, str::CharIndices};
+
+use super::*;
+
+/// A scalar expression of the coordinates of a point in `D`-dimensional space,
+/// parsed from a string, and used to describe signed distance functions in JSON.
+///
+/// Supported syntax:
+///
+/// - numbers: `1`, `0.5`, `2e-3`, and the constant `pi`
+/// - coordinates: `x`, `y`, `z`, `w` for the first four, or `x0`, `x1`, ..., `x{D-1}`
+/// - operators: `+`, `-` (binary and unary), `*`, `/`, `^` (right associative)
+/// - functions: `sqrt`, `abs`, `sin`, `cos`, `tan`, `exp`, `ln`, `floor`, `pow(a, b)`,
+///   `atan2(y, x)`, `min(a, ...)`, `max(a, ...)`, `length(a, ...)` (euclidean norm of it's arguments),
+///   `clamp(v, lo, hi)` and `smin(a, b, k)` (polynomial smooth minimum, with blend radius `k`,
+///   which must be a positive constant)
+///
+/// Example: a torus of major radius 2 and minor radius 0.5 around the z axis:
+/// `length(length(x, y) - 2, z) - 0.5`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expression<const D: usize> {
+    source: String,
+    root: Node,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Const(Float),
+    Var(usize),
+    Neg(Box<Node>),
+    Binary(BinaryOp, Box<[Node; 2]>),
+    Call(Function, Vec<Node>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Sqrt,
+    Abs,
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+    Floor,
+    Pow,
+    Atan2,
+    Min,
+    Max,
+    Length,
+    Clamp,
+    Smin,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sqrt" => Self::Sqrt,
+            "abs" => Self::Abs,
+            "sin" => Self::Sin,
+            "cos" => Self::Cos,
+            "tan" => Self::Tan,
+            "exp" => Self::Exp,
+            "ln" => Self::Ln,
+            "floor" => Self::Floor,
+            "pow" => Self::Pow,
+            "atan2" => Self::Atan2,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "length" => Self::Length,
+            "clamp" => Self::Clamp,
+            "smin" => Self::Smin,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether this function can be called with `n` arguments
+    fn accepts(&self, n: usize) -> bool {
+        match self {
+            Self::Sqrt
+            | Self::Abs
+            | Self::Sin
+            | Self::Cos
+            | Self::Tan
+            | Self::Exp
+            | Self::Ln
+            | Self::Floor => n == 1,
+            Self::Pow | Self::Atan2 => n == 2,
+            Self::Clamp | Self::Smin => n == 3,
+            Self::Min | Self::Max | Self::Length => n >= 1,
+        }
+    }
+
+    fn apply(&self, args: &[Float]) -> Float {
+        match (self, args) {
+            (Self::Sqrt, [a]) => a.sqrt(),
+            (Self::Abs, [a]) => a.abs(),
+            (Self::Sin, [a]) => a.sin(),
+            (Self::Cos, [a]) => a.cos(),
+            (Self::Tan, [a]) => a.tan(),
+            (Self::Exp, [a]) => a.exp(),
+            (Self::Ln, [a]) => a.ln(),
+            (Self::Floor, [a]) => a.floor(),
+            (Self::Pow, [a, b]) => a.powf(*b),
+            (Self::Atan2, [y, x]) => y.atan2(*x),
+            (Self::Clamp, [v, lo, hi]) => v.max(*lo).min(*hi),
+            (Self::Smin, [a, b, k]) => {
+                let h = (k - (a - b).abs()).max(0.0) / k;
+                a.min(*b) - h * h * k * 0.25
+            }
+            (Self::Min, args) => args.iter().copied().fold(Float::INFINITY, Float::min),
+            (Self::Max, args) => args.iter().copied().fold(Float::NEG_INFINITY, Float::max),
+            (Self::Length, args) => args.iter().map(|a| a * a).sum::<Float>().sqrt(),
+            // arity is checked when parsing
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl Node {
+    /// Whether this node doesn't depend on the coordinates of the point it is evaluated at
+    fn is_constant(&self) -> bool {
+        match self {
+            Self::Const(_) => true,
+            Self::Var(_) => false,
+            Self::Neg(node) => node.is_constant(),
+            Self::Binary(_, nodes) => nodes.iter().all(Self::is_constant),
+            Self::Call(_, args) => args.iter().all(Self::is_constant),
+        }
+    }
+
+    fn eval<const D: usize>(&self, p: &SVector<Float, D>) -> Float {
+        match self {
+            Self::Const(c) => *c,
+            Self::Var(i) => p[*i],
+            Self::Neg(node) => -node.eval(p),
+            Self::Binary(op, nodes) => {
+                let [a, b] = nodes.as_ref();
+                let (a, b) = (a.eval(p), b.eval(p));
+                match op {
+                    BinaryOp::Add => a + b,
+                    BinaryOp::Sub => a - b,
+                    BinaryOp::Mul => a * b,
+                    BinaryOp::Div => a / b,
+                    BinaryOp::Pow => a.powf(b),
+                }
+            }
+            Self::Call(function, args) => {
+                // most functions take at most 3 arguments, avoid allocating for those
+                let mut buf = [0.0; 3];
+                if args.len() <= buf.len() {
+                    let buf = &mut buf[..args.len()];
+                    for (v, arg) in buf.iter_mut().zip(args) {
+                        *v = arg.eval(p);
+                    }
+                    function.apply(buf)
+                } else {
+                    function.apply(&Vec::from_iter(args.iter().map(|arg| arg.eval(p))))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Num(Float),
+    Ident(String),
+    Op(char),
+}
+
+struct Parser<'a, const D: usize> {
+    chars: Peekable<CharIndices<'a>>,
+    src: &'a str,
+    peeked: Option<Token>,
+}
+
+impl<'a, const D: usize> Parser<'a, D> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            chars: src.char_indices().peekable(),
+            src,
+            peeked: None,
+        }
+    }
+
+    fn lex(&mut self) -> Result<Option<Token>, Box<dyn Error>> {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+
+        let Some((start, c)) = self.chars.next() else {
+            return Ok(None);
+        };
+
+        let mut end = start + c.len_utf8();
+
+        Ok(Some(if c.is_ascii_digit() || c == '.' {
+            let mut prev = c;
+            while let Some((i, c)) = self.chars.next_if(|&(_, c)| {
+                c.is_ascii_digit()
+                    || c == '.'
+                    || c == 'e'
+                    || c == 'E'
+                    || ((c == '-' || c == '+') && (prev == 'e' || prev == 'E'))
+            }) {
+                prev = c;
+                end = i + c.len_utf8();
+            }
+            let num = &self.src[start..end];
+            Token::Num(num.parse().map_err(|_| format!("invalid number: {num}"))?)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while let Some((i, c)) = self
+                .chars
+                .next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_')
+            {
+                end = i + c.len_utf8();
+            }
+            Token::Ident(self.src[start..end].into())
+        } else if "+-*/^(),".contains(c) {
+            Token::Op(c)
+        } else {
+            return Err(format!("unexpected character in expression: {c:?}").into());
+        }))
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token>, Box<dyn Error>> {
+        if self.peeked.is_none() {
+            self.peeked = self.lex()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next(&mut self) -> Result<Option<Token>, Box<dyn Error>> {
+        self.peek()?;
+        Ok(self.peeked.take())
+    }
+
+    fn eat_op(&mut self, op: char) -> Result<bool, Box<dyn Error>> {
+        let matches = self.peek()? == Some(&Token::Op(op));
+        if matches {
+            self.peeked = None;
+        }
+        Ok(matches)
+    }
+
+    fn expect_op(&mut self, op: char) -> Result<(), Box<dyn Error>> {
+        self.eat_op(op)?
+            .then_some(())
+            .ok_or_else(|| format!("expected '{op}' in expression").into())
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Node, Box<dyn Error>> {
+        let mut node = self.term()?;
+        loop {
+            let op = if self.eat_op('+')? {
+                BinaryOp::Add
+            } else if self.eat_op('-')? {
+                BinaryOp::Sub
+            } else {
+                break Ok(node);
+            };
+            node = Node::Binary(op, Box::new([node, self.term()?]));
+        }
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<Node, Box<dyn Error>> {
+        let mut node = self.unary()?;
+        loop {
+            let op = if self.eat_op('*')? {
+                BinaryOp::Mul
+            } else if self.eat_op('/')? {
+                BinaryOp::Div
+            } else {
+                break Ok(node);
+            };
+            node = Node::Binary(op, Box::new([node, self.unary()?]));
+        }
+    }
+
+    // unary := '-' unary | '+' unary | power
+    fn unary(&mut self) -> Result<Node, Box<dyn Error>> {
+        if self.eat_op('-')? {
+            Ok(Node::Neg(Box::new(self.unary()?)))
+        } else if self.eat_op('+')? {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    // power := atom ('^' unary)?
+    fn power(&mut self) -> Result<Node, Box<dyn Error>> {
+        let base = self.atom()?;
+        Ok(if self.eat_op('^')? {
+            Node::Binary(BinaryOp::Pow, Box::new([base, self.unary()?]))
+        } else {
+            base
+        })
+    }
+
+    // atom := number | variable | constant | function '(' expr (',' expr)* ')' | '(' expr ')'
+    fn atom(&mut self) -> Result<Node, Box<dyn Error>> {
+        match self.next()?.ok_or("unexpected end of expression")? {
+            Token::Num(n) => Ok(Node::Const(n)),
+            Token::Op('(') => {
+                let node = self.expr()?;
+                self.expect_op(')')?;
+                Ok(node)
+            }
+            Token::Op(op) => Err(format!("unexpected '{op}' in expression").into()),
+            Token::Ident(name) => {
+                if let Some(function) = Function::from_name(&name) {
+                    self.expect_op('(')?;
+                    let mut args = vec![self.expr()?];
+                    while self.eat_op(',')? {
+                        args.push(self.expr()?);
+                    }
+                    self.expect_op(')')?;
+
+                    if !function.accepts(args.len()) {
+                        return Err(format!("{name} can't take {} arguments", args.len()).into());
+                    }
+
+                    // a blend radius of 0 divides by 0
+                    if let (Function::Smin, [_, _, k]) = (function, args.as_slice()) {
+                        if !(k.is_constant() && k.eval(&SVector::<Float, D>::zeros()) > 0.0) {
+                            return Err(
+                                "the blend radius of smin must be a positive constant".into()
+                            );
+                        }
+                    }
+
+                    return Ok(Node::Call(function, args));
+                }
+
+                let index = match name.as_str() {
+                    "pi" => return Ok(Node::Const(core::f64::consts::PI as Float)),
+                    "x" => 0,
+                    "y" => 1,
+                    "z" => 2,
+                    "w" => 3,
+                    _ => name
+                        .strip_prefix('x')
+                        .and_then(|i| i.parse().ok())
+                        .ok_or_else(|| format!("unknown identifier: {name}"))?,
+                };
+
+                (index < D)
+                    .then_some(Node::Var(index))
+                    .ok_or_else(|| format!("{name} is not a coordinate in dimension {D}").into())
+            }
+        }
+    }
+}
+
+impl<const D: usize> Expression<D> {
+    /// Parse an expression, see the type's documentation for the syntax.
+    ///
+    /// Returns an error if `source` is invalid, or references coordinates
+    /// that don't exist in dimension `D`.
+    pub fn parse(source: &str) -> Result<Self, Box<dyn Error>> {
+        let mut parser = Parser::<D>::new(source);
+        let root = parser.expr()?;
+
+        if let Some(token) = parser.next()? {
+            return Err(format!("unexpected trailing token in expression: {token:?}").into());
+        }
+
+        Ok(Self {
+            source: source.into(),
+            root,
+        })
+    }
+
+    /// The string this expression was parsed from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate this expression at the point `p`
+    pub fn eval(&self, p: &SVector<Float, D>) -> Float {
+        self.root.eval(p)
+    }
+}
+
+impl<const D: usize> SignedDistance<D> for Expression<D> {
+    fn signed_distance(&self, p: &SVector<Float, D>) -> Float {
+        self.eval(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let expr = Expression::<3>::parse("1 + 2 * x ^ 2 - -y / 2").expect("parse error");
+
+        let v = expr.eval(&SVector::from([3., 4., 0.]));
+
+        assert!((v - 21.).abs() < Float::EPSILON * 32.0);
+    }
+
+    #[test]
+    fn test_functions() {
+        let expr = Expression::<2>::parse("length(x0, x1) - max(1, abs(-2)) + smin(1, 1, 0.5)")
+            .expect("parse error");
+
+        let v = expr.eval(&SVector::from([3., 4.]));
+
+        assert!((v - (5. - 2. + 1. - 0.125)).abs() < Float::EPSILON * 32.0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Expression::<2>::parse("z - 1").is_err());
+        assert!(Expression::<3>::parse("sqrt(x, y)").is_err());
+        assert!(Expression::<3>::parse("(x + 1").is_err());
+        assert!(Expression::<3>::parse("x y").is_err());
+        assert!(Expression::<3>::parse("x # y").is_err());
+        assert!(Expression::<3>::parse("smin(x, y, 0)").is_err());
+        assert!(Expression::<3>::parse("smin(x, y, 1 - 2)").is_err());
+        assert!(Expression::<3>::parse("smin(x, y, z)").is_err());
+        assert!(Expression::<3>::parse("smin(x, y, 1 / 2)").is_ok());
+    }
+}
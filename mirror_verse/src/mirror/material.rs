@@ -0,0 +1,785 @@
+use core::ops::Range;
+
+use super::*;
+
+/// Wavelengths of visible light, in nanometres
+pub const VISIBLE_WAVELENGTHS: Range<Float> = 380.0..750.0;
+
+/// The wavelength used to evaluate wavelength-dependent properties for rays
+/// that don't have one, in nanometres (the yellow helium d-line)
+pub const REFERENCE_WAVELENGTH: Float = 587.56;
+
+/// A quantity depending on the wavelength of light
+#[derive(Clone, Debug, PartialEq)]
+pub enum Spectrum {
+    Constant(Float),
+    /// Linear interpolation between `[wavelength, value]` samples, sorted
+    /// by wavelength, and constant outside of the sampled range.
+    Samples(Vec<[Float; 2]>),
+}
+
+impl Spectrum {
+    /// Returns `None` if there are no samples, or they aren't sorted by wavelength
+    pub fn from_samples(samples: Vec<[Float; 2]>) -> Option<Self> {
+        (!samples.is_empty() && samples.windows(2).all(|w| w[0][0] < w[1][0]))
+            .then_some(Self::Samples(samples))
+    }
+
+    /// The value of this spectrum at `wavelength`, in nanometres
+    pub fn eval(&self, wavelength: Float) -> Float {
+        match self {
+            Self::Constant(value) => *value,
+            Self::Samples(samples) => {
+                let i = samples.partition_point(|[w, _]| *w < wavelength);
+
+                match (samples.get(i.wrapping_sub(1)), samples.get(i)) {
+                    (Some([w_0, v_0]), Some([w_1, v_1])) => {
+                        v_0 + (v_1 - v_0) * (wavelength - w_0) / (w_1 - w_0)
+                    }
+                    (Some([_, v]), None) | (None, Some([_, v])) => *v,
+                    // SAFETY: there is at least one sample
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+    }
+
+    /// The spectrum whose values are `1 - v`, for the values `v` of this one
+    pub fn complement(&self) -> Self {
+        match self {
+            Self::Constant(value) => Self::Constant(1.0 - value),
+            Self::Samples(samples) => {
+                Self::Samples(Vec::from_iter(samples.iter().map(|[w, v]| [*w, 1.0 - v])))
+            }
+        }
+    }
+
+    /// Whether all the values of this spectrum are in `range`
+    pub fn is_in(&self, range: &core::ops::RangeInclusive<Float>) -> bool {
+        match self {
+            Self::Constant(value) => range.contains(value),
+            Self::Samples(samples) => samples.iter().all(|[_, v]| range.contains(v)),
+        }
+    }
+}
+
+impl JsonDes for Spectrum {
+    /// Deserialize a spectrum from JSON, either a number, for a constant
+    /// spectrum, or an array of `[wavelength, value]` pairs, sorted by wavelength.
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        if let Some(value) = json.as_f64() {
+            return Ok(Self::Constant(value));
+        }
+
+        let samples = util::map_json_array(json, |sample| {
+            sample
+                .as_array()
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_float_array)
+                .ok_or("spectrum samples must be pairs of numbers".into())
+        })?;
+
+        Self::from_samples(samples)
+            .ok_or("there must be at least one spectrum sample, sorted by wavelength".into())
+    }
+}
+
+impl JsonSer for Spectrum {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Constant(value) => (*value).into(),
+            Self::Samples(samples) => serde_json::json!(samples),
+        }
+    }
+}
+
+/// The refractive index of a transparent medium, as a function of the wavelength
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dispersion {
+    Constant(Float),
+    /// Cauchy's equation: `n = A_0 + A_1 / λ² + A_2 / λ⁴ + ...`
+    /// where `λ` is in micrometres, and the `A_k` are the coefficients.
+    Cauchy(Vec<Float>),
+    /// The Sellmeier equation: `n² = 1 + sum of B * λ² / (λ² - C)`
+    /// where `λ` is in micrometres, and the `[B, C]` terms are given.
+    Sellmeier(Vec<[Float; 2]>),
+}
+
+impl Dispersion {
+    /// The refractive index at `wavelength`, in nanometres
+    pub fn refractive_index(&self, wavelength: Float) -> Float {
+        let micrometres = wavelength * 1e-3;
+        let l_sq = micrometres * micrometres;
+
+        match self {
+            Self::Constant(n) => *n,
+            Self::Cauchy(coefficients) => {
+                coefficients.iter().rev().fold(0.0, |acc, a| acc / l_sq + a)
+            }
+            Self::Sellmeier(terms) => {
+                let n_sq = 1.0
+                    + terms
+                        .iter()
+                        .map(|[b, c]| b * l_sq / (l_sq - c))
+                        .sum::<Float>();
+                n_sq.max(0.0).sqrt()
+            }
+        }
+    }
+
+    /// Whether the refractive index is positive and finite at [`REFERENCE_WAVELENGTH`],
+    /// and across [`VISIBLE_WAVELENGTHS`] (sampled every nanometre)
+    pub fn is_positive(&self) -> bool {
+        let (start, end) = (VISIBLE_WAVELENGTHS.start, VISIBLE_WAVELENGTHS.end);
+        let count = (end - start) as usize;
+
+        (0..=count)
+            .map(|k| start + (end - start) * k as Float / count as Float)
+            .chain(iter::once(REFERENCE_WAVELENGTH))
+            .map(|wavelength| self.refractive_index(wavelength))
+            .all(|n| n > 0.0 && n.is_finite())
+    }
+}
+
+impl JsonDes for Dispersion {
+    /// Deserialize a refractive index from a JSON object.
+    ///
+    /// It must follow one of these formats:
+    ///
+    /// ```json
+    /// 1.5 // (a constant index)
+    /// ```
+    /// ```json
+    /// { "cauchy": [1.5046, 0.0042] } // (the coefficients A_0, A_1, ...)
+    /// ```
+    /// ```json
+    /// { "sellmeier": [[1.0396, 0.0060], [0.2318, 0.0200], [1.0105, 103.56]] } // (the [B, C] terms)
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        if let Some(n) = json.as_f64() {
+            return Ok(Self::Constant(n));
+        }
+
+        if let Some(coefficients) = json.get("cauchy") {
+            return util::map_json_array(coefficients, |a| {
+                a.as_f64()
+                    .ok_or("cauchy coefficients must be numbers".into())
+            })
+            .map(Self::Cauchy);
+        }
+
+        if let Some(terms) = json.get("sellmeier") {
+            return util::map_json_array(terms, |term| {
+                term.as_array()
+                    .map(Vec::as_slice)
+                    .and_then(util::json_array_to_float_array)
+                    .ok_or("sellmeier terms must be pairs of numbers".into())
+            })
+            .map(Self::Sellmeier);
+        }
+
+        Err("refractive index must be a number, or have a cauchy or sellmeier field".into())
+    }
+}
+
+impl JsonSer for Dispersion {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Constant(n) => (*n).into(),
+            Self::Cauchy(coefficients) => serde_json::json!({ "cauchy": coefficients }),
+            Self::Sellmeier(terms) => serde_json::json!({ "sellmeier": terms }),
+        }
+    }
+}
+
+/// A mirror that only reflects a (wavelength dependent) fraction of the power of the rays hitting it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Coated<T> {
+    inner: T,
+    reflectivity: Spectrum,
+}
+
+impl<T> Coated<T> {
+    /// Returns `None` if `reflectivity` has values outside of `[0 ; 1]`
+    pub fn new(inner: T, reflectivity: Spectrum) -> Option<Self> {
+        reflectivity.is_in(&(0.0..=1.0)).then_some(Self {
+            inner,
+            reflectivity,
+        })
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn reflectivity(&self) -> &Spectrum {
+        &self.reflectivity
+    }
+}
+
+impl<const D: usize, T: Mirror<D>> Mirror<D> for Coated<T> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let reflectivity = self
+            .reflectivity
+            .eval(ray.wavelength.unwrap_or(REFERENCE_WAVELENGTH));
+
+        let mut hits = vec![];
+        self.inner
+            .append_intersecting_points(ray, List::from(&mut hits));
+
+        // only regular mirror surfaces are coated
+        list.extend(hits.into_iter().map(|tangent| match tangent.surface {
+            Surface::Reflective => TangentPlane {
+                surface: Surface::Coated { reflectivity },
+                ..tangent
+            },
+            _ => tangent,
+        }));
+    }
+}
+
+impl<const D: usize, T: csg::Solid<D>> csg::Solid<D> for Coated<T> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        self.inner.contains(p)
+    }
+}
+
+impl<T> JsonType for Coated<T> {
+    fn json_type() -> String {
+        "coated".into()
+    }
+}
+
+impl<T: JsonDes> JsonDes for Coated<T> {
+    /// Deserialize a new coated mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "reflectivity": 0.9, // (a spectrum, see `Spectrum::from_json`, with values in [0 ; 1])
+    ///     "mirror": // <the inner mirror's layout>
+    /// }
+    /// ```
+    ///
+    /// Instead of it's reflectivity, the absorption coefficient of the coating, (the fraction of
+    /// the power it absorbs, as used in acoustics) can be given, with an `"absorption"` field.
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let reflectivity = match (json.get("reflectivity"), json.get("absorption")) {
+            (Some(reflectivity), None) => Spectrum::from_json(reflectivity)?,
+            (None, Some(absorption)) => Spectrum::from_json(absorption)?.complement(),
+            _ => return Err("Expected one of reflectivity, or absorption".into()),
+        };
+
+        let inner = T::from_json(json.get("mirror").ok_or("Missing mirror")?)?;
+
+        Self::new(inner, reflectivity).ok_or("reflectivity must be in [0 ; 1]".into())
+    }
+}
+
+impl<T: JsonSer> JsonSer for Coated<T> {
+    /// Serialize a coated mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "reflectivity": self.reflectivity.to_json(),
+            "mirror": self.inner.to_json(),
+        })
+    }
+}
+
+impl<T: render::OpenGLRenderable> render::OpenGLRenderable for Coated<T> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.inner.append_render_data(display, list)
+    }
+}
+
+impl<T: Random> Random for Coated<T> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        // SAFETY: the reflectivity is in [0 ; 1]
+        Self::new(T::random(rng), Spectrum::Constant(rng.gen_range(0.5..=1.0))).unwrap()
+    }
+}
+
+/// A mirror made of a metal, with a complex (wavelength dependent) refractive index `n + ik`.
+///
+/// The power it reflects, and the way it changes the polarization of rays,
+/// follows Fresnel's equations, see [`fresnel_reflection`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metal<T> {
+    inner: T,
+    refractive_index: Spectrum,
+    extinction: Spectrum,
+}
+
+impl<T> Metal<T> {
+    /// Returns `None` if `refractive_index` or `extinction` have negative values
+    pub fn new(inner: T, refractive_index: Spectrum, extinction: Spectrum) -> Option<Self> {
+        let non_negative = 0.0..=Float::INFINITY;
+
+        (refractive_index.is_in(&non_negative) && extinction.is_in(&non_negative)).then_some(Self {
+            inner,
+            refractive_index,
+            extinction,
+        })
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn refractive_index(&self) -> &Spectrum {
+        &self.refractive_index
+    }
+
+    pub fn extinction(&self) -> &Spectrum {
+        &self.extinction
+    }
+}
+
+impl<const D: usize, T: Mirror<D>> Mirror<D> for Metal<T> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let wavelength = ray.wavelength.unwrap_or(REFERENCE_WAVELENGTH);
+        let refractive_index = Complex::new(
+            self.refractive_index.eval(wavelength),
+            self.extinction.eval(wavelength),
+        );
+
+        let mut hits = vec![];
+        self.inner
+            .append_intersecting_points(ray, List::from(&mut hits));
+
+        list.extend(hits.into_iter().map(|tangent| match tangent.surface {
+            Surface::Reflective => TangentPlane {
+                surface: Surface::Metallic { refractive_index },
+                ..tangent
+            },
+            _ => tangent,
+        }));
+    }
+}
+
+impl<const D: usize, T: csg::Solid<D>> csg::Solid<D> for Metal<T> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        self.inner.contains(p)
+    }
+}
+
+impl<T> JsonType for Metal<T> {
+    fn json_type() -> String {
+        "metal".into()
+    }
+}
+
+impl<T: JsonDes> JsonDes for Metal<T> {
+    /// Deserialize a new metal mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "refractive_index": 0.2, // (a spectrum, see `Spectrum::from_json`, the real part n)
+    ///     "extinction": 3.0, // (a spectrum, the imaginary part k)
+    ///     "mirror": // <the inner mirror's layout>
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let refractive_index = Spectrum::from_json(
+            json.get("refractive_index")
+                .ok_or("Missing refractive index")?,
+        )?;
+
+        let extinction = Spectrum::from_json(json.get("extinction").ok_or("Missing extinction")?)?;
+
+        let inner = T::from_json(json.get("mirror").ok_or("Missing mirror")?)?;
+
+        Self::new(inner, refractive_index, extinction)
+            .ok_or("refractive index and extinction must be non-negative".into())
+    }
+}
+
+impl<T: JsonSer> JsonSer for Metal<T> {
+    /// Serialize a metal mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "refractive_index": self.refractive_index.to_json(),
+            "extinction": self.extinction.to_json(),
+            "mirror": self.inner.to_json(),
+        })
+    }
+}
+
+impl<T: render::OpenGLRenderable> render::OpenGLRenderable for Metal<T> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.inner.append_render_data(display, list)
+    }
+}
+
+impl<T: Random> Random for Metal<T> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        // SAFETY: both are non-negative
+        Self::new(
+            T::random(rng),
+            Spectrum::Constant(rng.gen_range(0.1..2.0)),
+            Spectrum::Constant(rng.gen_range(1.0..8.0)),
+        )
+        .unwrap()
+    }
+}
+
+/// A transparent solid, made of a medium with a (wavelength dependent) refractive index,
+/// surrounded by vacuum (or air), rays hitting it are refracted.
+///
+/// The part of the ray's power that would be reflected (according to Fresnel's equations,
+/// for unpolarized light) is lost. Rays that undergo total internal reflection are reflected.
+///
+/// Rays whose wavelength is outside of [`VISIBLE_WAVELENGTHS`], where the index isn't
+/// positive, are stopped when they hit it, see [`crate::Termination::NonPositiveIndex`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dielectric<T> {
+    inner: T,
+    refractive_index: Dispersion,
+}
+
+/// The distance, relative to the distance to the origin, from both sides
+/// of a hit point, at which the side a ray comes from is tested
+const SIDE_TEST_STEP: Float = 1e-8;
+
+impl<T> Dielectric<T> {
+    /// Returns `None` if `refractive_index` isn't positive, see [`Dispersion::is_positive`]
+    pub fn new(inner: T, refractive_index: Dispersion) -> Option<Self> {
+        refractive_index.is_positive().then_some(Self {
+            inner,
+            refractive_index,
+        })
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn refractive_index(&self) -> &Dispersion {
+        &self.refractive_index
+    }
+}
+
+impl<const D: usize, T: csg::Solid<D>> Mirror<D> for Dielectric<T> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let n = self
+            .refractive_index
+            .refractive_index(ray.wavelength.unwrap_or(REFERENCE_WAVELENGTH));
+
+        // the index is only checked over the visible spectrum, elsewhere it may not be positive,
+        // the relative index of the hit then isn't either, and the ray is stopped there
+
+        let mut hits = vec![];
+        self.inner
+            .append_intersecting_points(ray, List::from(&mut hits));
+
+        list.extend(hits.into_iter().filter_map(|tangent| {
+            let t = tangent.try_ray_intersection(ray)?;
+
+            Some(TangentPlane {
+                surface: Surface::Refractive {
+                    relative_index: boundary_relative_index(&self.inner, ray, t, n),
+                },
+                ..tangent
+            })
+        }));
+    }
+}
+
+/// Returns the relative refractive index across the boundary of `solid`, at the point
+/// `ray.at(t)`, when the index is `n` inside of it, and `1.0` outside:
+/// `n` if the ray enters the solid there, `1 / n` if it leaves it, and `1.0` if it only grazes it.
+pub(crate) fn boundary_relative_index<const D: usize>(
+    solid: &(impl csg::Solid<D> + ?Sized),
+    ray: &Ray<D>,
+    t: Float,
+    n: Float,
+) -> Float {
+    let step = SIDE_TEST_STEP * (1.0 + ray.at(t).norm());
+
+    match (
+        solid.contains(&ray.at(t - step)),
+        solid.contains(&ray.at(t + step)),
+    ) {
+        (false, true) => n,
+        (true, false) => n.recip(),
+        _ => 1.0,
+    }
+}
+
+impl<const D: usize, T: csg::Solid<D>> csg::Solid<D> for Dielectric<T> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        self.inner.contains(p)
+    }
+}
+
+impl<T> JsonType for Dielectric<T> {
+    fn json_type() -> String {
+        "dielectric".into()
+    }
+}
+
+impl<T: JsonDes> JsonDes for Dielectric<T> {
+    /// Deserialize a new dielectric solid from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "refractive_index": 1.5, // (see `Dispersion::from_json`, must be positive)
+    ///     "mirror": // <the inner solid's layout>
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let refractive_index = Dispersion::from_json(
+            json.get("refractive_index")
+                .ok_or("Missing refractive index")?,
+        )?;
+
+        let inner = T::from_json(json.get("mirror").ok_or("Missing mirror")?)?;
+
+        Self::new(inner, refractive_index).ok_or("refractive index must be positive".into())
+    }
+}
+
+impl<T: JsonSer> JsonSer for Dielectric<T> {
+    /// Serialize a dielectric solid into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "refractive_index": self.refractive_index.to_json(),
+            "mirror": self.inner.to_json(),
+        })
+    }
+}
+
+impl<T: render::OpenGLRenderable> render::OpenGLRenderable for Dielectric<T> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.inner.append_render_data(display, list)
+    }
+}
+
+impl<T: Random> Random for Dielectric<T> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        loop {
+            // some kind of glass
+            let dispersion =
+                Dispersion::Cauchy(vec![rng.gen_range(1.4..1.8), rng.gen_range(0.003..0.02)]);
+
+            if let Some(mirror) = Self::new(T::random(rng), dispersion) {
+                break mirror;
+            }
+        }
+    }
+}
+
+/// Returns the direction of a ray, with direction `d`, refracted by a surface whose
+/// tangent hyperplane has direction `tangent`, going from a medium of refractive index `1.0`,
+/// to one of index `relative_index`, and the fractions of it's power that are transmitted,
+/// for the components of it's electric field orthogonal to (s), and in (p) the plane of incidence.
+///
+/// Returns `None` in case of total internal reflection.
+pub fn refract<const D: usize>(
+    d: &Unit<SVector<Float, D>>,
+    tangent: &TangentSpace<D>,
+    relative_index: Float,
+) -> Option<(Unit<SVector<Float, D>>, [Float; 2])> {
+    let d = d.into_inner();
+    let reflected = tangent.reflect(d);
+
+    // decompose the direction into it's tangent and normal components
+    let tangential = (d + reflected) * 0.5;
+    let normal = (d - reflected) * 0.5;
+
+    let out_tangential = tangential / relative_index;
+    let cos_t_sq = 1.0 - out_tangential.norm_squared();
+
+    if cos_t_sq < 0.0 {
+        return None;
+    }
+
+    let cos_i = normal.norm();
+    let cos_t = cos_t_sq.sqrt();
+
+    if cos_i <= Float::EPSILON {
+        // grazing, nothing is transmitted
+        return Some((Unit::new_normalize(d), [0.0; 2]));
+    }
+
+    let out = Unit::new_normalize(out_tangential + normal * (cos_t / cos_i));
+
+    let r_s = (cos_i - relative_index * cos_t) / (cos_i + relative_index * cos_t);
+    let r_p = (relative_index * cos_i - cos_t) / (relative_index * cos_i + cos_t);
+
+    Some((out, [1.0 - r_s * r_s, 1.0 - r_p * r_p]))
+}
+
+/// Returns the (complex) amplitude reflection coefficients `[r_s, r_p]` of light hitting,
+/// with an angle of incidence whose cosine is `cos_i`, the surface of a medium
+/// of (complex) refractive index `n`, relative to the one the light is coming from.
+///
+/// The phase convention makes `r_s = -1`, and `r_p = 1` for a perfect mirror.
+pub fn fresnel_reflection(cos_i: Float, n: Complex<Float>) -> [Complex<Float>; 2] {
+    let sin_i_sq = 1.0 - cos_i * cos_i;
+    let cos_t = nalgebra::ComplexField::sqrt(Complex::from(1.0) - sin_i_sq / (n * n));
+
+    let r_s = (cos_i - n * cos_t) / (cos_i + n * cos_t);
+    let r_p = (n * cos_i - cos_t) / (n * cos_i + cos_t);
+
+    [r_s, r_p]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    #[test]
+    fn test_dispersion() {
+        // N-BK7
+        let bk7 = Dispersion::from_json(&json!({
+            "sellmeier": [[1.03961212, 0.00600069867], [0.231792344, 0.0200179144], [1.01046945, 103.560653]],
+        }))
+        .expect("json error");
+
+        assert!((bk7.refractive_index(587.56) - 1.5168).abs() < 1e-4);
+        assert!(bk7.refractive_index(450.) > bk7.refractive_index(650.));
+
+        let cauchy = Dispersion::Cauchy(vec![1.5, 0.01]);
+        assert!((cauchy.refractive_index(500.) - 1.54).abs() < 1e-12);
+
+        let spectrum = Spectrum::from_json(&json!([[400., 0.5], [600., 0.9]])).unwrap();
+        assert_eq!(spectrum.eval(300.), 0.5);
+        assert!((spectrum.eval(500.) - 0.7).abs() < 1e-12);
+        assert_eq!(spectrum.eval(700.), 0.9);
+        assert!(Spectrum::from_json(&json!([[600., 0.5], [400., 0.9]])).is_err());
+
+        // n² = 1 - 2 λ² / (λ² - 0.01) is negative, clamped to 0, over the visible spectrum
+        let negative = Dispersion::Sellmeier(vec![[-2., 0.01]]);
+        assert!(!negative.is_positive());
+        assert!(Dielectric::new((), negative).is_none());
+        assert!(Dielectric::new((), Dispersion::Constant(-1.5)).is_none());
+        assert!(Dielectric::new((), Dispersion::Cauchy(vec![0., 0.01])).is_some());
+        assert!(Dielectric::new((), Dispersion::Cauchy(vec![-1., 0.01])).is_none());
+    }
+
+    #[test]
+    fn test_refraction() {
+        let slab = Dielectric::new(
+            csg::HalfSpace::<2>::new([0., 0.].into(), [0., 1.].into()).unwrap(),
+            Dispersion::Constant(1.5),
+        )
+        .unwrap();
+
+        let ray = Ray::new([-1., 1.].into(), Unit::new_normalize([1., -1.].into()));
+
+        let mut intersections = vec![];
+        slab.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [tangent] = intersections.as_slice() else {
+            panic!("expected exactly one intersection")
+        };
+
+        let out = tangent
+            .outgoing_ray(&ray, &mut rand::rngs::StdRng::seed_from_u64(0))
+            .unwrap();
+        // Snell's law
+        let sin_t = out.direction.x;
+        assert!((sin_t * 1.5 - (0.5 as Float).sqrt()).abs() < 1e-12);
+        assert!(out.direction.y < 0.);
+        assert!(out.power < 1. && out.power > 0.9);
+
+        // leaving at an angle greater than the critical angle: total internal reflection
+        let ray = Ray::new([-1., -1.].into(), Unit::new_normalize([1., 1.].into()));
+
+        intersections.clear();
+        slab.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let out = intersections[0]
+            .outgoing_ray(&ray, &mut rand::rngs::StdRng::seed_from_u64(0))
+            .unwrap();
+        assert!((out.direction.into_inner() - SVector::from([1., -1.]).normalize()).norm() < 1e-12);
+        assert!((out.power - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_index_not_positive_outside_visible() {
+        use crate::Termination::*;
+
+        // n² = 1 + λ² / (λ² - 0.09) has a pole at 300 nm, and is negative just below it
+        let slab = Dielectric::new(
+            csg::HalfSpace::<2>::new([0., 0.].into(), [-1., 0.].into()).unwrap(),
+            Dispersion::Sellmeier(vec![[1., 0.09]]),
+        )
+        .unwrap();
+
+        let ray = |wavelength| Ray {
+            wavelength: Some(wavelength),
+            ..Ray::new([-1., 0.].into(), Unit::new_normalize([1., 0.].into()))
+        };
+
+        let simulation = crate::Simulation {
+            rays: vec![ray(550.), ray(290.)],
+            mirror: slab,
+            seed: 0,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let paths = simulation.get_ray_paths(10);
+        assert!(paths
+            .iter()
+            .map(crate::RayPath::termination)
+            .eq([Escaped, NonPositiveIndex]));
+
+        // the ray is stopped on the slab, instead of going through it unchanged
+        let last = paths[1].all_points_raw().last().unwrap();
+        assert!(last.norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_coated() {
+        let mirror = Coated::<plane::PlaneMirror<2>>::from_json(&json!({
+            "reflectivity": [[400., 0.2], [700., 0.8]],
+            "mirror": {
+                "center": [0., 0.],
+                "basis": [[1., 0.]],
+            },
+        }))
+        .expect("json error");
+
+        let ray = Ray {
+            wavelength: Some(550.),
+            ..Ray::new([0., 1.].into(), Unit::new_normalize([0., -1.].into()))
+        };
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let out = intersections[0]
+            .outgoing_ray(&ray, &mut rand::rngs::StdRng::seed_from_u64(0))
+            .unwrap();
+        assert!((out.power - 0.5).abs() < 1e-12);
+        assert!((out.direction.into_inner() - SVector::from([0., 1.])).norm() < 1e-12);
+
+        let mirror2 = Coated::<plane::PlaneMirror<2>>::from_json(&mirror.to_json()).unwrap();
+        assert_eq!(mirror, mirror2);
+
+        let mut json = mirror.to_json();
+        json["absorption"] = json!(0.25);
+        assert!(Coated::<plane::PlaneMirror<2>>::from_json(&json).is_err());
+
+        json.as_object_mut().unwrap().remove("reflectivity");
+        let mirror = Coated::<plane::PlaneMirror<2>>::from_json(&json).expect("json error");
+        assert_eq!(mirror.reflectivity(), &Spectrum::Constant(0.75));
+    }
+}
@@ -0,0 +1,897 @@
+use super::*;
+
+use transform::{plane_rotation, Transformed};
+
+/// The maximum number of bisections of the step in which a ray meets a rotating mirror
+const MAX_ITERATIONS: usize = 64;
+
+/// The maximum angle a mirror rotates by, during one of the steps it's
+/// meeting with a ray is looked for in, see [`Moving::append_intersecting_points`]
+const MAX_ANGLE_STEP: Float = 1.0 / 32.0;
+
+/// The maximum number of steps a meeting with a ray is looked for in, mirrors that
+/// keep rotating, or looping, are only hit within a bounded time window
+const MAX_STEPS: usize = 1 << 12;
+
+/// The pose of a moving mirror at a given time, see [`MotionLaw::Keyframes`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe<const D: usize> {
+    pub time: Float,
+    /// The angle the mirror is rotated by, in radians, see [`Motion`]
+    pub angle: Float,
+    pub translation: SVector<Float, D>,
+}
+
+/// A time interval over which a moving mirror rotates, and translates, at constant speeds
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Piece<const D: usize> {
+    start: Float,
+    /// (infinite if the mirror keeps moving that way forever)
+    end: Float,
+    /// The angle at time `start`
+    angle: Float,
+    /// The translation at time `start`
+    translation: SVector<Float, D>,
+    angular_velocity: Float,
+    velocity: SVector<Float, D>,
+}
+
+impl<const D: usize> Piece<D> {
+    /// Returns the angle, and the translation, at time `time`
+    fn pose(&self, time: Float) -> (Float, SVector<Float, D>) {
+        let elapsed = time - self.start;
+        (
+            self.angle + self.angular_velocity * elapsed,
+            self.translation + self.velocity * elapsed,
+        )
+    }
+}
+
+/// How the pose of a moving mirror evolves with time
+#[derive(Clone, Debug, PartialEq)]
+pub enum MotionLaw<const D: usize> {
+    /// Rotation, and translation, at constant speeds: at time `t`, the mirror
+    /// is rotated by `angular_velocity * t`, and translated by `velocity * t`
+    Uniform {
+        angular_velocity: Float,
+        velocity: SVector<Float, D>,
+    },
+    /// The pose is linearly interpolated between keyframes, sorted by increasing time,
+    /// and held still before the first one, and after the last one, unless `repeat`
+    /// is `true`, in which case the motion loops, from the first keyframe to the last one.
+    Keyframes {
+        keyframes: Vec<Keyframe<D>>,
+        repeat: bool,
+    },
+}
+
+impl<const D: usize> MotionLaw<D> {
+    /// Returns the angle, and the translation, at time `time`
+    fn pose(&self, time: Float) -> (Float, SVector<Float, D>) {
+        match self {
+            Self::Uniform {
+                angular_velocity,
+                velocity,
+            } => (angular_velocity * time, velocity * time),
+            Self::Keyframes { keyframes, repeat } => {
+                let (first, last) = (&keyframes[0], &keyframes[keyframes.len() - 1]);
+
+                let time = if *repeat && last.time > first.time {
+                    first.time + (time - first.time).rem_euclid(last.time - first.time)
+                } else {
+                    time
+                };
+
+                match keyframes.iter().position(|k| k.time > time) {
+                    None => (last.angle, last.translation),
+                    Some(0) => (first.angle, first.translation),
+                    Some(i) => {
+                        let (k0, k1) = (&keyframes[i - 1], &keyframes[i]);
+                        let s = (time - k0.time) / (k1.time - k0.time);
+                        (
+                            k0.angle + (k1.angle - k0.angle) * s,
+                            k0.translation.lerp(&k1.translation, s),
+                        )
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the successive pieces of this motion, from time `start` on, the last
+    /// one being unbounded, unless the keyframes loop.
+    fn pieces(&self, start: Float) -> impl Iterator<Item = Piece<D>> + '_ {
+        // the index of the keyframe ending the current piece, (`0` before the first one,
+        // and the number of keyframes after the last one) and the keyframes' time offset
+        let (mut index, mut offset) = match self {
+            Self::Uniform { .. } => (0, 0.0),
+            Self::Keyframes { keyframes, repeat } => {
+                let (first, last) = (&keyframes[0], &keyframes[keyframes.len() - 1]);
+                let period = last.time - first.time;
+
+                if *repeat && period > 0.0 {
+                    let offset = ((start - first.time) / period).floor() * period;
+                    let index = keyframes
+                        .iter()
+                        .position(|k| k.time + offset > start)
+                        .unwrap_or(keyframes.len());
+                    (index.clamp(1, keyframes.len() - 1), offset)
+                } else {
+                    let index = keyframes
+                        .iter()
+                        .position(|k| k.time > start)
+                        .unwrap_or(keyframes.len());
+                    (index, 0.0)
+                }
+            }
+        };
+
+        let mut start = Some(start);
+
+        iter::from_fn(move || {
+            let time = start?;
+
+            let piece = match self {
+                Self::Uniform {
+                    angular_velocity,
+                    velocity,
+                } => {
+                    start = None;
+                    Piece {
+                        start: time,
+                        end: Float::INFINITY,
+                        angle: angular_velocity * time,
+                        translation: velocity * time,
+                        angular_velocity: *angular_velocity,
+                        velocity: *velocity,
+                    }
+                }
+                Self::Keyframes { keyframes, repeat } => {
+                    let count = keyframes.len();
+
+                    let piece = if index == 0 || index == count {
+                        // held still
+                        let k = &keyframes[index.min(count - 1)];
+                        Piece {
+                            start: time,
+                            end: if index == 0 { k.time } else { Float::INFINITY },
+                            angle: k.angle,
+                            translation: k.translation,
+                            angular_velocity: 0.0,
+                            velocity: SVector::zeros(),
+                        }
+                    } else {
+                        let (k0, k1) = (&keyframes[index - 1], &keyframes[index]);
+                        let duration = k1.time - k0.time;
+
+                        Piece {
+                            start: k0.time + offset,
+                            end: k1.time + offset,
+                            angle: k0.angle,
+                            translation: k0.translation,
+                            angular_velocity: (k1.angle - k0.angle) / duration,
+                            velocity: (k1.translation - k0.translation) / duration,
+                        }
+                    };
+
+                    if index == count {
+                        start = None;
+                    } else if *repeat && index == count - 1 {
+                        // loop back to the first keyframe
+                        offset += keyframes[count - 1].time - keyframes[0].time;
+                        index = 1;
+                        start = Some(piece.end);
+                    } else {
+                        index += 1;
+                        start = Some(piece.end);
+                    }
+
+                    // start the piece at `time`
+                    let (angle, translation) = piece.pose(time);
+                    Piece {
+                        start: time,
+                        angle,
+                        translation,
+                        ..piece
+                    }
+                }
+            };
+
+            Some(piece)
+        })
+    }
+
+    /// Returns `true` if the mirror ever rotates
+    fn rotates(&self) -> bool {
+        match self {
+            Self::Uniform {
+                angular_velocity, ..
+            } => *angular_velocity != 0.0,
+            Self::Keyframes { keyframes, .. } => keyframes.iter().any(|k| k.angle != 0.0),
+        }
+    }
+}
+
+/// The motion of a mirror: at time `t`, it is rotated by some angle, around `pivot`, in the
+/// plane spanned by `plane`, (from the first vector towards the second one, see
+/// [`transform::plane_rotation`]) then translated, both given by a [`MotionLaw`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Motion<const D: usize> {
+    pivot: SVector<Float, D>,
+    plane: [SVector<Float, D>; 2],
+    law: MotionLaw<D>,
+}
+
+impl<const D: usize> Motion<D> {
+    /// Returns `None` if `law` rotates and the vectors of `plane` aren't orthonormal, or if
+    /// `law` has no keyframes, or keyframes whose times aren't increasing. A mirror that never
+    /// rotates ignores `plane`, so any plane will do, even in dimension `1`.
+    pub fn new(
+        pivot: SVector<Float, D>,
+        plane: [SVector<Float, D>; 2],
+        law: MotionLaw<D>,
+    ) -> Option<Self> {
+        const E: Float = 1e-9;
+
+        let [u, v] = &plane;
+        let orthonormal = !law.rotates()
+            || (u.norm() - 1.0).abs() < E && (v.norm() - 1.0).abs() < E && u.dot(v).abs() < E;
+
+        let valid_law = match &law {
+            MotionLaw::Uniform { .. } => true,
+            MotionLaw::Keyframes { keyframes, .. } => {
+                !keyframes.is_empty() && keyframes.windows(2).all(|w| w[0].time < w[1].time)
+            }
+        };
+
+        (orthonormal && valid_law).then_some(Self { pivot, plane, law })
+    }
+
+    pub fn pivot(&self) -> &SVector<Float, D> {
+        &self.pivot
+    }
+
+    pub fn plane(&self) -> &[SVector<Float, D>; 2] {
+        &self.plane
+    }
+
+    pub fn law(&self) -> &MotionLaw<D> {
+        &self.law
+    }
+
+    /// Returns `inner`, moved to it's pose at time `time`
+    pub fn transform_at<T>(&self, inner: T, time: Float) -> Transformed<T, D> {
+        let (angle, translation) = self.law.pose(time);
+        self.transform(inner, angle, translation)
+    }
+
+    /// Returns `inner`, rotated by `angle`, then translated by `translation`
+    fn transform<T>(
+        &self,
+        inner: T,
+        angle: Float,
+        translation: SVector<Float, D>,
+    ) -> Transformed<T, D> {
+        let [u, v] = &self.plane;
+        let rotation = plane_rotation(u, v, angle);
+
+        // SAFETY: plane rotations are orthogonal
+        Transformed::new(
+            inner,
+            rotation,
+            self.pivot - rotation * self.pivot + translation,
+            1.0,
+        )
+        .unwrap()
+    }
+}
+
+impl<const D: usize> Random for Motion<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        let basis = util::basis_from_vector(&util::rand_unit_vect(rng));
+
+        // there is no rotation in dimension `1`, the mirror is only translated
+        let (plane, angular_velocity) = match basis[..] {
+            [u, v, ..] => ([u, v], rng.gen_range(-0.1..0.1)),
+            _ => ([SVector::zeros(); 2], 0.0),
+        };
+
+        let law = MotionLaw::Uniform {
+            angular_velocity,
+            velocity: util::rand_vect(rng, 0.05),
+        };
+
+        // SAFETY: the plane is orthonormal, or the mirror never rotates,
+        // and uniform motions are always valid
+        Self::new(util::rand_vect(rng, 5.0), plane, law).unwrap()
+    }
+}
+
+/// A mirror moving with time: it is hit by a ray at the time, and in the pose, at which the
+/// ray, travelling at unit speed, (see [`Ray::time`]) meets it. Mirrors must move slower than
+/// rays for that to be well-defined. Mirrors that keep rotating, or whose keyframes loop, are
+/// only hit within a bounded time window, after the ray's time, see [`MAX_STEPS`].
+///
+/// Along circular arcs, (see [`crate::Propagation`]) and for it's refractive index, (see
+/// [`Mirror::refractive_index_at`]) the mirror is considered in it's pose at time `0`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Moving<T, const D: usize> {
+    inner: T,
+    motion: Motion<D>,
+}
+
+impl<T, const D: usize> Moving<T, D> {
+    pub fn new(inner: T, motion: Motion<D>) -> Self {
+        Self { inner, motion }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn motion(&self) -> &Motion<D> {
+        &self.motion
+    }
+
+    /// Returns this mirror, frozen in it's pose at time `time`
+    pub fn at_time(&self, time: Float) -> Transformed<&T, D> {
+        self.motion.transform_at(&self.inner, time)
+    }
+}
+
+impl<const D: usize, T: Mirror<D>> Moving<T, D> {
+    /// Returns the distance, in `(lo ; hi]`, at which `ray` meets this mirror, while it moves as
+    /// described by `piece`, if it does. The path of the ray, in the frame of the mirror, is
+    /// approximated by it's chord, which is exact if the mirror doesn't rotate.
+    fn chord_crossing(
+        &self,
+        ray: &Ray<D>,
+        piece: &Piece<D>,
+        lo: Float,
+        hi: Float,
+    ) -> Option<Float> {
+        // the point the ray is at, in the frame of the mirror, after travelling a distance `d`
+        let local = |d: Float| {
+            let (angle, translation) = piece.pose(ray.time + d);
+            self.motion
+                .transform((), angle, translation)
+                .point_to_local(&ray.at(d))
+        };
+
+        let origin = local(lo);
+
+        // the displacement of that point, per unit of distance travelled by the ray
+        let velocity = if hi.is_finite() {
+            (local(hi) - origin) / (hi - lo)
+        } else {
+            local(lo + 1.0) - origin
+        };
+
+        let speed = velocity.norm();
+        let chord = Ray {
+            origin,
+            direction: Unit::try_new(velocity, Float::EPSILON)?,
+            ..*ray
+        };
+
+        let mut hits = vec![];
+        self.inner
+            .append_intersecting_points(&chord, List::from(&mut hits));
+
+        hits.iter()
+            .filter_map(|tangent| {
+                let d = lo + tangent.try_ray_intersection(&chord)? / speed;
+                (d > lo && d <= hi && d > Float::EPSILON * 64.0).then_some(d)
+            })
+            .min_by(Float::total_cmp)
+    }
+
+    /// Returns the surface `ray` meets this mirror at, after travelling a distance in
+    /// `(lo ; hi]`, while it moves as described by `piece`, if it does.
+    fn meeting(
+        &self,
+        ray: &Ray<D>,
+        piece: &Piece<D>,
+        lo: Float,
+        hi: Float,
+    ) -> Option<TangentPlane<D>> {
+        let mut distance = self.chord_crossing(ray, piece, lo, hi)?;
+
+        if piece.angular_velocity != 0.0 {
+            // the path of the ray in the frame of the mirror is curved,
+            // narrow down the part of it that crosses the mirror
+            let (mut lo, mut hi) = (lo, hi);
+
+            for _ in 0..MAX_ITERATIONS {
+                if hi - lo <= 1e-12 * (1.0 + hi) {
+                    break;
+                }
+
+                let mid = (lo + hi) * 0.5;
+
+                match self.chord_crossing(ray, piece, lo, mid) {
+                    Some(d) => {
+                        distance = d;
+                        hi = mid;
+                    }
+                    None => lo = mid,
+                }
+            }
+
+            distance = distance.clamp(lo, hi);
+        }
+
+        // the mirror, frozen in it's pose at the time the ray meets it, is hit there
+        let (angle, translation) = piece.pose(ray.time + distance);
+        let mut hits = vec![];
+        self.motion
+            .transform(&self.inner, angle, translation)
+            .append_intersecting_points(ray, List::from(&mut hits));
+
+        hits.into_iter()
+            .filter_map(|tangent| {
+                let error = (tangent.try_ray_intersection(ray)? - distance).abs();
+                (error <= 1e-9 * (1.0 + distance)).then_some((error, tangent))
+            })
+            .min_by(|(e1, _), (e2, _)| e1.total_cmp(e2))
+            .map(|(_, tangent)| tangent)
+    }
+}
+
+impl<const D: usize, T: Mirror<D>> Mirror<D> for Moving<T, D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        // the mirror moves while the ray travels: follow the ray's path in the frame of the
+        // mirror, piece by piece of it's motion, in steps where it is (close to) a straight line,
+        // up to the first one where it crosses the mirror
+        let mut steps = 0;
+
+        for piece in self.motion.law.pieces(ray.time) {
+            let (start, end) = (piece.start - ray.time, piece.end - ray.time);
+
+            let step = if piece.angular_velocity == 0.0 {
+                Float::INFINITY
+            } else {
+                MAX_ANGLE_STEP / piece.angular_velocity.abs()
+            };
+
+            let mut lo = start;
+
+            while lo < end {
+                if steps == MAX_STEPS {
+                    return;
+                }
+                steps += 1;
+
+                let hi = (lo + step).min(end);
+
+                if let Some(tangent) = self.meeting(ray, &piece, lo, hi) {
+                    list.push(tangent);
+                    return;
+                }
+
+                lo = hi;
+            }
+        }
+    }
+
+    fn refractive_index_at(&self, p: &SVector<Float, D>) -> Option<(Float, SVector<Float, D>)> {
+        self.at_time(0.0).refractive_index_at(p)
+    }
+
+    fn append_arc_intersections(&self, arc: &arc::Arc<D>, list: List<(Float, TangentPlane<D>)>) {
+        self.at_time(0.0).append_arc_intersections(arc, list)
+    }
+}
+
+impl<T, const D: usize> JsonType for Moving<T, D> {
+    fn json_type() -> String {
+        "moving".into()
+    }
+}
+
+impl<T: JsonDes, const D: usize> JsonDes for Moving<T, D> {
+    /// Deserialize a new moving mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "pivot": [1., 2., 3., ...], // (optional, the point the mirror rotates around, defaults to zero)
+    ///     "plane": [[1., 0., 0., ...], [0., 1., 0., ...]], // (the plane the mirror rotates in, optional
+    ///                                                      // in 2D, or if the mirror doesn't rotate)
+    ///     // or, only in 3D, the axis to rotate around, counterclockwise:
+    ///     "axis": [0., 0., 1.],
+    ///     "angular_velocity": 0.1, // (optional, in radians per unit of time, defaults to 0)
+    ///     "velocity": [1., 2., 3., ...], // (optional, an array of D floats, defaults to zero)
+    ///     // or, instead of the velocities:
+    ///     "keyframes": [ // (at least one, with increasing times)
+    ///         {
+    ///             "time": 0.,
+    ///             "angle": 0.5, // (optional, in radians, defaults to 0)
+    ///             "translation": [1., 2., 3., ...], // (optional, defaults to zero)
+    ///         },
+    ///         ...
+    ///     ],
+    ///     "repeat": true, // (optional, whether the keyframes loop, defaults to false)
+    ///     "mirror": // <the inner mirror's layout>
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let vector = |value: &serde_json::Value, name: &str| {
+            value
+                .as_array()
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector)
+                .ok_or(format!("Failed to parse {name}"))
+        };
+
+        let pivot = match json.get("pivot") {
+            Some(value) => vector(value, "pivot")?,
+            None => SVector::zeros(),
+        };
+
+        let law = if let Some(keyframes) = json.get("keyframes") {
+            let keyframes = util::map_json_array(keyframes, |keyframe| {
+                let time = keyframe
+                    .get("time")
+                    .and_then(serde_json::Value::as_f64)
+                    .ok_or("Missing or invalid keyframe time")?;
+
+                let angle = match keyframe.get("angle") {
+                    Some(value) => value.as_f64().ok_or("Failed to parse keyframe angle")?,
+                    None => 0.0,
+                };
+
+                let translation = match keyframe.get("translation") {
+                    Some(value) => vector(value, "keyframe translation")?,
+                    None => SVector::zeros(),
+                };
+
+                Ok(Keyframe {
+                    time,
+                    angle,
+                    translation,
+                })
+            })?;
+
+            let repeat = match json.get("repeat") {
+                Some(value) => value.as_bool().ok_or("repeat must be a boolean")?,
+                None => false,
+            };
+
+            MotionLaw::Keyframes { keyframes, repeat }
+        } else {
+            let angular_velocity = match json.get("angular_velocity") {
+                Some(value) => value.as_f64().ok_or("Failed to parse angular velocity")?,
+                None => 0.0,
+            };
+
+            let velocity = match json.get("velocity") {
+                Some(value) => vector(value, "velocity")?,
+                None => SVector::zeros(),
+            };
+
+            MotionLaw::Uniform {
+                angular_velocity,
+                velocity,
+            }
+        };
+
+        let plane =
+            if D == 2 || law.rotates() || json.get("plane").is_some() || json.get("axis").is_some()
+            {
+                transform::rotation_plane_from_json(json)?
+            } else {
+                // the mirror never rotates, any plane will do
+                [0, 1].map(|k| SVector::from_fn(|i, _| if i == k { 1.0 } else { 0.0 }))
+            };
+
+        let motion = Motion::new(pivot, plane, law)
+            .ok_or("keyframes must be non-empty, with increasing times")?;
+
+        let inner = T::from_json(json.get("mirror").ok_or("Missing inner mirror")?)?;
+
+        Ok(Self::new(inner, motion))
+    }
+}
+
+impl<T: JsonSer, const D: usize> JsonSer for Moving<T, D> {
+    /// Serialize a moving mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`],
+    /// the rotation plane is always written as a pair of vectors.
+    fn to_json(&self) -> serde_json::Value {
+        let Motion { pivot, plane, law } = &self.motion;
+
+        let mut json = serde_json::json!({
+            "pivot": pivot.as_slice(),
+            "plane": plane.each_ref().map(|v| v.as_slice()),
+            "mirror": self.inner.to_json(),
+        });
+
+        match law {
+            MotionLaw::Uniform {
+                angular_velocity,
+                velocity,
+            } => {
+                json["angular_velocity"] = (*angular_velocity).into();
+                json["velocity"] = velocity.as_slice().into();
+            }
+            MotionLaw::Keyframes { keyframes, repeat } => {
+                json["keyframes"] = Vec::from_iter(keyframes.iter().map(|keyframe| {
+                    serde_json::json!({
+                        "time": keyframe.time,
+                        "angle": keyframe.angle,
+                        "translation": keyframe.translation.as_slice(),
+                    })
+                }))
+                .into();
+                json["repeat"] = (*repeat).into();
+            }
+        }
+
+        json
+    }
+}
+
+/// Render data moving with a mirror, see [`render::RenderData::model_at`]
+struct MovingRenderData<const D: usize> {
+    inner: Box<dyn render::RenderData>,
+    motion: Motion<D>,
+}
+
+impl<const D: usize> render::RenderData for MovingRenderData<D> {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        self.inner.vertices()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        self.inner.indices()
+    }
+
+    fn color(&self) -> Option<[f32; 4]> {
+        self.inner.color()
+    }
+
+    fn model(&self) -> [[f32; 4]; 4] {
+        self.model_at(0.0)
+    }
+
+    fn model_at(&self, time: Float) -> [[f32; 4]; 4] {
+        let outer = self.motion.transform_at((), time).model_matrix();
+        let [outer, inner] = [outer, self.inner.model_at(time)].map(nalgebra::Matrix4::from);
+        (outer * inner).into()
+    }
+}
+
+impl<T: render::OpenGLRenderable, const D: usize> Moving<T, D> {
+    fn append_moving_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let mut inner = vec![];
+        self.inner
+            .append_render_data(display, List::from(&mut inner));
+
+        list.extend(inner.into_iter().map(|inner| {
+            Box::new(MovingRenderData {
+                inner,
+                motion: self.motion.clone(),
+            }) as Box<dyn render::RenderData>
+        }));
+    }
+}
+
+impl<T: render::OpenGLRenderable> render::OpenGLRenderable for Moving<T, 2> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.append_moving_render_data(display, list)
+    }
+}
+
+impl<T: render::OpenGLRenderable> render::OpenGLRenderable for Moving<T, 3> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.append_moving_render_data(display, list)
+    }
+}
+
+impl<T: Random, const D: usize> Random for Moving<T, D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        Self::new(T::random(rng), Motion::random(rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::f64::consts::FRAC_PI_2;
+    use serde_json::json;
+
+    fn ray(origin: [Float; 2], direction: [Float; 2]) -> Ray<2> {
+        Ray::new(origin.into(), Unit::new_normalize(direction.into()))
+    }
+
+    /// The vertical segment x = `x`, y in [-1 ; 1], moving as described by `motion`
+    fn moving_segment(x: Float, motion: serde_json::Value) -> Moving<plane::PlaneMirror<2>, 2> {
+        let mut json = motion;
+        json["mirror"] = json!({ "center": [x, 0.], "basis": [[0., 1.]] });
+        Moving::from_json(&json).expect("json error")
+    }
+
+    fn first_hit(mirror: &Moving<plane::PlaneMirror<2>, 2>, ray: &Ray<2>) -> SVector<Float, 2> {
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(ray, List::from(&mut intersections));
+        assert_eq!(intersections.len(), 1);
+        ray.at(intersections[0].try_ray_intersection(ray).unwrap())
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let mirror = moving_segment(
+            0.,
+            json!({
+                "keyframes": [
+                    { "time": 1., "angle": 1., "translation": [0., 2.] },
+                    { "time": 3., "angle": -1. },
+                ],
+            }),
+        );
+        let law = mirror.motion().law();
+
+        assert_eq!(law.pose(0.), (1., [0., 2.].into()));
+        assert_eq!(law.pose(2.), (0., [0., 1.].into()));
+        assert_eq!(law.pose(5.), (-1., [0., 0.].into()));
+
+        let json = mirror.to_json();
+        assert_eq!(Moving::from_json(&json).ok().as_ref(), Some(&mirror));
+
+        // repeating: at the end of each period, the mirror jumps back to it's first pose
+        let mut json = json;
+        json["repeat"] = true.into();
+        let looping = Moving::<plane::PlaneMirror<2>, 2>::from_json(&json).unwrap();
+        assert_eq!(looping.motion().law().pose(4.), (0., [0., 1.].into()));
+
+        assert!(Moving::<plane::PlaneMirror<2>, 2>::from_json(&json!({
+            "keyframes": [{ "time": 1. }, { "time": 1. }],
+            "mirror": { "center": [0., 0.], "basis": [[0., 1.]] },
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_translating_mirror() {
+        // the mirror moves towards the ray, at half it's speed
+        let mirror = moving_segment(10., json!({ "velocity": [-0.5, 0.] }));
+
+        let hit = first_hit(&mirror, &ray([0., 0.], [1., 0.]));
+        assert!((hit.x - 20. / 3.).abs() < 1e-9);
+
+        // later on, the mirror is closer
+        let late_ray = Ray {
+            time: 4.,
+            ..ray([0., 0.], [1., 0.])
+        };
+        let hit = first_hit(&mirror, &late_ray);
+        assert!((hit.x - 16. / 3.).abs() < 1e-9);
+
+        // rays keep track of the time they reach mirrors at
+        let simulation = crate::Simulation {
+            rays: vec![ray([0., 0.], [1., 0.])],
+            mirror,
+            seed: 0,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let path = simulation.get_ray_paths(1).remove(0);
+        let final_ray = path.final_ray().unwrap();
+        assert!((final_ray.time - 20. / 3.).abs() < 1e-9);
+        assert!((final_ray.direction.x + 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mirror_moving_into_the_ray() {
+        // the segment x = 5, y in [2 ; 4], slides down, it is across the ray from time 10 / 3
+        let mirror = Moving::<plane::PlaneMirror<2>, 2>::from_json(&json!({
+            "velocity": [0., -0.6],
+            "mirror": { "center": [5., 3.], "basis": [[0., 1.]] },
+        }))
+        .expect("json error");
+
+        let ray = ray([0., 0.], [1., 0.]);
+
+        let mut intersections = vec![];
+        mirror
+            .at_time(0.)
+            .append_intersecting_points(&ray, List::from(&mut intersections));
+        assert!(intersections.is_empty());
+
+        let hit = first_hit(&mirror, &ray);
+        assert!((hit - SVector::from([5., 0.])).norm() < 1e-9);
+
+        // it slides out of the way again, after time 20 / 3
+        let late_ray = Ray { time: 2., ..ray };
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&late_ray, List::from(&mut intersections));
+        assert!(intersections.is_empty());
+
+        // keyframes: the segment is only across the ray between times 4 and 6, and loops
+        let mirror = moving_segment(
+            5.,
+            json!({
+                "keyframes": [
+                    { "time": 0., "translation": [0., 3.] },
+                    { "time": 4., "translation": [0., 0.5] },
+                    { "time": 6., "translation": [0., -0.5] },
+                    { "time": 10., "translation": [0., -3.] },
+                ],
+                "repeat": true,
+            }),
+        );
+
+        let hit = first_hit(&mirror, &ray);
+        assert!((hit - SVector::from([5., 0.])).norm() < 1e-9);
+
+        // the ray reaches x = 5 at time 8, the segment is below it then
+        let late_ray = Ray { time: 3., ..ray };
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&late_ray, List::from(&mut intersections));
+        assert!(intersections.is_empty());
+
+        // and at time 15, during the next period, the segment is across it again
+        let later_ray = Ray { time: 10., ..ray };
+        let hit = first_hit(&mirror, &later_ray);
+        assert!((hit - SVector::from([5., 0.])).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_rotating_mirror() {
+        // the mirror spins around it's center, which the ray reaches at time 5
+        let angular_velocity: Float = 0.05;
+        let mirror = moving_segment(
+            5.,
+            json!({ "pivot": [5., 0.], "angular_velocity": angular_velocity }),
+        );
+
+        let simulation = crate::Simulation {
+            rays: vec![ray([0., 0.], [1., 0.])],
+            mirror,
+            seed: 0,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let path = simulation.get_ray_paths(1).remove(0);
+        let final_ray = path.final_ray().unwrap();
+        assert!((final_ray.origin - SVector::from([5., 0.])).norm() < 1e-9);
+
+        // the ray is reflected by a mirror rotated by `θ = 5 * angular_velocity`
+        let theta = 5. * angular_velocity;
+        let expected = SVector::from([-(2. * theta).cos(), -(2. * theta).sin()]);
+        assert!((final_ray.direction.as_ref() - expected).norm() < 1e-9);
+
+        // a quarter turn later, the mirror is horizontal: a ray hitting it then goes back up
+        let late_ray = Ray {
+            time: FRAC_PI_2 / angular_velocity - 5.,
+            ..ray([5., 5.], [0., -1.])
+        };
+        let hit = first_hit(&simulation.mirror, &late_ray);
+        assert!((hit - SVector::from([5., 0.])).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_random_motion_in_1d() {
+        use rand::SeedableRng;
+
+        // there are no rotations in dimension `1`, random motions are translations
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let motion = Motion::<1>::random(&mut rng);
+        assert!(!motion.law().rotates());
+
+        let json =
+            json!({ "pivot": [0.], "velocity": [1.], "mirror": { "center": [0.], "basis": [] } });
+        assert!(Moving::<plane::PlaneMirror<1>, 1>::from_json(&json).is_ok());
+    }
+}
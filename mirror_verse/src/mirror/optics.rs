@@ -0,0 +1,361 @@
+use super::*;
+
+/// Returns the direction of a ray, going in direction `d`, after it goes through a thin
+/// lens whose tangent hyperplane has direction `tangent`, see [`Surface::Lens`].
+///
+/// Parallel rays meet at the same point of the focal plane (this is an ideal thin lens).
+pub fn deflect<const D: usize>(
+    d: &Unit<SVector<Float, D>>,
+    tangent: &TangentSpace<D>,
+    deflection: &SVector<Float, D>,
+) -> Unit<SVector<Float, D>> {
+    let d = d.into_inner();
+    let cos = ((d - tangent.reflect(d)) * 0.5).norm();
+
+    if cos <= Float::EPSILON {
+        // the ray only grazes the lens
+        return Unit::new_normalize(d);
+    }
+
+    // scale the direction so it's normal component has unit length, the point
+    // it reaches one focal length after the lens then only depends on `d`
+    Unit::new_normalize(d / cos + deflection)
+}
+
+/// An ideal thin lens, with the shape of a [`plane::PlaneMirror`], whose center is the optical center.
+///
+/// Rays parallel to each other meet on the focal plane, at a distance `focal_length` from the lens.
+/// Lenses with a negative focal length make rays diverge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThinLens<const D: usize> {
+    plane: plane::PlaneMirror<D>,
+    focal_length: Float,
+}
+
+impl<const D: usize> ThinLens<D> {
+    /// Returns `None` if `focal_length` is too close to zero, or not finite.
+    pub fn new(plane: plane::PlaneMirror<D>, focal_length: Float) -> Option<Self> {
+        (focal_length.is_finite() && focal_length.abs() > Float::EPSILON * 8.0).then_some(Self {
+            plane,
+            focal_length,
+        })
+    }
+
+    pub fn plane(&self) -> &plane::PlaneMirror<D> {
+        &self.plane
+    }
+
+    pub fn focal_length(&self) -> Float {
+        self.focal_length
+    }
+}
+
+impl<const D: usize> Mirror<D> for ThinLens<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let center = self.plane.inner_plane().v0();
+
+        list.extend(self.plane.intersection(ray).and_then(|tangent| {
+            let offset = ray.at(tangent.try_ray_intersection(ray)?) - center;
+
+            Some(TangentPlane {
+                surface: Surface::Lens {
+                    deflection: -offset / self.focal_length,
+                },
+                ..tangent
+            })
+        }));
+    }
+}
+
+impl<const D: usize> JsonType for ThinLens<D> {
+    fn json_type() -> String {
+        "thin_lens".into()
+    }
+}
+
+impl<const D: usize> JsonDes for ThinLens<D> {
+    /// Deserialize a new thin lens from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "center": [1., 2., 3., ...], // (an array of D floats, the optical center)
+    ///     "basis": [ // (an array of D - 1 arrays of D floats, see `PlaneMirror`)
+    ///         [1., 0., 0., ...],
+    ///         [0., 1., 0., ...],
+    ///         ...
+    ///     ],
+    ///     "focal_length": 5., // (non-zero, negative for diverging lenses)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let plane = plane::PlaneMirror::from_json(json)?;
+
+        let focal_length = json
+            .get("focal_length")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse focal length")? as Float;
+
+        Self::new(plane, focal_length).ok_or("focal length must be non-zero".into())
+    }
+}
+
+impl<const D: usize> JsonSer for ThinLens<D> {
+    /// Serialize a thin lens into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = self.plane.to_json();
+        json["focal_length"] = self.focal_length.into();
+        json
+    }
+}
+
+impl render::OpenGLRenderable for ThinLens<2> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.plane.append_render_data(display, list)
+    }
+}
+
+impl render::OpenGLRenderable for ThinLens<3> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.plane.append_render_data(display, list)
+    }
+}
+
+impl<const D: usize> Random for ThinLens<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        let focal_length = rng.gen_range(1.0..20.0) * if rng.gen() { 1.0 } else { -1.0 };
+
+        // SAFETY: the focal length is non-zero
+        Self::new(plane::PlaneMirror::random(rng), focal_length).unwrap()
+    }
+}
+
+/// A beam splitter, with the shape of a [`plane::PlaneMirror`]: rays hitting it are
+/// split into a reflected one, carrying a fraction `reflectance` of their power, and
+/// a transmitted one, carrying the rest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeamSplitter<const D: usize> {
+    plane: plane::PlaneMirror<D>,
+    reflectance: Float,
+}
+
+impl<const D: usize> BeamSplitter<D> {
+    /// Returns `None` if `reflectance` is outside of `[0 ; 1]`
+    pub fn new(plane: plane::PlaneMirror<D>, reflectance: Float) -> Option<Self> {
+        (0.0..=1.0)
+            .contains(&reflectance)
+            .then_some(Self { plane, reflectance })
+    }
+
+    pub fn plane(&self) -> &plane::PlaneMirror<D> {
+        &self.plane
+    }
+
+    pub fn reflectance(&self) -> Float {
+        self.reflectance
+    }
+}
+
+impl<const D: usize> Mirror<D> for BeamSplitter<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        list.extend(self.plane.intersection(ray).map(|tangent| TangentPlane {
+            surface: Surface::BeamSplitter {
+                reflectance: self.reflectance,
+            },
+            ..tangent
+        }));
+    }
+}
+
+impl<const D: usize> JsonType for BeamSplitter<D> {
+    fn json_type() -> String {
+        "beam_splitter".into()
+    }
+}
+
+impl<const D: usize> JsonDes for BeamSplitter<D> {
+    /// Deserialize a new beam splitter from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "center": [1., 2., 3., ...], // (an array of D floats)
+    ///     "basis": [ // (an array of D - 1 arrays of D floats, see `PlaneMirror`)
+    ///         [1., 0., 0., ...],
+    ///         [0., 1., 0., ...],
+    ///         ...
+    ///     ],
+    ///     "reflectance": 0.5, // (optional, in [0 ; 1], defaults to 0.5)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let plane = plane::PlaneMirror::from_json(json)?;
+
+        let reflectance = json
+            .get("reflectance")
+            .map(|reflectance| reflectance.as_f64().ok_or("Failed to parse reflectance"))
+            .transpose()?
+            .unwrap_or(0.5);
+
+        Self::new(plane, reflectance).ok_or("reflectance must be in [0 ; 1]".into())
+    }
+}
+
+impl<const D: usize> JsonSer for BeamSplitter<D> {
+    /// Serialize a beam splitter into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = self.plane.to_json();
+        json["reflectance"] = self.reflectance.into();
+        json
+    }
+}
+
+impl render::OpenGLRenderable for BeamSplitter<2> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.plane.append_render_data(display, list)
+    }
+}
+
+impl render::OpenGLRenderable for BeamSplitter<3> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.plane.append_render_data(display, list)
+    }
+}
+
+impl<const D: usize> Random for BeamSplitter<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        // SAFETY: the reflectance is in [0 ; 1]
+        Self::new(plane::PlaneMirror::random(rng), rng.gen()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    fn outgoing_rays<const D: usize>(mirror: &impl Mirror<D>, ray: &Ray<D>) -> Vec<Ray<D>> {
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(ray, List::from(&mut intersections));
+
+        let [tangent] = intersections.as_slice() else {
+            panic!("expected exactly one intersection")
+        };
+
+        let mut rays = vec![];
+        tangent.append_outgoing_rays(
+            &Ray {
+                origin: ray.at(tangent.try_ray_intersection(ray).unwrap()),
+                ..*ray
+            },
+            &mut rand::rngs::StdRng::seed_from_u64(0),
+            List::from(&mut rays),
+        );
+        rays
+    }
+
+    #[test]
+    fn test_thin_lens() {
+        let lens = ThinLens::<3>::from_json(&json!({
+            "center": [0., 0., 0.],
+            "basis": [[2., 0., 0.], [0., 2., 0.]],
+            "focal_length": 3.,
+        }))
+        .expect("json error");
+
+        for direction in [[0., 0., 1.], [0.2, -0.1, 1.]] {
+            let direction = Unit::new_normalize(SVector::from(direction));
+            // parallel rays meet on the focal plane, on the ray going through the center
+            let focus = direction.as_ref() * (3. / direction.z);
+
+            for [x, y] in [[0., 0.], [1., 0.], [-0.5, 1.5], [1.2, -0.7]] {
+                let origin = SVector::from([x, y, 0.]) - direction.as_ref() / direction.z;
+                let [out] = outgoing_rays(&lens, &Ray::new(origin, direction))[..] else {
+                    panic!("expected exactly one outgoing ray")
+                };
+
+                let t = (3. - out.origin.z) / out.direction.z;
+                assert!((out.at(t) - focus).norm() < 1e-12);
+                assert_eq!(out.power, 1.);
+            }
+        }
+
+        let lens2 = ThinLens::<3>::from_json(&lens.to_json()).unwrap();
+        assert_eq!(lens, lens2);
+    }
+
+    #[test]
+    fn test_beam_splitter() {
+        let splitter = BeamSplitter::<2>::from_json(&json!({
+            "center": [0., 0.],
+            "basis": [[1., 1.]],
+            "reflectance": 0.3,
+        }))
+        .expect("json error");
+
+        let ray = Ray::new([-1., 0.].into(), Unit::new_normalize([1., 0.].into()));
+
+        let [reflected, transmitted] = outgoing_rays(&splitter, &ray)[..] else {
+            panic!("expected exactly two outgoing rays")
+        };
+
+        assert!((reflected.direction.into_inner() - SVector::from([0., 1.])).norm() < 1e-12);
+        assert!((reflected.power - 0.3).abs() < 1e-12);
+        assert_eq!(transmitted.direction, ray.direction);
+        assert!((transmitted.power - 0.7).abs() < 1e-12);
+
+        let splitter2 = BeamSplitter::<2>::from_json(&splitter.to_json()).unwrap();
+        assert_eq!(splitter, splitter2);
+    }
+
+    #[test]
+    fn test_branching_paths() {
+        // a Michelson-like layout: a splitter, and two mirrors sending both halves back to it
+        let plane = |center: [Float; 2], basis: [Float; 2]| {
+            plane::PlaneMirror::try_new([center.into(), basis.into()]).unwrap()
+        };
+
+        let simulation = crate::Simulation {
+            rays: vec![Ray::new(
+                [-2., 0.].into(),
+                Unit::new_normalize([1., 0.].into()),
+            )],
+            mirror: vec![
+                Box::new(BeamSplitter::new(plane([0., 0.], [1., 1.]), 0.5).unwrap())
+                    as Box<dyn Mirror<2>>,
+                Box::new(plane([3., 0.], [0., 1.])),
+                Box::new(plane([0., 3.], [1., 0.])),
+            ],
+            seed: 0,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let [path] = &simulation.get_ray_paths(10)[..] else {
+            panic!("expected exactly one path")
+        };
+
+        // the splitter is hit three times: the ray is split there, and so are
+        // the two returning halves, four rays then leave the interferometer
+        let paths = Vec::from_iter(path.all_branches());
+
+        assert_eq!(paths.len(), 4);
+        assert!(paths
+            .iter()
+            .all(|path| path.divergence_direction().is_some()));
+
+        let total_power: Float = paths
+            .iter()
+            .map(|path| path.final_ray().unwrap().power)
+            .sum();
+        assert!((total_power - 1.).abs() < 1e-12);
+    }
+}
@@ -19,6 +19,32 @@ impl<const D: usize> PlaneMirror<D> {
     pub fn inner_plane(&self) -> &AffineHyperPlane<D> {
         &self.plane
     }
+
+    /// The plane this mirror belongs to, represented with an orthonormal basis
+    pub fn orthonormalised(&self) -> &AffineHyperPlaneOrtho<D> {
+        &self.orthonormalised
+    }
+
+    /// Returns the tangent plane where `ray` hits this mirror, if it does
+    pub fn intersection(&self, ray: &Ray<D>) -> Option<TangentPlane<D>> {
+        let p = self.inner_plane();
+
+        let intersection_coords = p.intersection_coordinates(ray, p.v0());
+
+        intersection_coords.as_ref().and_then(|v| {
+            let (distance, plane_coords) = v.as_slice().split_first().unwrap();
+            plane_coords
+                .iter()
+                .all(|mu| mu.abs() < 1.0)
+                .then_some(TangentPlane {
+                    // We could return `self.plane.v0()`, but since we already calculated `t`,
+                    // we might as well save the simulation runner some work, and return that
+                    intersection: Intersection::Distance(*distance),
+                    direction: TangentSpace::Plane(self.orthonormalised),
+                    surface: Surface::Reflective,
+                })
+        })
+    }
 }
 
 impl<const D: usize> TryFrom<[SVector<Float, D>; D]> for PlaneMirror<D> {
@@ -54,24 +80,31 @@ impl<const D: usize> PlaneMirror<D> {
 
 impl<const D: usize> Mirror<D> for PlaneMirror<D> {
     fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
-        let p = self.inner_plane();
-
-        let intersection_coords = p.intersection_coordinates(ray, p.v0());
+        list.extend(self.intersection(ray));
+    }
 
-        if let Some(&t) = intersection_coords.as_ref().and_then(|v| {
-            let (distance, plane_coords) = v.as_slice().split_first().unwrap();
-            plane_coords
-                .iter()
-                .all(|mu| mu.abs() < 1.0)
-                .then_some(distance)
-        }) {
-            list.push(TangentPlane {
-                // We could return `self.plane.v0()`, but since we already calculated `t`,
-                // we might as well save the simulation runner some work, and return that
-                intersection: Intersection::Distance(t),
-                direction: TangentSpace::Plane(self.orthonormalised),
-            });
-        }
+    fn append_arc_intersections(
+        &self,
+        arc: &arc::Arc<D>,
+        mut list: List<(Float, TangentPlane<D>)>,
+    ) {
+        let plane = self.orthonormalised();
+
+        list.extend(
+            arc.hyperplane_intersections(plane.v0(), &plane.normal())
+                .filter_map(|angle| {
+                    let p = arc.at(angle);
+                    // the arc crosses the plane, so it isn't parallel to it there
+                    self.intersection(&Ray::new(p, arc.direction_at(angle)))
+                        .map(|tangent| {
+                            let tangent = TangentPlane {
+                                intersection: Intersection::StartingPoint(p),
+                                ..tangent
+                            };
+                            (angle, tangent)
+                        })
+                }),
+        );
     }
 }
 
@@ -136,8 +169,8 @@ impl<const D: usize> JsonSer for PlaneMirror<D> {
     }
 }
 
-struct PlaneRenderData<const D: usize> {
-    vertices: gl::VertexBuffer<render::Vertex<D>>,
+pub(crate) struct PlaneRenderData<const D: usize> {
+    pub(crate) vertices: gl::VertexBuffer<render::Vertex<D>>,
 }
 
 impl<const D: usize> render::RenderData for PlaneRenderData<D> {
@@ -211,10 +244,7 @@ mod tests {
         }))
         .expect("json monke");
 
-        let mut ray = Ray {
-            origin: [-1., 0.].into(),
-            direction: Unit::new_normalize([1., 0.].into()),
-        };
+        let mut ray = Ray::new([-1., 0.].into(), Unit::new_normalize([1., 0.].into()));
 
         let mut intersections = vec![];
         mirror.append_intersecting_points(&ray, List::from(&mut intersections));
@@ -254,10 +284,7 @@ mod tests {
         }))
         .expect("json monke");
 
-        let mut ray = Ray {
-            origin: [1., 0.].into(),
-            direction: Unit::new_normalize([-1., 0.].into()),
-        };
+        let mut ray = Ray::new([1., 0.].into(), Unit::new_normalize([-1., 0.].into()));
 
         let mut intersections = vec![];
 
@@ -298,10 +325,7 @@ mod tests {
         }))
         .expect("json monke");
 
-        let mut ray = Ray {
-            origin: [-1., 1.].into(),
-            direction: Unit::new_normalize([1., -1.].into()),
-        };
+        let mut ray = Ray::new([-1., 1.].into(), Unit::new_normalize([1., -1.].into()));
 
         let mut intersections = vec![];
         mirror.append_intersecting_points(&ray, List::from(&mut intersections));
@@ -350,10 +374,7 @@ mod tests {
         }))
         .expect("json monke");
 
-        let mut ray = Ray {
-            origin: [0., 0.5].into(),
-            direction: Unit::new_normalize([1., 0.].into()),
-        };
+        let mut ray = Ray::new([0., 0.5].into(), Unit::new_normalize([1., 0.].into()));
 
         let mut pts = vec![];
         m1.append_intersecting_points(&ray, List::from(&mut pts));
@@ -0,0 +1,250 @@
+use nalgebra::Complex;
+
+use super::*;
+
+/// The polarization state of a ray: the complex amplitude of it's electric field, a
+/// vector orthogonal to the ray's direction, with a norm of `1` (a ray's power is stored separately).
+///
+/// Jones vectors and Stokes parameters are expressed in the frame given by [`transverse_frame`].
+/// Only 3D rays can be arbitrarily polarized.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Polarization<const D: usize> {
+    field: SVector<Complex<Float>, D>,
+}
+
+/// Returns two orthonormal vectors, orthogonal to `direction` (the
+/// second and third vectors of [`util::basis_from_vector`]), in 3D.
+///
+/// In 2D, the second vector is zero.
+pub fn transverse_frame<const D: usize>(
+    direction: &Unit<SVector<Float, D>>,
+) -> [SVector<Float, D>; 2] {
+    let basis = util::basis_from_vector(direction);
+    [1, 2].map(|i| basis.get(i).copied().unwrap_or_else(SVector::zeros))
+}
+
+/// The (bilinear) dot product of a complex vector and a real one
+fn dot<const D: usize>(z: &SVector<Complex<Float>, D>, v: &SVector<Float, D>) -> Complex<Float> {
+    z.iter().zip(v.iter()).map(|(z, x)| z * x).sum()
+}
+
+fn complexify<const D: usize>(v: &SVector<Float, D>) -> SVector<Complex<Float>, D> {
+    v.map(Complex::from)
+}
+
+impl<const D: usize> Polarization<D> {
+    /// Create a new polarization state from an electric field, for a ray going in `direction`.
+    ///
+    /// The field is projected orthogonally to `direction`, and normalized.
+    /// Returns `None` if it is then too close to zero.
+    pub fn new(
+        field: SVector<Complex<Float>, D>,
+        direction: &Unit<SVector<Float, D>>,
+    ) -> Option<Self> {
+        let field = field - complexify(direction) * dot(&field, direction);
+        let norm = field.iter().map(Complex::norm_sqr).sum::<Float>().sqrt();
+
+        (norm > Float::EPSILON * 8.0).then(|| Self {
+            field: field.unscale(norm),
+        })
+    }
+
+    /// Create a new polarization state from a Jones vector, in the frame of [`transverse_frame`].
+    pub fn from_jones(
+        jones: [Complex<Float>; 2],
+        direction: &Unit<SVector<Float, D>>,
+    ) -> Option<Self> {
+        let [e_1, e_2] = transverse_frame(direction);
+        Self::new(
+            complexify(&e_1) * jones[0] + complexify(&e_2) * jones[1],
+            direction,
+        )
+    }
+
+    pub fn field(&self) -> &SVector<Complex<Float>, D> {
+        &self.field
+    }
+
+    /// The Jones vector of this polarization state, in the frame of [`transverse_frame`]
+    pub fn jones(&self, direction: &Unit<SVector<Float, D>>) -> [Complex<Float>; 2] {
+        transverse_frame(direction).map(|e| dot(&self.field, &e))
+    }
+
+    /// The normalized Stokes parameters `[S_0, S_1, S_2, S_3]` of this polarization state,
+    /// in the frame of [`transverse_frame`]: `S_0 = 1`, `S_1 = 1` for linear polarization along the first
+    /// vector of the frame, `S_2 = 1` for linear polarization at 45°, and `S_3 = 1` for right-handed
+    /// circular polarization (`E_2` a quarter period late w.r.t `E_1`, with
+    /// the field varying as `E * exp(iωt)`, as in `[1, -i]`).
+    pub fn stokes(&self, direction: &Unit<SVector<Float, D>>) -> [Float; 4] {
+        let [e_1, e_2] = self.jones(direction);
+        let cross = e_1 * e_2.conj();
+
+        [
+            e_1.norm_sqr() + e_2.norm_sqr(),
+            e_1.norm_sqr() - e_2.norm_sqr(),
+            2.0 * cross.re,
+            2.0 * cross.im,
+        ]
+    }
+
+    /// Applies `rotation` (an orthogonal matrix) to the electric field
+    pub fn rotated(&self, rotation: &SMatrix<Float, D, D>) -> Self {
+        Self {
+            field: rotation.map(Complex::from) * self.field,
+        }
+    }
+
+    /// Updates this polarization state after a ray, going in direction `d_in`, bounces off
+    /// a surface whose tangent hyperplane has direction `tangent`, and leaves in direction `d_out`.
+    ///
+    /// `amplitudes` are the (complex) factors applied to the components of the electric
+    /// field orthogonal to (s), and in (p) the plane of incidence, the square of their
+    /// moduli being the fraction of power reflected (or transmitted) in each component.
+    ///
+    /// Returns the fraction of the ray's power that is kept.
+    pub fn transfer(
+        &mut self,
+        d_in: &Unit<SVector<Float, D>>,
+        d_out: &Unit<SVector<Float, D>>,
+        tangent: &TangentSpace<D>,
+        amplitudes: [Complex<Float>; 2],
+    ) -> Float {
+        let [a_s, a_p] = amplitudes;
+        let d_in = d_in.into_inner();
+
+        // the normal component of the incoming direction
+        let normal = (d_in - tangent.reflect(d_in)) * 0.5;
+        let reflected = d_out.dot(&normal) < 0.0;
+
+        let field = match Unit::try_new(normal - d_in * normal.dot(&d_in), 1e-9) {
+            Some(p_in) => {
+                let amp_p = dot(&self.field, &p_in);
+                let s = self.field - complexify(&p_in) * amp_p;
+
+                // the p direction after the bounce, in the plane of incidence, and orthogonal to `d_out`
+                let p_out = if reflected {
+                    -tangent.reflect(p_in.into_inner())
+                } else {
+                    p_in.into_inner()
+                };
+                let p_out = p_out - d_out.as_ref() * p_out.dot(d_out);
+
+                s * a_s + complexify(&p_out) * (amp_p * a_p)
+            }
+            // normal incidence, the s and p components can't be told apart
+            None => self.field * a_s,
+        };
+
+        let field = field - complexify(d_out) * dot(&field, d_out);
+        let kept = field.iter().map(Complex::norm_sqr).sum::<Float>();
+
+        if kept > Float::EPSILON {
+            self.field = field.unscale(kept.sqrt());
+        }
+
+        kept
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    fn bounce(surface: Surface<3>, ray: &Ray<3>, normal: [Float; 3]) -> Ray<3> {
+        let tangent = TangentSpace::Normal(Unit::new_normalize(normal.into()));
+        surface
+            .outgoing_ray(ray, &tangent, &mut rand::rngs::StdRng::seed_from_u64(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_stokes() {
+        let direction = Unit::new_normalize([0., 0., 1.].into());
+        let i = Complex::i();
+        let one = Complex::from(1.);
+
+        let circular = Polarization::<3>::from_jones([one, -i], &direction).unwrap();
+        let [s_0, s_1, s_2, s_3] = circular.stokes(&direction);
+        assert!((s_0 - 1.).abs() < 1e-12);
+        assert!(s_1.abs() < 1e-12 && s_2.abs() < 1e-12);
+        assert!((s_3 - 1.).abs() < 1e-12);
+
+        let diagonal = Polarization::<3>::from_jones([one, one], &direction).unwrap();
+        assert!((diagonal.stokes(&direction)[2] - 1.).abs() < 1e-12);
+
+        let ray = Ray::<3>::from_json(&json!({
+            "origin": [0., 0., 0.],
+            "direction": [0., 0., 1.],
+            "polarization": [[1., 0.], [0., -1.]],
+        }))
+        .expect("json error");
+
+        let ray2 = Ray::<3>::from_json(&ray.to_json()).expect("json error");
+        assert!((ray2.polarization.unwrap().stokes(&direction)[3] - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_perfect_mirror() {
+        // a perfect mirror keeps linear s and p polarizations, and the power
+        let direction = Unit::new_normalize([1., 0., -1.].into());
+        let [e_1, e_2] = transverse_frame(&direction);
+
+        for e in [e_1, e_2] {
+            let ray = Ray {
+                polarization: Polarization::new(complexify(&e), &direction),
+                ..Ray::new([0., 0., 1.].into(), direction)
+            };
+
+            let out = bounce(Surface::Reflective, &ray, [0., 0., 1.]);
+            let [s_0, s_1, _, s_3] = out.polarization.unwrap().stokes(&out.direction);
+
+            assert!((out.power - 1.).abs() < 1e-12);
+            assert!((s_0 - 1.).abs() < 1e-12);
+            assert!((s_1.abs() - 1.).abs() < 1e-12);
+            assert!(s_3.abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_metal() {
+        let n = Complex::new(0.2, 3.);
+
+        // normal incidence
+        let ray = Ray::new(
+            [0., 0., 1.].into(),
+            Unit::new_normalize([0., 0., -1.].into()),
+        );
+        let out = bounce(
+            Surface::Metallic {
+                refractive_index: n,
+            },
+            &ray,
+            [0., 0., 1.],
+        );
+        let r = ((n - 1.) / (n + 1.)).norm_sqr();
+        assert!((out.power - r).abs() < 1e-12);
+
+        // linear polarization at 45° from the plane of incidence becomes elliptical
+        let direction = Unit::new_normalize([1., 0., -1.].into());
+        let ray = Ray {
+            polarization: Polarization::from_jones(
+                [Complex::from(1.), Complex::from(1.)],
+                &direction,
+            ),
+            ..Ray::new([0., 0., 1.].into(), direction)
+        };
+
+        let out = bounce(
+            Surface::Metallic {
+                refractive_index: n,
+            },
+            &ray,
+            [0., 0., 1.],
+        );
+        let [r_s, r_p] = material::fresnel_reflection((0.5 as Float).sqrt(), n);
+        assert!((out.power - 0.5 * (r_s.norm_sqr() + r_p.norm_sqr())).abs() < 1e-12);
+        assert!(out.polarization.unwrap().stokes(&out.direction)[3].abs() > 0.01);
+    }
+}
@@ -0,0 +1,394 @@
+use super::*;
+
+/// The colours portals are drawn with, in the order they are listed in their group
+const PORTAL_COLORS: [[f32; 4]; 6] = [
+    [0.1, 0.45, 1.0, 0.8],
+    [1.0, 0.5, 0.0, 0.8],
+    [0.1, 0.75, 0.2, 0.8],
+    [0.6, 0.2, 0.9, 0.8],
+    [0.0, 0.8, 0.8, 0.8],
+    [0.9, 0.8, 0.0, 0.8],
+];
+
+/// A parallelotope-shaped (hyper)plane, (see [`plane::PlaneMirror`]) that rays go into,
+/// to come out of the portal it is linked to, see [`Portals`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Portal<const D: usize> {
+    name: String,
+    plane: plane::PlaneMirror<D>,
+    /// The index, in it's group, of the portal linked to this one
+    link: usize,
+    /// The rigid motion mapping this portal onto the linked one
+    rotation: SMatrix<Float, D, D>,
+    translation: SVector<Float, D>,
+}
+
+impl<const D: usize> Portal<D> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn plane(&self) -> &plane::PlaneMirror<D> {
+        &self.plane
+    }
+
+    /// Returns the tangent plane where `ray` goes into this portal, if it does
+    pub fn intersection(&self, ray: &Ray<D>) -> Option<TangentPlane<D>> {
+        self.plane.intersection(ray).map(|tangent| TangentPlane {
+            surface: Surface::Portal {
+                rotation: self.rotation,
+                translation: self.translation,
+            },
+            ..tangent
+        })
+    }
+}
+
+/// Returns the rigid motion `(rotation, translation)` mapping the center, and basis
+/// vectors, of `from`, to those of `to`, or `None` if the portals aren't congruent.
+fn isometry<const D: usize>(
+    from: &plane::PlaneMirror<D>,
+    to: &plane::PlaneMirror<D>,
+) -> Option<(SMatrix<Float, D, D>, SVector<Float, D>)> {
+    // the basis vectors, preceded by the normal, as columns
+    let frame = |plane: &plane::PlaneMirror<D>| {
+        let mut frame = SMatrix::from_columns(plane.inner_plane().vectors_raw());
+        frame.set_column(0, plane.orthonormalised().normal().as_ref());
+        frame
+    };
+
+    let from_frame_inverse = frame(from).try_inverse()?;
+    let mut to_frame = frame(to);
+
+    let mut rotation = to_frame * from_frame_inverse;
+    // the normals are mapped to one another, in the direction that doesn't mirror space
+    if util::determinant(rotation) < 0.0 {
+        to_frame.set_column(0, &-to_frame.column(0));
+        rotation = to_frame * from_frame_inverse;
+    }
+
+    let orthogonal = (rotation.transpose() * rotation - SMatrix::identity()).amax() < 1e-9;
+
+    orthogonal.then(|| {
+        let translation = to.inner_plane().v0() - rotation * from.inner_plane().v0();
+        (rotation, translation)
+    })
+}
+
+/// A group of named portals, each linked to another one (or itself) of the group, by name:
+/// rays going into a portal come out of the linked one, their position, and direction, mapped
+/// by the rigid motion taking the center, and basis vectors, of the first portal to those of
+/// the second one, (see [`Surface::Portal`]) which must thus be congruent.
+///
+/// Links don't need to be mutual: a portal can be an exit only, for some other portal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Portals<const D: usize> {
+    portals: Vec<Portal<D>>,
+}
+
+impl<const D: usize> Portals<D> {
+    /// `portals` are `(name, plane, name of the linked portal)` triples.
+    ///
+    /// Returns `None` if two portals have the same name, if a portal is linked to a name
+    /// that isn't in `portals`, or if two linked portals aren't congruent.
+    pub fn new(portals: Vec<(String, plane::PlaneMirror<D>, String)>) -> Option<Self> {
+        let index_of = |name: &str| portals.iter().position(|(n, _, _)| n == name);
+
+        if (0..portals.len()).any(|i| index_of(&portals[i].0) != Some(i)) {
+            return None;
+        }
+
+        let links = Vec::from_iter(portals.iter().map(|(_, _, link)| index_of(link)));
+
+        let portals = portals
+            .iter()
+            .zip(links)
+            .map(|((name, plane, _), link)| {
+                let link = link?;
+                let (rotation, translation) = isometry(plane, &portals[link].1)?;
+                Some(Portal {
+                    name: name.clone(),
+                    plane: *plane,
+                    link,
+                    rotation,
+                    translation,
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self { portals })
+    }
+
+    pub fn portals(&self) -> &[Portal<D>] {
+        self.portals.as_slice()
+    }
+
+    /// Returns the portal linked to `portal`, which must belong to this group
+    pub fn linked(&self, portal: &Portal<D>) -> &Portal<D> {
+        &self.portals[portal.link]
+    }
+}
+
+impl<const D: usize> Mirror<D> for Portals<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        list.extend(
+            self.portals
+                .iter()
+                .filter_map(|portal| portal.intersection(ray)),
+        );
+    }
+}
+
+impl<const D: usize> JsonType for Portals<D> {
+    fn json_type() -> String {
+        "portals".into()
+    }
+}
+
+impl<const D: usize> JsonDes for Portals<D> {
+    /// Deserialize a new group of portals from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "portals": [
+    ///         {
+    ///             "name": "blue", // (must be unique in the group)
+    ///             "link": "orange", // (the name of the portal rays going into this one come out of)
+    ///             // the plane of the portal, see `PlaneMirror::from_json`
+    ///             "center": [1., 2., 3., ...],
+    ///             "basis": [[1., 0., 0., ...], ...],
+    ///         },
+    ///         ...
+    ///     ],
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let portals =
+            util::map_json_array(json.get("portals").ok_or("Missing portals")?, |portal| {
+                let get_name = |key: &str| {
+                    portal
+                        .get(key)
+                        .and_then(serde_json::Value::as_str)
+                        .map(String::from)
+                        .ok_or(format!("Missing or invalid portal {key}"))
+                };
+
+                Ok((
+                    get_name("name")?,
+                    plane::PlaneMirror::from_json(portal)?,
+                    get_name("link")?,
+                ))
+            })?;
+
+        Self::new(portals).ok_or(
+            "portal names must be unique, links must be names of portals of the group, \
+            and linked portals must be congruent"
+                .into(),
+        )
+    }
+}
+
+impl<const D: usize> JsonSer for Portals<D> {
+    /// Serialize a group of portals into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let portals = Vec::from_iter(self.portals.iter().map(|portal| {
+            let mut json = portal.plane.to_json();
+            json["name"] = portal.name.as_str().into();
+            json["link"] = self.linked(portal).name.as_str().into();
+            json
+        }));
+
+        serde_json::json!({ "portals": portals })
+    }
+}
+
+struct PortalRenderData<const D: usize> {
+    plane: plane::PlaneRenderData<D>,
+    color: [f32; 4],
+}
+
+impl<const D: usize> render::RenderData for PortalRenderData<D> {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        self.plane.vertices()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        self.plane.indices()
+    }
+
+    fn color(&self) -> Option<[f32; 4]> {
+        Some(self.color)
+    }
+}
+
+impl<const D: usize> render::OpenGLRenderable for Portals<D>
+where
+    render::Vertex<D>: gl::Vertex,
+{
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        for (portal, color) in self.portals.iter().zip(PORTAL_COLORS.iter().cycle()) {
+            let vertices = Vec::from_iter(portal.plane.vertices().map(render::Vertex::from));
+
+            list.push(Box::new(PortalRenderData {
+                plane: plane::PlaneRenderData {
+                    vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+                },
+                color: *color,
+            }))
+        }
+    }
+}
+
+impl<const D: usize> Random for Portals<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        loop {
+            // the second portal is a random isometric copy of the first one
+            let first = plane::PlaneMirror::<D>::random(rng);
+            let rotation =
+                SMatrix::from_columns(&util::basis_from_vector(&util::rand_unit_vect(rng)));
+            let translation = util::rand_vect(rng, 10.0);
+
+            let mut vectors = first.inner_plane().vectors_raw().map(|v| rotation * v);
+            vectors[0] += translation;
+
+            let Some(second) = plane::PlaneMirror::try_new(vectors) else {
+                continue;
+            };
+
+            let portals = vec![
+                ("a".into(), first, "b".into()),
+                ("b".into(), second, "a".into()),
+            ];
+
+            if let Some(portals) = Self::new(portals) {
+                break portals;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ray(origin: [Float; 2], direction: [Float; 2]) -> Ray<2> {
+        Ray::new(origin.into(), Unit::new_normalize(direction.into()))
+    }
+
+    /// A vertical portal, at x = 0, y in [-1 ; 1], linked to a horizontal one, at y = 5, x in [4 ; 6]
+    fn portals() -> Portals<2> {
+        Portals::from_json(&json!({
+            "portals": [
+                { "name": "blue", "link": "orange", "center": [0., 0.], "basis": [[0., 1.]] },
+                { "name": "orange", "link": "blue", "center": [5., 5.], "basis": [[1., 0.]] },
+            ],
+        }))
+        .expect("json error")
+    }
+
+    fn teleport<M: Mirror<2>>(mirror: &M, ray: &Ray<2>) -> Ray<2> {
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(ray, List::from(&mut intersections));
+        let [tangent] = intersections.as_slice() else {
+            panic!("there must be one intersection");
+        };
+
+        let mut ray = *ray;
+        ray.advance(tangent.try_ray_intersection(&ray).unwrap());
+        tangent
+            .outgoing_ray(&ray, &mut rand::rngs::mock::StepRng::new(0, 1))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_isometry() {
+        let portals = portals();
+
+        // the rotation taking blue's basis vector (0, 1) to orange's (1, 0) is a quarter turn
+        // clockwise, the ray keeps going "through" the portal, out of orange's bottom side
+        let out = teleport(&portals, &ray([-1., 0.5], [1., 0.]));
+        assert!((out.origin - SVector::from([5.5, 5.])).norm() < 1e-12);
+        assert!((out.direction.into_inner() - SVector::from([0., -1.])).norm() < 1e-12);
+        assert_eq!(portals.linked(&portals.portals()[1]).name(), "blue");
+
+        // transformed portals keep mapping one onto the other
+        let transformed = transform::Transformed::new(
+            &portals,
+            transform::plane_rotation(&[1., 0.].into(), &[0., 1.].into(), 0.3),
+            [2., -1.].into(),
+            2.,
+        )
+        .unwrap();
+
+        let entry = transformed.point_to_world(&[0., 0.5].into());
+        let direction = transformed.rotation() * SVector::from([1., 0.]);
+        let out = teleport(
+            &transformed,
+            &Ray::new(entry - direction, Unit::new_normalize(direction)),
+        );
+        assert!((out.origin - transformed.point_to_world(&[5.5, 5.].into())).norm() < 1e-9);
+        let expected = transformed.rotation() * SVector::from([0., -1.]);
+        assert!((out.direction.into_inner() - expected).norm() < 1e-9);
+
+        let json = portals.to_json();
+        assert_eq!(Portals::from_json(&json).ok().as_ref(), Some(&portals));
+    }
+
+    #[test]
+    fn test_invalid_links() {
+        let portal = |name: &str, link: &str, length: Float| json!({ "name": name, "link": link, "center": [0., 0.], "basis": [[0., length]] });
+
+        for group in [
+            vec![portal("a", "b", 1.)],
+            vec![portal("a", "a", 1.), portal("a", "a", 1.)],
+            vec![portal("a", "b", 1.), portal("b", "a", 2.)],
+        ] {
+            assert!(Portals::<2>::from_json(&json!({ "portals": group })).is_err());
+        }
+
+        // one way portals are fine
+        let group = vec![portal("a", "b", 1.), portal("b", "b", 1.)];
+        assert!(Portals::<2>::from_json(&json!({ "portals": group })).is_ok());
+    }
+
+    #[test]
+    fn test_corridor() {
+        // parallel portals, a ray going into one comes out of the other, going the same way
+        let portals = Portals::from_json(&json!({
+            "portals": [
+                { "name": "left", "link": "right", "center": [0., 0.], "basis": [[0., 1.]] },
+                { "name": "right", "link": "left", "center": [4., 0.], "basis": [[0., 1.]] },
+            ],
+        }))
+        .unwrap();
+
+        let simulation = crate::Simulation {
+            rays: vec![ray([2., 0.], [1., 0.15])],
+            mirror: portals,
+            seed: 0,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let path = simulation.get_ray_paths(100).remove(0);
+        assert_eq!(path.wraps().len(), 2);
+
+        // the ray drifts up, until it misses the portals
+        let points = path.all_points_raw();
+        assert!((points[1] - SVector::from([4., 0.3])).norm() < 1e-12);
+        assert!((points[2] - SVector::from([0., 0.3])).norm() < 1e-12);
+        assert_eq!(path.wraps()[0], 2);
+        assert!((points[4] - SVector::from([0., 0.9])).norm() < 1e-12);
+        assert!(path.divergence_direction().is_some());
+    }
+}
@@ -0,0 +1,367 @@
+use super::*;
+
+/// A model of the way a rough surface scatters the rays hitting it, see [`Surface::Scattering`].
+///
+/// The outgoing direction is random, drawn from the generator passed to
+/// [`Surface::outgoing_ray`]. Scattered rays keep their power, but lose their polarization.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scattering {
+    /// Rays leave in a random direction, with a probability density proportional to
+    /// the cosine of the angle it makes with the normal (an ideal matte surface)
+    Lambertian,
+    /// Rays leave in a random direction around the direction of specular reflection,
+    /// with a probability density proportional to `cos(α)^exponent`, `α` being the
+    /// angle between them (in 3D). Rays sent into the surface are absorbed.
+    Glossy { exponent: Float },
+    /// Rays are specularly reflected with probability `specularity`,
+    /// and scattered as by a [`Scattering::Lambertian`] surface otherwise
+    Mixed { specularity: Float },
+}
+
+/// Returns a uniformly distributed random point of the unit ball
+/// of the orthogonal complement of `axis`
+fn sample_ball<const D: usize>(
+    axis: &Unit<SVector<Float, D>>,
+    rng: &mut (impl rand::Rng + ?Sized),
+) -> SVector<Float, D> {
+    let basis = util::basis_from_vector(axis);
+
+    // rejection sampling in the enclosing cube
+    loop {
+        let v = basis[1..].iter().fold(SVector::zeros(), |acc, e| {
+            acc + e * rng.gen_range(-1.0..1.0)
+        });
+
+        if v.norm_squared() <= 1.0 {
+            return v;
+        }
+    }
+}
+
+/// Returns a random direction, on the side of `normal`, with a
+/// probability density proportional to it's dot product with `normal`
+fn sample_lambertian<const D: usize>(
+    normal: &Unit<SVector<Float, D>>,
+    rng: &mut (impl rand::Rng + ?Sized),
+) -> Unit<SVector<Float, D>> {
+    // Malley's method: lift a uniform point of the tangent ball onto the hemisphere
+    let t = sample_ball(normal, rng);
+    let height = (1.0 - t.norm_squared()).max(0.0).sqrt();
+
+    Unit::new_normalize(t + normal.as_ref() * height)
+}
+
+impl Scattering {
+    fn is_valid(&self) -> bool {
+        match *self {
+            Self::Lambertian => true,
+            Self::Glossy { exponent } => exponent >= 0.0,
+            Self::Mixed { specularity } => (0.0..=1.0).contains(&specularity),
+        }
+    }
+
+    /// Returns the direction in which a ray, going in direction `d`, leaves a surface whose
+    /// tangent hyperplane has direction `tangent`, drawing random numbers from `rng`.
+    ///
+    /// Returns `None` if the ray is absorbed.
+    pub fn scatter<const D: usize>(
+        &self,
+        d: &Unit<SVector<Float, D>>,
+        tangent: &TangentSpace<D>,
+        rng: &mut (impl rand::Rng + ?Sized),
+    ) -> Option<Unit<SVector<Float, D>>> {
+        let reflected = tangent.reflect_unit(*d);
+
+        // the normal, on the side the ray comes from
+        let Some(normal) = Unit::try_new(reflected.into_inner() - d.into_inner(), 1e-9) else {
+            // the ray only grazes the surface
+            return Some(reflected);
+        };
+
+        match *self {
+            Self::Lambertian => Some(sample_lambertian(&normal, rng)),
+            Self::Glossy { exponent } => {
+                let cos_a = rng.gen::<Float>().powf((exponent + 1.0).recip());
+                let sin_a = (1.0 - cos_a * cos_a).max(0.0).sqrt();
+
+                let out = match Unit::try_new(sample_ball(&reflected, rng), 1e-9) {
+                    Some(t) => Unit::new_normalize(reflected.as_ref() * cos_a + t.as_ref() * sin_a),
+                    None => reflected,
+                };
+
+                (out.dot(&normal) > 0.0).then_some(out)
+            }
+            Self::Mixed { specularity } => Some(if rng.gen::<Float>() < specularity {
+                reflected
+            } else {
+                sample_lambertian(&normal, rng)
+            }),
+        }
+    }
+}
+
+impl JsonDes for Scattering {
+    /// Deserialize a scattering model from a JSON object.
+    ///
+    /// It must follow one of these formats:
+    ///
+    /// ```json
+    /// { "model": "lambertian" }
+    /// ```
+    /// ```json
+    /// { "model": "glossy", "exponent": 20.0 } // (non-negative)
+    /// ```
+    /// ```json
+    /// { "model": "mixed", "specularity": 0.3 } // (in [0 ; 1])
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let get_float = |name: &str| {
+            json.get(name)
+                .and_then(serde_json::Value::as_f64)
+                .ok_or(format!("Missing or invalid {name}"))
+        };
+
+        let scattering = match json
+            .get("model")
+            .and_then(serde_json::Value::as_str)
+            .ok_or("Missing scattering model")?
+        {
+            "lambertian" => Self::Lambertian,
+            "glossy" => Self::Glossy {
+                exponent: get_float("exponent")?,
+            },
+            "mixed" => Self::Mixed {
+                specularity: get_float("specularity")?,
+            },
+            other => return Err(format!("Unknown scattering model: {other}").into()),
+        };
+
+        if scattering.is_valid() {
+            Ok(scattering)
+        } else {
+            Err("exponent must be non-negative, and specularity in [0 ; 1]".into())
+        }
+    }
+}
+
+impl JsonSer for Scattering {
+    /// Serialize a scattering model into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::Lambertian => serde_json::json!({ "model": "lambertian" }),
+            Self::Glossy { exponent } => {
+                serde_json::json!({ "model": "glossy", "exponent": exponent })
+            }
+            Self::Mixed { specularity } => {
+                serde_json::json!({ "model": "mixed", "specularity": specularity })
+            }
+        }
+    }
+}
+
+impl Random for Scattering {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        match rng.gen_range(0usize..3) {
+            0 => Self::Lambertian,
+            1 => Self::Glossy {
+                exponent: rng.gen_range(1.0..100.0),
+            },
+            _ => Self::Mixed {
+                specularity: rng.gen(),
+            },
+        }
+    }
+}
+
+/// A mirror whose regular (reflective) surfaces scatter the rays hitting them
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scattered<T> {
+    inner: T,
+    scattering: Scattering,
+}
+
+impl<T> Scattered<T> {
+    /// Returns `None` if the parameters of `scattering` are out of range
+    /// (a negative exponent, or a specularity outside of `[0 ; 1]`).
+    pub fn new(inner: T, scattering: Scattering) -> Option<Self> {
+        scattering.is_valid().then_some(Self { inner, scattering })
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn scattering(&self) -> &Scattering {
+        &self.scattering
+    }
+}
+
+impl<const D: usize, T: Mirror<D>> Mirror<D> for Scattered<T> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let mut hits = vec![];
+        self.inner
+            .append_intersecting_points(ray, List::from(&mut hits));
+
+        list.extend(hits.into_iter().map(|tangent| match tangent.surface {
+            Surface::Reflective => TangentPlane {
+                surface: Surface::Scattering(self.scattering),
+                ..tangent
+            },
+            _ => tangent,
+        }));
+    }
+}
+
+impl<const D: usize, T: csg::Solid<D>> csg::Solid<D> for Scattered<T> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        self.inner.contains(p)
+    }
+}
+
+impl<T> JsonType for Scattered<T> {
+    fn json_type() -> String {
+        "scattering".into()
+    }
+}
+
+impl<T: JsonDes> JsonDes for Scattered<T> {
+    /// Deserialize a new scattering mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "model": "glossy", // (and the model's parameters, see `Scattering::from_json`)
+    ///     "exponent": 20.0,
+    ///     "mirror": // <the inner mirror's layout>
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let scattering = Scattering::from_json(json)?;
+
+        let inner = T::from_json(json.get("mirror").ok_or("Missing mirror")?)?;
+
+        // SAFETY: `Scattering::from_json` checks the parameters
+        Ok(Self::new(inner, scattering).unwrap())
+    }
+}
+
+impl<T: JsonSer> JsonSer for Scattered<T> {
+    /// Serialize a scattering mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let mut json = self.scattering.to_json();
+        json["mirror"] = self.inner.to_json();
+        json
+    }
+}
+
+impl<T: render::OpenGLRenderable> render::OpenGLRenderable for Scattered<T> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.inner.append_render_data(display, list)
+    }
+}
+
+impl<T: Random> Random for Scattered<T> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        // SAFETY: random models have valid parameters
+        Self::new(T::random(rng), Scattering::random(rng)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    fn floor() -> Scattered<plane::PlaneMirror<3>> {
+        Scattered::from_json(&json!({
+            "model": "lambertian",
+            "mirror": {
+                "center": [0., 0., 0.],
+                "basis": [[10., 0., 0.], [0., 10., 0.]],
+            },
+        }))
+        .expect("json error")
+    }
+
+    #[test]
+    fn test_lambertian() {
+        let mirror = floor();
+        let ray = Ray::new(
+            [0., 0., 1.].into(),
+            Unit::new_normalize([1., 0., -1.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let n = 10000;
+        let mut mean = SVector::<Float, 3>::zeros();
+
+        for _ in 0..n {
+            let out = intersections[0].outgoing_ray(&ray, &mut rng).unwrap();
+            assert!(out.direction.z > 0.);
+            assert_eq!(out.power, 1.);
+            mean += out.direction.as_ref() / n as Float;
+        }
+
+        // the mean of a cosine-weighted hemisphere is (0, 0, 2/3)
+        assert!(mean.x.abs() < 0.03 && mean.y.abs() < 0.03);
+        assert!((mean.z - 2. / 3.).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_glossy_and_mixed() {
+        let d = Unit::new_normalize([1., 0., -1.].into());
+        let tangent = TangentSpace::Normal(Unit::new_normalize([0., 0., 1.].into()));
+        let specular = SVector::<Float, 3>::from([1., 0., 1.]).normalize();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+
+        let glossy = Scattering::Glossy { exponent: 1e4 };
+        for _ in 0..100 {
+            let out = glossy.scatter(&d, &tangent, &mut rng).unwrap();
+            assert!(out.dot(&specular) > 0.99);
+        }
+
+        let mirror = Scattering::Mixed { specularity: 1. };
+        let out = mirror.scatter(&d, &tangent, &mut rng).unwrap();
+        assert!((out.into_inner() - specular).norm() < 1e-12);
+
+        let scattering = Scattering::from_json(&glossy.to_json()).unwrap();
+        assert_eq!(scattering, glossy);
+        assert!(Scattering::from_json(&json!({ "model": "mixed", "specularity": 2. })).is_err());
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let simulation = crate::Simulation {
+            rays: vec![
+                Ray::new(
+                    [0., 0., 1.].into(),
+                    Unit::new_normalize([0., 0., -1.].into())
+                );
+                8
+            ],
+            mirror: floor(),
+            seed: 42,
+            propagation: crate::Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let paths = simulation.get_ray_paths(4);
+        assert_eq!(paths, simulation.get_ray_paths(4));
+
+        let other = crate::Simulation {
+            seed: 43,
+            ..simulation
+        };
+        assert_ne!(paths, other.get_ray_paths(4));
+    }
+}
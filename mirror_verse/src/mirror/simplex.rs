@@ -0,0 +1,249 @@
+use core::array;
+
+use super::*;
+
+/// A simplex-shaped reflective (hyper)plane: the convex hull of `D` affinely independent points.
+///
+/// In 2D, this is a line segment, in 3D a triangle, in 4D, a tetrahedron, etc...
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SimplexMirror<const D: usize> {
+    /// The plane containing this simplex, whose starting point is the first
+    /// vertex, and whose basis is the family of edges starting from it.
+    plane: AffineHyperPlane<D>,
+    /// The same plane, but represented with an orthonormal basis, useful for orthogonal symmetries
+    orthonormalised: AffineHyperPlaneOrtho<D>,
+}
+
+impl<const D: usize> SimplexMirror<D> {
+    /// Create a new simplex from it's vertices.
+    ///
+    /// Returns `None` if the vertices are not affinely independent, (i. e. the simplex is degenerate)
+    pub fn try_new(vertices: [SVector<Float, D>; D]) -> Option<Self> {
+        let v0 = vertices[0];
+        let edges = array::from_fn(|i| if i == 0 { v0 } else { vertices[i] - v0 });
+
+        AffineHyperPlane::new(edges).map(|(plane, orthonormalised)| Self {
+            plane,
+            orthonormalised,
+        })
+    }
+
+    pub fn inner_plane(&self) -> &AffineHyperPlane<D> {
+        &self.plane
+    }
+
+    pub fn vertices(&self) -> impl Iterator<Item = SVector<Float, D>> + '_ {
+        let v0 = *self.plane.v0();
+        core::iter::once(v0).chain(self.plane.basis().iter().map(move |edge| v0 + edge))
+    }
+
+    /// Returns the barycentric coordinates of the intersection of `ray` with this simplex's plane,
+    /// (in the same order as the vertices) along with the distance from the ray's origin to it.
+    ///
+    /// Returns `None` if `ray` is parallel to this simplex.
+    pub fn barycentric_coordinates(&self, ray: &Ray<D>) -> Option<(Float, SVector<Float, D>)> {
+        let p = self.inner_plane();
+
+        p.intersection_coordinates(ray, p.v0()).map(|mut coords| {
+            // the intersection's coordinates in the plane's basis
+            // are exactly it's barycentric coords, minus the first one
+            let t = coords[0];
+            coords[0] = 1.0 - coords.as_slice()[1..].iter().sum::<Float>();
+            (t, coords)
+        })
+    }
+}
+
+impl<const D: usize> Mirror<D> for SimplexMirror<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        if let Some((t, coords)) = self.barycentric_coordinates(ray) {
+            if coords.iter().all(|lambda| *lambda >= 0.0) {
+                list.push(TangentPlane {
+                    intersection: Intersection::Distance(t),
+                    direction: TangentSpace::Plane(self.orthonormalised),
+                    surface: Surface::Reflective,
+                });
+            }
+        }
+    }
+}
+
+impl<const D: usize> JsonType for SimplexMirror<D> {
+    fn json_type() -> String {
+        "simplex".into()
+    }
+}
+
+impl<const D: usize> JsonDes for SimplexMirror<D> {
+    /// Deserialize a new simplex mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "vertices": [
+    ///         [1., 2., 3., ...], // (D arrays of D floats)
+    ///         [4., 5., 6., ...],
+    ///         [7., 8., 9., ...],
+    ///         ...
+    ///     ],
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let mut vertices = [SVector::zeros(); D];
+
+        let vertices_json = json
+            .get("vertices")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .filter(|l| l.len() == D)
+            .ok_or("Failed to parse vertices")?;
+
+        for (value, vertex) in vertices_json.iter().zip(&mut vertices) {
+            *vertex = value
+                .as_array()
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector)
+                .ok_or("Failed to parse vertex")?;
+        }
+
+        Self::try_new(vertices).ok_or("the provided vertices must be affinely independent".into())
+    }
+}
+
+impl<const D: usize> JsonSer for SimplexMirror<D> {
+    /// Serialize a simplex mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let vertices = Vec::from_iter(self.vertices().map(|v| v.as_slice().to_vec()));
+
+        serde_json::json!({
+            "vertices": vertices,
+        })
+    }
+}
+
+impl render::OpenGLRenderable for SimplexMirror<2> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let vertices: Vec<_> = self.vertices().map(render::Vertex::<2>::from).collect();
+
+        list.push(Box::new(plane::PlaneRenderData {
+            vertices: gl::VertexBuffer::new(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl render::OpenGLRenderable for SimplexMirror<3> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let vertices: Vec<_> = self.vertices().map(render::Vertex::<3>::from).collect();
+
+        list.push(Box::new(plane::PlaneRenderData {
+            vertices: gl::VertexBuffer::new(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl<const D: usize> Random for SimplexMirror<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        loop {
+            if let Some(mirror) = Self::try_new(array::from_fn(|_| util::rand_vect(rng, 10.0))) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_triangle() {
+        let mirror = SimplexMirror::<3>::from_json(&json!({
+            "vertices": [
+                [0., 0., 0.],
+                [2., 0., 0.],
+                [0., 2., 0.],
+            ],
+        }))
+        .expect("json error");
+
+        let mut ray = Ray::new(
+            [0.5, 0.5, 1.].into(),
+            Unit::new_normalize([0., 0., -1.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [tangent] = intersections.as_slice() else {
+            panic!("there must be one intersection");
+        };
+
+        let t = tangent.try_ray_intersection(&ray).unwrap();
+        assert!((t - 1.).abs() < Float::EPSILON * 4.0);
+
+        ray.advance(t);
+        ray.reflect_dir(&tangent.direction);
+
+        assert!((ray.direction.into_inner() - SVector::from([0., 0., 1.])).norm() < 1e-12);
+
+        // inside the parallelogram spanned by the edges, but outside of the triangle
+        let ray = Ray::new(
+            [1.5, 1.5, 1.].into(),
+            Unit::new_normalize([0., 0., -1.].into()),
+        );
+
+        intersections.clear();
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        assert!(intersections.is_empty());
+    }
+
+    #[test]
+    fn test_barycentric() {
+        let mirror =
+            SimplexMirror::<2>::try_new([[0., -1.].into(), [0., 3.].into()]).expect("degenerate");
+
+        let ray = Ray::new([-1., 0.].into(), Unit::new_normalize([1., 0.].into()));
+
+        let (t, coords) = mirror.barycentric_coordinates(&ray).unwrap();
+
+        assert!((t - 1.).abs() < Float::EPSILON * 4.0);
+        assert!((coords - SVector::from([0.75, 0.25])).norm() < Float::EPSILON * 4.0);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        assert!(SimplexMirror::<3>::try_new([
+            [0., 0., 0.].into(),
+            [1., 0., 0.].into(),
+            [3., 0., 0.].into(),
+        ])
+        .is_none());
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror = SimplexMirror::<3>::try_new([
+            [1., 0., 0.].into(),
+            [0., 1., 0.].into(),
+            [0., 0., 1.].into(),
+        ])
+        .unwrap();
+
+        let mirror2 = SimplexMirror::<3>::from_json(&mirror.to_json()).expect("json error");
+
+        assert_eq!(mirror, mirror2);
+    }
+}
@@ -1,9 +1,10 @@
 use super::*;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 /// All vectors at a certain distance (radius) from a certain vector (center)
 /// where the distance here is the standard euclidean distance
-// TODO: We can do other distances, can we huh?
+///
+/// See [`LpSphereMirror`] for spheres of other distances.
 pub struct EuclideanSphereMirror<const D: usize> {
     pub center: SVector<Float, D>,
     radius: Float,
@@ -62,10 +63,36 @@ impl<const D: usize> Mirror<D> for EuclideanSphereMirror<D> {
                 list.push(TangentPlane {
                     intersection: Intersection::Distance(t),
                     direction: TangentSpace::Normal(normal),
+                    surface: Surface::Reflective,
                 });
             }
         }
     }
+
+    fn append_arc_intersections(
+        &self,
+        arc: &arc::Arc<D>,
+        mut list: List<(Float, TangentPlane<D>)>,
+    ) {
+        let v0 = &self.center;
+        let r = self.radius();
+
+        list.extend(arc.sphere_intersections(v0, *r).map(|angle| {
+            let p = arc.at(angle);
+            let tangent = TangentPlane {
+                intersection: Intersection::StartingPoint(p),
+                direction: TangentSpace::Normal(Unit::new_normalize(p - v0)),
+                surface: Surface::Reflective,
+            };
+            (angle, tangent)
+        }));
+    }
+}
+
+impl<const D: usize> csg::Solid<D> for EuclideanSphereMirror<D> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        (p - self.center).norm_squared() <= self.radius * self.radius
+    }
 }
 
 impl<const D: usize> JsonType for EuclideanSphereMirror<D> {
@@ -167,6 +194,308 @@ impl<const D: usize> Random for EuclideanSphereMirror<D> {
     }
 }
 
+/// All vectors at a certain distance (radius) from a certain vector (center)
+/// where the distance here is the one derived from the Lp norm, with `p >= 1`:
+///
+/// `||v||_p = (|v_1|^p + |v_2|^p + ... + |v_D|^p)^(1/p)`
+///
+/// `p = 2` is the euclidean sphere, `p = 1` the cross-polytope (a diamond in 2D,
+/// an octahedron in 3D) and `p = inf` (`||v||_inf = max |v_i|`) the (hyper)cube.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LpSphereMirror<const D: usize> {
+    pub center: SVector<Float, D>,
+    radius: Float,
+    p: Float,
+}
+
+/// Relative tolerance used to decide whether a point lies on a "crease" of the sphere
+/// (i. e. where it's not differentiable), when `p = 1` or `p = inf`
+const CREASE_TOLERANCE: Float = 1e-9;
+
+/// The intersection search is restricted to a box slightly larger than the
+/// (hyper)cube the sphere is inscribed in, by this relative margin
+const BOUNDING_BOX_MARGIN: Float = 1e-6;
+
+/// The Lp norm of `v`, for `p` in `[1 ; inf]`
+pub fn lp_norm<const D: usize>(v: &SVector<Float, D>, p: Float) -> Float {
+    let max = v.amax();
+
+    if p.is_infinite() || max == 0.0 {
+        max
+    } else {
+        // factor out the largest coordinate to avoid overflowing with large values of `p`
+        max * v
+            .iter()
+            .map(|x| (x.abs() / max).powf(p))
+            .sum::<Float>()
+            .powf(p.recip())
+    }
+}
+
+/// Returns the point where `f`, assumed to be unimodal on `[a ; b]`,
+/// reaches it's minimum, using golden-section search
+fn golden_section_min(f: impl Fn(Float) -> Float, mut a: Float, mut b: Float) -> Float {
+    // 1 / phi
+    const INV_PHI: Float = 0.618_033_988_749_894_8;
+
+    let mut c = b - (b - a) * INV_PHI;
+    let mut d = a + (b - a) * INV_PHI;
+    let (mut f_c, mut f_d) = (f(c), f(d));
+
+    // the interval shrinks by a factor of `1 / phi` at each step
+    for _ in 0..128 {
+        if f_c < f_d {
+            (b, d, f_d) = (d, c, f_c);
+            c = b - (b - a) * INV_PHI;
+            f_c = f(c);
+        } else {
+            (a, c, f_c) = (c, d, f_d);
+            d = a + (b - a) * INV_PHI;
+            f_d = f(d);
+        }
+    }
+
+    0.5 * (a + b)
+}
+
+impl<const D: usize> LpSphereMirror<D> {
+    /// Returns `None` if `radius` is too close to zero or if `p` isn't in `[1 ; inf]`.
+    pub fn new(center: SVector<Float, D>, radius: Float, p: Float) -> Option<Self> {
+        (radius.abs() >= Float::EPSILON && p >= 1.0).then_some(Self {
+            center,
+            radius: radius.abs(),
+            p,
+        })
+    }
+
+    pub fn radius(&self) -> &Float {
+        &self.radius
+    }
+
+    pub fn p(&self) -> Float {
+        self.p
+    }
+
+    /// Negative inside of the sphere, positive outside, and zero on it.
+    pub fn eval(&self, v: &SVector<Float, D>) -> Float {
+        lp_norm(&(v - self.center), self.p) - self.radius
+    }
+
+    /// Returns a vector normal to the sphere at `v`, the gradient of the norm at `v - center`.
+    ///
+    /// Where the norm isn't differentiable (the edges and vertices of the cube, when
+    /// `p = inf`, and of the cross-polytope, when `p = 1`), this is instead the sum
+    /// of the normals of the faces meeting there.
+    pub fn normal(&self, v: &SVector<Float, D>) -> SVector<Float, D> {
+        let x = v - self.center;
+        let max = x.amax();
+
+        if self.p.is_infinite() {
+            // only the coordinates of the largest magnitude contribute
+            x.map(|x_i| {
+                if x_i.abs() >= max * (1.0 - CREASE_TOLERANCE) {
+                    x_i.signum()
+                } else {
+                    0.0
+                }
+            })
+        } else if self.p == 1.0 {
+            // zero coordinates are shared by both faces, with opposite signs, and cancel out
+            x.map(|x_i| {
+                if x_i.abs() > max * CREASE_TOLERANCE {
+                    x_i.signum()
+                } else {
+                    0.0
+                }
+            })
+        } else {
+            let norm = lp_norm(&x, self.p);
+            x.map(|x_i| x_i.signum() * (x_i.abs() / norm).powf(self.p - 1.0))
+        }
+    }
+}
+
+impl<const D: usize> Mirror<D> for LpSphereMirror<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        // the ball is convex, so `t -> eval(ray.at(t))` is a convex function, with at most
+        // two zeros, on both sides of it's minimum, we find it, then bisect on each side
+
+        // clip the ray to the (slightly enlarged) cube the sphere is inscribed in
+        // (slab method), `eval` is positive on it's boundary
+        let half_side = self.radius * (1.0 + BOUNDING_BOX_MARGIN);
+        let v = ray.origin - self.center;
+
+        let (mut t_start, mut t_end) = (Float::NEG_INFINITY, Float::INFINITY);
+
+        for (o, d) in v.iter().zip(ray.direction.iter()) {
+            if d.abs() > Float::EPSILON {
+                let (t_1, t_2) = ((-half_side - o) / d, (half_side - o) / d);
+                t_start = t_start.max(t_1.min(t_2));
+                t_end = t_end.min(t_1.max(t_2));
+            } else if o.abs() > half_side {
+                return;
+            }
+        }
+
+        if t_start >= t_end {
+            return;
+        }
+
+        let f = |t| self.eval(&ray.at(t));
+
+        let t_min = golden_section_min(f, t_start, t_end);
+        let f_min = f(t_min);
+
+        if f_min >= 0.0 {
+            return;
+        }
+
+        for t in [
+            implicit::bisect(f, t_start, f(t_start), t_min),
+            implicit::bisect(f, t_min, f_min, t_end),
+        ] {
+            if let Some(normal) = Unit::try_new(self.normal(&ray.at(t)), Float::EPSILON) {
+                list.push(TangentPlane {
+                    intersection: Intersection::Distance(t),
+                    direction: TangentSpace::Normal(normal),
+                    surface: Surface::Reflective,
+                });
+            }
+        }
+    }
+}
+
+impl<const D: usize> csg::Solid<D> for LpSphereMirror<D> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        self.eval(p) <= 0.0
+    }
+}
+
+impl<const D: usize> JsonType for LpSphereMirror<D> {
+    fn json_type() -> String {
+        "lp_sphere".into()
+    }
+}
+
+impl<const D: usize> JsonDes for LpSphereMirror<D> {
+    /// Deserialize a new Lp sphere mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "center": [1., 2., 3., ...], // (an array of D floats)
+    ///     "radius": 4., // (must be a float of magnitude > Float::EPSILON ~= 10^-16 )
+    ///     "p": 3., // (a float >= 1, or the string "inf")
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn std::error::Error>> {
+        let center = json
+            .get("center")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse center")?;
+
+        let radius = json
+            .get("radius")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse radius")? as Float;
+
+        let p = json
+            .get("p")
+            .and_then(|p| {
+                p.as_f64()
+                    .or_else(|| (p.as_str()? == "inf").then_some(Float::INFINITY))
+            })
+            .ok_or("Failed to parse p")? as Float;
+
+        Self::new(center, radius, p)
+            .ok_or("radius must not be too close to 0.0, and p must be >= 1".into())
+    }
+}
+
+impl<const D: usize> JsonSer for LpSphereMirror<D> {
+    /// Serialize an Lp sphere mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        let p = if self.p.is_infinite() {
+            serde_json::json!("inf")
+        } else {
+            serde_json::json!(self.p)
+        };
+
+        serde_json::json!({
+            "center": self.center.as_slice(),
+            "radius": self.radius(),
+            "p": p,
+        })
+    }
+}
+
+impl render::OpenGLRenderable for LpSphereMirror<2> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        // enlarge the sampled square a bit, otherwise, the sides of the square (p = inf)
+        // would be right on the boundary of the grid
+        let vertices = Vec::from_iter(
+            implicit::contour_2d(|v| self.eval(v), &self.center, self.radius * 1.01)
+                .into_iter()
+                .map(render::Vertex::from),
+        );
+
+        list.push(Box::new(implicit::ContourRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl render::OpenGLRenderable for LpSphereMirror<3> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        let vertices = Vec::from_iter(
+            implicit::contour_3d(|v| self.eval(v), &self.center, self.radius * 1.01)
+                .into_iter()
+                .map(render::Vertex::from),
+        );
+
+        list.push(Box::new(implicit::ContourRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl<const D: usize> Random for LpSphereMirror<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        const MAX_RADIUS: Float = 3.0;
+        const MAX_P: Float = 6.0;
+
+        loop {
+            // the special cases p = 1 and p = inf are given a fair chance
+            let p = match rng.gen_range(0..4) {
+                0 => 1.0,
+                1 => Float::INFINITY,
+                _ => 1.0 + rng.gen::<Float>() * (MAX_P - 1.0),
+            };
+
+            if let Some(mirror) = Self::new(
+                util::rand_vect(rng, 9.0),
+                rng.gen::<Float>() * MAX_RADIUS,
+                p,
+            ) {
+                break mirror;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,10 +509,10 @@ mod tests {
         }))
         .expect("json error");
 
-        let mut ray = Ray {
-            origin: [-2., 0., 0.].into(),
-            direction: Unit::new_normalize([1., 0., 0.].into()),
-        };
+        let mut ray = Ray::new(
+            [-2., 0., 0.].into(),
+            Unit::new_normalize([1., 0., 0.].into()),
+        );
 
         let mut intersections = vec![];
         mirror.append_intersecting_points(&ray, List::from(&mut intersections));
@@ -219,10 +548,10 @@ mod tests {
         }))
         .expect("json error");
 
-        let ray = Ray {
-            origin: [-2., 0., 0.].into(),
-            direction: Unit::new_normalize([0., 1., 0.].into()),
-        };
+        let ray = Ray::new(
+            [-2., 0., 0.].into(),
+            Unit::new_normalize([0., 1., 0.].into()),
+        );
 
         let mut intersections = vec![];
         mirror.append_intersecting_points(&ray, List::from(&mut intersections));
@@ -238,10 +567,10 @@ mod tests {
         }))
         .expect("json error");
 
-        let mut ray = Ray {
-            origin: [-2., -1., 0.].into(),
-            direction: Unit::new_normalize([1., 1., 0.].into()),
-        };
+        let mut ray = Ray::new(
+            [-2., -1., 0.].into(),
+            Unit::new_normalize([1., 1., 0.].into()),
+        );
 
         let mut intersections = vec![];
         mirror.append_intersecting_points(&ray, List::from(&mut intersections));
@@ -285,4 +614,110 @@ mod tests {
         assert_eq!(mirror.center, mirror2.center);
         assert_eq!(mirror.radius(), mirror2.radius());
     }
+
+    #[test]
+    fn test_lp_euclidean() {
+        // p = 2 must behave like the euclidean sphere
+        let lp = LpSphereMirror::<3>::new([1., 0., 0.].into(), 2., 2.).unwrap();
+        let euclidean = EuclideanSphereMirror::<3>::new([1., 0., 0.].into(), 2.).unwrap();
+
+        let ray = Ray::new(
+            [-4., -1., 0.5].into(),
+            Unit::new_normalize([1., 0.3, 0.].into()),
+        );
+
+        let (mut a, mut b) = (vec![], vec![]);
+        lp.append_intersecting_points(&ray, List::from(&mut a));
+        euclidean.append_intersecting_points(&ray, List::from(&mut b));
+
+        assert_eq!(a.len(), 2);
+        assert_eq!(b.len(), 2);
+
+        for (x, y) in a.iter().zip(&b) {
+            let (t_x, t_y) = (x.try_ray_intersection(&ray), y.try_ray_intersection(&ray));
+            assert!((t_x.unwrap() - t_y.unwrap()).abs() < 1e-9);
+
+            let (TangentSpace::Normal(n_x), TangentSpace::Normal(n_y)) = (x.direction, y.direction)
+            else {
+                panic!("expected normals");
+            };
+            assert!((n_x.into_inner() - n_y.into_inner()).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_lp_cube() {
+        let mirror = LpSphereMirror::<3>::from_json(&json!({
+            "center": [0., 0., 0.],
+            "radius": 1.,
+            "p": "inf",
+        }))
+        .expect("json error");
+
+        let mut ray = Ray::new(
+            [-3., 0.5, 0.25].into(),
+            Unit::new_normalize([1., 0., 0.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [tangent, _] = intersections.as_slice() else {
+            panic!("there must be two intersections");
+        };
+
+        let t = tangent.try_ray_intersection(&ray).unwrap();
+        assert!((t - 2.).abs() < 1e-9);
+
+        ray.advance(t);
+        ray.reflect_dir(&tangent.direction);
+
+        assert!((ray.direction.into_inner() - SVector::from([-1., 0., 0.])).norm() < 1e-9);
+
+        // on an edge of the cube, the normal is the mean of both faces' normals
+        let normal = mirror.normal(&[1., 1., 0.3].into()).normalize();
+        assert!((normal - SVector::from([1., 1., 0.]).normalize()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_lp_diamond() {
+        let mirror = LpSphereMirror::<2>::new([0., 0.].into(), 1., 1.).unwrap();
+
+        // hits the side x + y = 1 at (0.5, 0.5)
+        let ray = Ray::new([0.5, -3.].into(), Unit::new_normalize([0., 1.].into()));
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [_, tangent] = intersections.as_slice() else {
+            panic!("there must be two intersections");
+        };
+
+        assert!((tangent.try_ray_intersection(&ray).unwrap() - 3.5).abs() < 1e-9);
+
+        let TangentSpace::Normal(normal) = tangent.direction else {
+            panic!("expected a normal");
+        };
+        assert!((normal.into_inner() - SVector::from([1., 1.]).normalize()).norm() < 1e-9);
+
+        // at a vertex, the normal points along the axis
+        let normal = mirror.normal(&[0., 1.].into()).normalize();
+        assert!((normal - SVector::from([0., 1.])).norm() < 1e-9);
+
+        // misses the diamond, but goes through it's bounding square
+        let ray = Ray::new([0.9, -3.].into(), Unit::new_normalize([0.05, 1.].into()));
+
+        intersections.clear();
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+        assert!(intersections.is_empty());
+    }
+
+    #[test]
+    fn test_lp_json() {
+        for p in [1.5, Float::INFINITY] {
+            let mirror = LpSphereMirror::<2>::new([1., 2.].into(), 3., p).unwrap();
+            let mirror2 = LpSphereMirror::<2>::from_json(&mirror.to_json()).expect("json error");
+            assert_eq!(mirror, mirror2);
+        }
+    }
 }
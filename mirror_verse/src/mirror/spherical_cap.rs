@@ -0,0 +1,343 @@
+use super::*;
+
+/// The part of a euclidean sphere within a certain angle (the half-angle) of an axis
+/// going through it's center.
+///
+/// In 3D, this is a (concave or convex) dish, and in 2D, a circular arc.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphericalCapMirror<const D: usize> {
+    center: SVector<Float, D>,
+    radius: Float,
+    axis: Unit<SVector<Float, D>>,
+    half_angle: Float,
+    cos_half_angle: Float,
+}
+
+impl<const D: usize> SphericalCapMirror<D> {
+    /// Create a new spherical cap from the center and radius of the sphere it belongs to,
+    /// the direction of the cap's apex, seen from the center, and the angle (in radians)
+    /// between that direction and the cap's rim.
+    ///
+    /// Returns `None` if `radius` or `axis` are too close to zero,
+    /// or if `half_angle` isn't in `]0 ; pi]`
+    pub fn new(
+        center: SVector<Float, D>,
+        radius: Float,
+        axis: SVector<Float, D>,
+        half_angle: Float,
+    ) -> Option<Self> {
+        const E: Float = Float::EPSILON * 8.0;
+
+        let axis = Unit::try_new(axis, E)?;
+
+        (radius.abs() > E && half_angle > 0.0 && half_angle <= core::f64::consts::PI).then(|| {
+            Self {
+                center,
+                radius: radius.abs(),
+                axis,
+                half_angle,
+                cos_half_angle: half_angle.cos(),
+            }
+        })
+    }
+
+    pub fn center(&self) -> &SVector<Float, D> {
+        &self.center
+    }
+
+    pub fn radius(&self) -> Float {
+        self.radius
+    }
+
+    pub fn axis(&self) -> &Unit<SVector<Float, D>> {
+        &self.axis
+    }
+
+    pub fn half_angle(&self) -> Float {
+        self.half_angle
+    }
+
+    /// The apex of the cap, i. e. the point of the cap on it's axis
+    pub fn apex(&self) -> SVector<Float, D> {
+        self.center + self.axis.as_ref() * self.radius
+    }
+}
+
+impl<const D: usize> Mirror<D> for SphericalCapMirror<D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        // same as the sphere (see sphere.rs), but we only keep the points whose
+        // angle with the axis, seen from the center, is smaller than the half-angle
+
+        let d = &ray.direction;
+        let a = d.norm_squared();
+
+        let v = ray.origin - self.center;
+        let b = v.dot(d);
+        let c = v.norm_squared() - self.radius * self.radius;
+
+        let delta = b * b - a * c;
+
+        if delta > Float::EPSILON {
+            let root_delta = delta.sqrt();
+            let neg_b = -b;
+
+            for t in [(neg_b - root_delta) / a, (neg_b + root_delta) / a] {
+                // SAFETY: the vector `ray.at(t) - self.center` always has length `self.radius`
+                let normal = Unit::new_unchecked((ray.at(t) - self.center) / self.radius);
+
+                if normal.dot(&self.axis) >= self.cos_half_angle {
+                    list.push(TangentPlane {
+                        intersection: Intersection::Distance(t),
+                        direction: TangentSpace::Normal(normal),
+                        surface: Surface::Reflective,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<const D: usize> JsonType for SphericalCapMirror<D> {
+    fn json_type() -> String {
+        "spherical_cap".into()
+    }
+}
+
+impl<const D: usize> JsonDes for SphericalCapMirror<D> {
+    /// Deserialize a new spherical cap mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "center": [1., 2., 3., ...], // (an array of D floats)
+    ///     "radius": 4., // (must be a float of magnitude > Float::EPSILON ~= 10^-16)
+    ///     "axis": [0., 0., 1., ...], // (an array of D floats, must not be the zero vector)
+    ///     "half_angle": 0.5, // (in radians, must be in ]0 ; pi])
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let center = json
+            .get("center")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse center")?;
+
+        let radius = json
+            .get("radius")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse radius")? as Float;
+
+        let axis = json
+            .get("axis")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse axis")?;
+
+        let half_angle = json
+            .get("half_angle")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse half_angle")? as Float;
+
+        Self::new(center, radius, axis, half_angle).ok_or(
+            "radius and axis must not be too close to zero, and half_angle must be in ]0 ; pi]"
+                .into(),
+        )
+    }
+}
+
+impl<const D: usize> JsonSer for SphericalCapMirror<D> {
+    /// Serialize a spherical cap mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "center": self.center.as_slice(),
+            "radius": self.radius,
+            "axis": self.axis.as_slice(),
+            "half_angle": self.half_angle,
+        })
+    }
+}
+
+struct SphericalCapRenderData<const D: usize> {
+    vertices: gl::VertexBuffer<render::Vertex<D>>,
+}
+
+impl<const D: usize> render::RenderData for SphericalCapRenderData<D> {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: match D {
+                0 => unreachable!("dimension must not be zero"),
+                1 | 2 => gl::index::PrimitiveType::LineStrip,
+                _ => gl::index::PrimitiveType::TrianglesList,
+            },
+        }
+    }
+}
+
+impl render::OpenGLRenderable for SphericalCapMirror<2> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        const NUM_POINTS: usize = 360;
+
+        let [a, u] = util::basis_from_vector(&self.axis);
+
+        let vertices: Vec<_> = (0..=NUM_POINTS)
+            .map(|i| {
+                let angle = (2.0 * i as Float / NUM_POINTS as Float - 1.0) * self.half_angle;
+                let (sin, cos) = angle.sin_cos();
+                self.center + (cos * a + sin * u) * self.radius
+            })
+            .map(render::Vertex2D::from)
+            .collect();
+
+        list.push(Box::new(SphericalCapRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl render::OpenGLRenderable for SphericalCapMirror<3> {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        const POLAR_DIVISIONS: usize = 60;
+        const AZIMUTHAL_DIVISIONS: usize = 120;
+
+        use core::f64::consts::TAU;
+
+        let [a, u, v] = util::basis_from_vector(&self.axis);
+
+        let point = |i: usize, j: usize| {
+            let (sin_p, cos_p) =
+                (i as Float / POLAR_DIVISIONS as Float * self.half_angle).sin_cos();
+            let (sin_a, cos_a) = (j as Float / AZIMUTHAL_DIVISIONS as Float * TAU).sin_cos();
+            self.center + (cos_p * a + sin_p * (cos_a * u + sin_a * v)) * self.radius
+        };
+
+        let vertices: Vec<_> = (0..POLAR_DIVISIONS)
+            .flat_map(|i| (0..AZIMUTHAL_DIVISIONS).map(move |j| (i, j)))
+            .flat_map(|(i, j)| {
+                let [a, b, c, d] =
+                    [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)].map(|(i, j)| point(i, j));
+                [a, b, c, a, c, d]
+            })
+            .map(render::Vertex3D::from)
+            .collect();
+
+        list.push(Box::new(SphericalCapRenderData {
+            vertices: gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap(),
+        }))
+    }
+}
+
+impl<const D: usize> Random for SphericalCapMirror<D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        const MAX_RADIUS: Float = 4.0;
+
+        use core::f64::consts::PI;
+
+        loop {
+            if let Some(mirror) = Self::new(
+                util::rand_vect(rng, 9.0),
+                rng.gen::<Float>() * MAX_RADIUS,
+                util::rand_vect(rng, 1.0),
+                rng.gen::<Float>() * PI,
+            ) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_concave_dish() {
+        use core::f64::consts::FRAC_PI_4;
+
+        // a dish, opening towards -z, with it's apex at (0, 0, 2)
+        let mirror = SphericalCapMirror::<3>::from_json(&json!({
+            "center": [0., 0., 0.],
+            "radius": 2.,
+            "axis": [0., 0., 3.],
+            "half_angle": FRAC_PI_4,
+        }))
+        .expect("json error");
+
+        let mut ray = Ray::new(
+            [0., 0., -5.].into(),
+            Unit::new_normalize([0., 0., 1.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        // the other side of the sphere, at (0, 0, -2) is not part of the cap
+        let [tangent] = intersections.as_slice() else {
+            panic!("there must be one intersection");
+        };
+
+        let t = tangent.try_ray_intersection(&ray).unwrap();
+        assert!((t - 7.).abs() < Float::EPSILON * 16.0);
+
+        ray.advance(t);
+        ray.reflect_dir(&tangent.direction);
+
+        assert!((ray.origin - mirror.apex()).norm() < Float::EPSILON * 16.0);
+        assert!(
+            (ray.direction.into_inner() - SVector::from([0., 0., -1.])).norm()
+                < Float::EPSILON * 4.0
+        );
+    }
+
+    #[test]
+    fn test_arc_bounds() {
+        use core::f64::consts::FRAC_PI_2;
+
+        // the right half of the unit circle
+        let mirror =
+            SphericalCapMirror::<2>::new([0., 0.].into(), 1., [1., 0.].into(), FRAC_PI_2).unwrap();
+
+        let mut intersections = vec![];
+
+        // goes through the circle above the arc's endpoints
+        for y in [0.5, -0.5] {
+            let ray = Ray::new([-3., y].into(), Unit::new_normalize([1., 0.].into()));
+            intersections.clear();
+            mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+            assert_eq!(intersections.len(), 1);
+        }
+
+        // misses the arc entirely, but intersects the circle twice
+        let ray = Ray::new([-0.5, -3.].into(), Unit::new_normalize([0., 1.].into()));
+        intersections.clear();
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+        assert!(intersections.is_empty());
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror =
+            SphericalCapMirror::<3>::new([1., 2., 3.].into(), 4., [0., 1., 0.].into(), 1.).unwrap();
+
+        let mirror2 = SphericalCapMirror::<3>::from_json(&mirror.to_json()).expect("json error");
+
+        assert_eq!(mirror, mirror2);
+    }
+}
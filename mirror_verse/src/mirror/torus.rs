@@ -0,0 +1,478 @@
+use super::*;
+
+/// A ring torus: all points at distance `minor_radius` from the circle of
+/// radius `major_radius`, centered at `center`, and orthogonal to `axis`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TorusMirror {
+    center: SVector<Float, 3>,
+    axis: Unit<SVector<Float, 3>>,
+    major_radius: Float,
+    minor_radius: Float,
+}
+
+impl TorusMirror {
+    /// Create a new torus mirror.
+    ///
+    /// Returns `None` if one of the radii is too close to zero, or `axis` is, or if
+    /// `minor_radius` isn't smaller than `major_radius`, (for horn, and spindle tori)
+    pub fn new(
+        center: SVector<Float, 3>,
+        axis: SVector<Float, 3>,
+        major_radius: Float,
+        minor_radius: Float,
+    ) -> Option<Self> {
+        const E: Float = Float::EPSILON * 8.0;
+
+        let axis = Unit::try_new(axis, E)?;
+        let (major_radius, minor_radius) = (major_radius.abs(), minor_radius.abs());
+
+        (minor_radius > E && minor_radius < major_radius).then_some(Self {
+            center,
+            axis,
+            major_radius,
+            minor_radius,
+        })
+    }
+
+    pub fn center(&self) -> &SVector<Float, 3> {
+        &self.center
+    }
+
+    pub fn axis(&self) -> &Unit<SVector<Float, 3>> {
+        &self.axis
+    }
+
+    pub fn major_radius(&self) -> Float {
+        self.major_radius
+    }
+
+    pub fn minor_radius(&self) -> Float {
+        self.minor_radius
+    }
+
+    /// The normal to the torus at `p` (assumed to be on the torus), pointing outwards.
+    pub fn normal_at(&self, p: &SVector<Float, 3>) -> Option<Unit<SVector<Float, 3>>> {
+        let q = p - self.center;
+        let n = self.axis.as_ref();
+        let radial = q - q.dot(n) * n;
+        // the closest point to `p` on the torus' core circle
+        let core_pt = Unit::try_new(radial, Float::EPSILON)?.into_inner() * self.major_radius;
+        Unit::try_new(q - core_pt, Float::EPSILON)
+    }
+}
+
+/// Newton-Raphson polishing of a root of the monic polynomial
+/// with coefficients `coefs` (highest degree first, excluding the leading `1`)
+fn polish_root<const N: usize>(coefs: [Float; N], mut x: Float) -> Float {
+    for _ in 0..4 {
+        let (p, dp) = coefs
+            .iter()
+            .fold((1.0, 0.0), |(p, dp), c| (p * x + c, dp * x + p));
+
+        if dp.abs() <= Float::EPSILON {
+            break;
+        }
+
+        let next = x - p / dp;
+        if !next.is_finite() {
+            break;
+        }
+        x = next;
+    }
+    x
+}
+
+/// Returns the real roots of `x^2 + b * x + c`, in increasing order
+pub(crate) fn solve_quadratic(b: Float, c: Float) -> Option<[Float; 2]> {
+    let delta = b * b - 4.0 * c;
+
+    (delta >= 0.0).then(|| {
+        // avoid catastrophic cancellation
+        let q = -0.5 * (b + b.signum() * delta.sqrt());
+        if q == 0.0 {
+            [0.0; 2]
+        } else {
+            let (x1, x2) = (q, c / q);
+            [x1.min(x2), x1.max(x2)]
+        }
+    })
+}
+
+/// Returns the real roots of the monic cubic `x^3 + a * x^2 + b * x + c`
+pub fn solve_cubic(a: Float, b: Float, c: Float) -> Vec<Float> {
+    use core::f64::consts::TAU;
+
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let shift = a / 3.0;
+
+    let roots = if r * r < q * q * q {
+        // three real roots, use the trigonometric method
+        let theta = (r / (q * q * q).sqrt()).acos();
+        let m = -2.0 * q.sqrt();
+        Vec::from([0.0, TAU, -TAU].map(|phase| m * ((theta + phase as Float) / 3.0).cos() - shift))
+    } else {
+        // one real root, Cardano's formula
+        let big_a = -r.signum() * (r.abs() + (r * r - q * q * q).sqrt()).cbrt();
+        let big_b = if big_a == 0.0 { 0.0 } else { q / big_a };
+        vec![big_a + big_b - shift]
+    };
+
+    Vec::from_iter(roots.into_iter().map(|x| polish_root([a, b, c], x)))
+}
+
+/// Returns the real roots of the monic quartic `x^4 + a * x^3 + b * x^2 + c * x + d`, in no particular order.
+///
+/// Uses Ferrari's method on the depressed quartic, then polishes
+/// the roots with a few Newton-Raphson iterations.
+pub fn solve_quartic(a: Float, b: Float, c: Float, d: Float) -> Vec<Float> {
+    // substitute x = y - a / 4, to get y^4 + p * y^2 + q * y + r
+    let a2 = a * a;
+    let p = b - 3.0 / 8.0 * a2;
+    let q = c - a * b / 2.0 + a2 * a / 8.0;
+    let r = d - a * c / 4.0 + a2 * b / 16.0 - 3.0 / 256.0 * a2 * a2;
+
+    let shift = a / 4.0;
+    let scale = 1.0 + p.abs() + r.abs().sqrt();
+
+    let mut roots = Vec::with_capacity(4);
+
+    if q.abs() <= Float::EPSILON * 64.0 * scale * scale {
+        // biquadratic: solve for z = y^2
+        for z in solve_quadratic(p, r).into_iter().flatten() {
+            if z >= 0.0 {
+                let y = z.sqrt();
+                roots.extend([y, -y]);
+            }
+        }
+    } else {
+        // find m > 0 such that (y^2 + p/2 + m)^2 = (sqrt(2m) * y - q / (2 * sqrt(2m)))^2
+        // m is a root of the resolvent cubic, and there is always a positive one since q != 0
+        let m = solve_cubic(p, p * p / 4.0 - r, -q * q / 8.0)
+            .into_iter()
+            .fold(Float::NEG_INFINITY, Float::max);
+
+        if m > 0.0 {
+            let s = (2.0 * m).sqrt();
+            let k = q / (2.0 * s);
+
+            for [y1, y2] in [
+                solve_quadratic(-s, p / 2.0 + m + k),
+                solve_quadratic(s, p / 2.0 + m - k),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                roots.extend([y1, y2]);
+            }
+        }
+    }
+
+    Vec::from_iter(
+        roots
+            .into_iter()
+            .map(|y| polish_root([a, b, c, d], y - shift)),
+    )
+}
+
+impl Mirror<3> for TorusMirror {
+    fn append_intersecting_points(&self, ray: &Ray<3>, mut list: List<TangentPlane<3>>) {
+        // substituting V for P + t * D in the torus equation:
+        // (||V - C||^2 + R^2 - r^2)^2 = 4 * R^2 * ||proj(V - C)||^2
+        // (where proj is the orthogonal projection onto the plane orthogonal to the axis)
+        // results in a quartic equation in t
+
+        let n = self.axis.as_ref();
+        let proj = |v: SVector<Float, 3>| v - v.dot(n) * n;
+
+        let q = ray.origin - self.center;
+        let d = ray.direction.into_inner();
+
+        let (big_r, r) = (self.major_radius, self.minor_radius);
+        let four_r2 = 4.0 * big_r * big_r;
+
+        // ||V - C||^2 + R^2 - r^2 = t^2 + b * t + c, since ||D|| = 1
+        let b = 2.0 * q.dot(&d);
+        let c = q.norm_squared() + big_r * big_r - r * r;
+
+        // ||proj(V - C)||^2 = alpha * t^2 + beta * t + gamma
+        let (qp, dp) = (proj(q), proj(d));
+        let alpha = dp.norm_squared();
+        let beta = 2.0 * qp.dot(&dp);
+        let gamma = qp.norm_squared();
+
+        let roots = solve_quartic(
+            2.0 * b,
+            b * b + 2.0 * c - four_r2 * alpha,
+            2.0 * b * c - four_r2 * beta,
+            c * c - four_r2 * gamma,
+        );
+
+        for t in roots {
+            if let Some(normal) = self.normal_at(&ray.at(t)) {
+                list.push(TangentPlane {
+                    intersection: Intersection::Distance(t),
+                    direction: TangentSpace::Normal(normal),
+                    surface: Surface::Reflective,
+                });
+            }
+        }
+    }
+}
+
+impl csg::Solid<3> for TorusMirror {
+    fn contains(&self, p: &SVector<Float, 3>) -> bool {
+        let v = p - self.center;
+        let h = v.dot(&self.axis);
+        let dist_to_circle = (v - h * self.axis.into_inner()).norm() - self.major_radius;
+
+        dist_to_circle * dist_to_circle + h * h <= self.minor_radius * self.minor_radius
+    }
+}
+
+impl JsonType for TorusMirror {
+    fn json_type() -> String {
+        "torus".into()
+    }
+}
+
+impl JsonDes for TorusMirror {
+    /// Deserialize a new torus mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "center": [1.0, 2.0, 3.0],
+    ///     "axis": [0.0, 0.0, 1.0], // (must not be the zero vector)
+    ///     "major_radius": 3.0,
+    ///     "minor_radius": 1.0, // (must be smaller than major_radius)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let center = json
+            .get("center")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse center")?;
+
+        let axis = json
+            .get("axis")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .ok_or("Failed to parse axis")?;
+
+        let major_radius = json
+            .get("major_radius")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse major_radius")? as Float;
+
+        let minor_radius = json
+            .get("minor_radius")
+            .and_then(serde_json::Value::as_f64)
+            .ok_or("Failed to parse minor_radius")? as Float;
+
+        Self::new(center, axis, major_radius, minor_radius).ok_or(
+            "radii must not be too close to 0.0, minor_radius must be smaller than \
+            major_radius, and axis must not be the zero vector"
+                .into(),
+        )
+    }
+}
+
+impl JsonSer for TorusMirror {
+    /// Serialize a torus mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "center": self.center.as_slice(),
+            "axis": self.axis.as_slice(),
+            "major_radius": self.major_radius,
+            "minor_radius": self.minor_radius,
+        })
+    }
+}
+
+struct TorusRenderData {
+    vertices: gl::VertexBuffer<render::Vertex3D>,
+}
+
+impl render::RenderData for TorusRenderData {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        (&self.vertices).into()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        gl::index::IndicesSource::NoIndices {
+            primitives: gl::index::PrimitiveType::TrianglesList,
+        }
+    }
+}
+
+impl render::OpenGLRenderable for TorusMirror {
+    fn append_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) {
+        const MAJOR_DIVISIONS: usize = 120;
+        const MINOR_DIVISIONS: usize = 40;
+
+        use core::f64::consts::TAU;
+
+        let [n, u, v] = util::basis_from_vector(&self.axis);
+
+        let point = |i: usize, j: usize| {
+            let (sin_u, cos_u) = (i as Float / MAJOR_DIVISIONS as Float * TAU).sin_cos();
+            let (sin_v, cos_v) = (j as Float / MINOR_DIVISIONS as Float * TAU).sin_cos();
+            let radial = cos_u * u + sin_u * v;
+            self.center
+                + (self.major_radius + self.minor_radius * cos_v) * radial
+                + self.minor_radius * sin_v * n
+        };
+
+        let vertices: Vec<_> = (0..MAJOR_DIVISIONS)
+            .flat_map(|i| (0..MINOR_DIVISIONS).map(move |j| (i, j)))
+            .flat_map(|(i, j)| {
+                let [a, b, c, d] =
+                    [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)].map(|(i, j)| point(i, j));
+                [a, b, c, a, c, d]
+            })
+            .map(render::Vertex3D::from)
+            .collect();
+
+        let vertices = gl::VertexBuffer::immutable(display, vertices.as_slice()).unwrap();
+
+        list.push(Box::new(TorusRenderData { vertices }))
+    }
+}
+
+impl Random for TorusMirror {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self
+    where
+        Self: Sized,
+    {
+        const MAX_MAJOR_RADIUS: Float = 4.0;
+
+        loop {
+            let major_radius = rng.gen::<Float>() * MAX_MAJOR_RADIUS;
+            if let Some(mirror) = Self::new(
+                util::rand_vect(rng, 9.0),
+                util::rand_vect(rng, 1.0),
+                major_radius,
+                rng.gen::<Float>() * major_radius * 0.5,
+            ) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        let mut roots = solve_quartic(-10., 35., -50., 24.);
+        roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(roots.len(), 4);
+        for (x, expected) in roots.into_iter().zip([1., 2., 3., 4.]) {
+            assert!((x - expected).abs() < 1e-9);
+        }
+
+        // (x^2 + 1)(x - 2)(x + 3)
+        let mut roots = solve_quartic(1., -5., 1., -6.);
+        roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0] + 3.).abs() < 1e-9);
+        assert!((roots[1] - 2.).abs() < 1e-9);
+
+        // x^4 + 1
+        assert!(solve_quartic(0., 0., 0., 1.).is_empty());
+    }
+
+    #[test]
+    fn test_through_hole() {
+        let mirror = TorusMirror::from_json(&json!({
+            "center": [0., 0., 0.],
+            "axis": [0., 0., 1.],
+            "major_radius": 2.,
+            "minor_radius": 0.5,
+        }))
+        .expect("json error");
+
+        let mut ray = Ray::new(
+            [-5., 0., 0.].into(),
+            Unit::new_normalize([1., 0., 0.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let mut hits = Vec::from_iter(
+            intersections
+                .iter()
+                .map(|tangent| (tangent.try_ray_intersection(&ray).unwrap(), tangent)),
+        );
+        hits.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+
+        assert_eq!(hits.len(), 4);
+        for ((t, _), expected) in hits.iter().zip([2.5, 3.5, 6.5, 7.5]) {
+            assert!((t - expected).abs() < 1e-9);
+        }
+
+        let (t, tangent) = hits[0];
+        ray.advance(t);
+        ray.reflect_dir(&tangent.direction);
+
+        assert!((ray.direction.into_inner() - SVector::from([-1., 0., 0.])).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_along_axis() {
+        let mirror = TorusMirror::new(SVector::zeros(), [0., 0., 1.].into(), 2., 0.5).unwrap();
+
+        // straight through the hole, no intersections
+        let ray = Ray::new(
+            [0., 0., -5.].into(),
+            Unit::new_normalize([0., 0., 1.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+        assert!(intersections.is_empty());
+
+        // through the tube
+        let ray = Ray::new(
+            [2., 0., -5.].into(),
+            Unit::new_normalize([0., 0., 1.].into()),
+        );
+
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+        assert_eq!(intersections.len(), 2);
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror = TorusMirror::new([1., 2., 3.].into(), [1., 1., 0.].into(), 3., 1.).unwrap();
+
+        let mirror2 = TorusMirror::from_json(&mirror.to_json()).expect("json error");
+
+        assert!((mirror.center() - mirror2.center()).norm() < Float::EPSILON * 4.0);
+        assert!((mirror.axis().as_ref() - mirror2.axis().as_ref()).norm() < Float::EPSILON * 4.0);
+        assert_eq!(mirror.major_radius(), mirror2.major_radius());
+        assert_eq!(mirror.minor_radius(), mirror2.minor_radius());
+
+        // horn, and spindle tori
+        assert!(TorusMirror::new(SVector::zeros(), [0., 0., 1.].into(), 1., 1.).is_none());
+        assert!(TorusMirror::new(SVector::zeros(), [0., 0., 1.].into(), 1., 2.).is_none());
+    }
+}
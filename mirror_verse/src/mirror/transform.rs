@@ -0,0 +1,564 @@
+use super::*;
+
+/// A mirror moved around by a rigid transformation, a rotation, followed by a uniform scaling,
+/// then a translation: a point `p` of the inner mirror is at `scale * rotation * p + translation`.
+///
+/// Rays are mapped to the inner mirror's frame before intersecting, and the tangent
+/// planes it returns are mapped back, so any mirror can be transformed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transformed<T, const D: usize> {
+    inner: T,
+    rotation: SMatrix<Float, D, D>,
+    translation: SVector<Float, D>,
+    scale: Float,
+}
+
+/// The rotation in the plane spanned by `u` and `v` (which must be orthonormal),
+/// that maps `u` to `cos(angle) * u + sin(angle) * v`, leaving it's orthogonal complement fixed
+pub fn plane_rotation<const D: usize>(
+    u: &SVector<Float, D>,
+    v: &SVector<Float, D>,
+    angle: Float,
+) -> SMatrix<Float, D, D> {
+    let (sin, cos) = angle.sin_cos();
+
+    SMatrix::identity()
+        + sin * (v * u.transpose() - u * v.transpose())
+        + (cos - 1.0) * (u * u.transpose() + v * v.transpose())
+}
+
+impl<T, const D: usize> Transformed<T, D> {
+    /// Returns `None` if `rotation` isn't a rotation (an orthogonal matrix with a
+    /// determinant of `1`), or if `scale` isn't positive. Reflections are rejected, as they
+    /// would flip the orientation of the inner mirror's surfaces.
+    pub fn new(
+        inner: T,
+        rotation: SMatrix<Float, D, D>,
+        translation: SVector<Float, D>,
+        scale: Float,
+    ) -> Option<Self> {
+        const E: Float = 1e-9;
+
+        let orthogonal =
+            (rotation.transpose() * rotation - SMatrix::<Float, D, D>::identity()).amax() < E;
+
+        let proper = util::determinant(rotation) > 0.0;
+
+        (orthogonal && proper && scale > Float::EPSILON * 8.0).then_some(Self {
+            inner,
+            rotation,
+            translation,
+            scale,
+        })
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn rotation(&self) -> &SMatrix<Float, D, D> {
+        &self.rotation
+    }
+
+    pub fn translation(&self) -> &SVector<Float, D> {
+        &self.translation
+    }
+
+    pub fn scale(&self) -> Float {
+        self.scale
+    }
+
+    /// Maps a point in the inner mirror's frame to the world
+    pub fn point_to_world(&self, p: &SVector<Float, D>) -> SVector<Float, D> {
+        self.scale * (self.rotation * p) + self.translation
+    }
+
+    /// Maps a point in the world to the inner mirror's frame
+    pub fn point_to_local(&self, p: &SVector<Float, D>) -> SVector<Float, D> {
+        self.rotation.tr_mul(&(p - self.translation)) / self.scale
+    }
+
+    /// Maps a ray to the inner mirror's frame. Note that distances along
+    /// the ray are divided by `self.scale()` in the process.
+    pub fn ray_to_local(&self, ray: &Ray<D>) -> Ray<D> {
+        Ray {
+            origin: self.point_to_local(&ray.origin),
+            direction: Unit::new_normalize(self.rotation.tr_mul(ray.direction.as_ref())),
+            polarization: ray
+                .polarization
+                .map(|p| p.rotated(&self.rotation.transpose())),
+            ..*ray
+        }
+    }
+
+    /// Maps a tangent plane, returned by the inner mirror, to the world
+    pub fn tangent_to_world(&self, tangent: &TangentPlane<D>) -> TangentPlane<D> {
+        TangentPlane {
+            intersection: match tangent.intersection {
+                Intersection::Distance(t) => Intersection::Distance(t * self.scale),
+                Intersection::StartingPoint(p) => {
+                    Intersection::StartingPoint(self.point_to_world(&p))
+                }
+            },
+            direction: match tangent.direction {
+                // rotations and uniform scalings preserve orthogonality
+                TangentSpace::Normal(normal) => {
+                    TangentSpace::Normal(Unit::new_normalize(self.rotation * normal.as_ref()))
+                }
+                TangentSpace::Plane(mut plane) => {
+                    let (v0, basis) = plane.vectors.split_first_mut().unwrap();
+                    *v0 = self.point_to_world(v0);
+                    basis.iter_mut().for_each(|v| *v = self.rotation * *v);
+                    TangentSpace::Plane(plane)
+                }
+            },
+            surface: match tangent.surface.rotated(&self.rotation) {
+                // portals also move points, which are scaled, then translated
+                Surface::Portal {
+                    rotation,
+                    translation,
+                } => Surface::Portal {
+                    rotation,
+                    translation: self.scale * translation + self.translation
+                        - rotation * self.translation,
+                },
+                surface => surface,
+            },
+        }
+    }
+}
+
+impl<const D: usize, T: Mirror<D>> Mirror<D> for Transformed<T, D> {
+    fn append_intersecting_points(&self, ray: &Ray<D>, mut list: List<TangentPlane<D>>) {
+        let mut local = vec![];
+        self.inner
+            .append_intersecting_points(&self.ray_to_local(ray), List::from(&mut local));
+
+        list.extend(local.iter().map(|tangent| self.tangent_to_world(tangent)));
+    }
+
+    fn refractive_index_at(&self, p: &SVector<Float, D>) -> Option<(Float, SVector<Float, D>)> {
+        self.inner
+            .refractive_index_at(&self.point_to_local(p))
+            .map(|(n, gradient)| (n, self.rotation * gradient / self.scale))
+    }
+
+    fn append_arc_intersections(
+        &self,
+        arc: &arc::Arc<D>,
+        mut list: List<(Float, TangentPlane<D>)>,
+    ) {
+        let mut local = vec![];
+        self.inner.append_arc_intersections(
+            &arc.mapped(
+                |p| self.point_to_local(p),
+                |v| self.rotation.tr_mul(v) / self.scale,
+            ),
+            List::from(&mut local),
+        );
+
+        list.extend(
+            local
+                .iter()
+                .map(|(angle, tangent)| (*angle, self.tangent_to_world(tangent))),
+        );
+    }
+}
+
+impl<const D: usize, T: csg::Solid<D>> csg::Solid<D> for Transformed<T, D> {
+    fn contains(&self, p: &SVector<Float, D>) -> bool {
+        self.inner.contains(&self.point_to_local(p))
+    }
+}
+
+impl<T, const D: usize> JsonType for Transformed<T, D> {
+    fn json_type() -> String {
+        "transform".into()
+    }
+}
+
+/// Parses a rotation, see [`Transformed::from_json`] for the format
+fn rotation_from_json<const D: usize>(
+    json: &serde_json::Value,
+) -> Result<SMatrix<Float, D, D>, Box<dyn Error>> {
+    if json.is_array() {
+        let rows = util::map_json_array(json, |row| {
+            row.as_array()
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector::<D>)
+                .ok_or("Failed to parse rotation matrix row".into())
+        })?;
+
+        return if rows.len() == D {
+            Ok(SMatrix::from_fn(|i, j| rows[i][j]))
+        } else {
+            Err("rotation matrix must have D rows".into())
+        };
+    }
+
+    let angle = json
+        .get("angle")
+        .and_then(serde_json::Value::as_f64)
+        .ok_or("Failed to parse rotation angle")? as Float;
+
+    let [u, v] = rotation_plane_from_json(json)?;
+
+    Ok(plane_rotation(&u, &v, angle))
+}
+
+/// Parses the orthonormal basis `[u, v]` of a rotation plane, given by the `"plane"`, or
+/// `"axis"`, field of `json`, see [`Transformed::from_json`] for the format.
+pub(super) fn rotation_plane_from_json<const D: usize>(
+    json: &serde_json::Value,
+) -> Result<[SVector<Float, D>; 2], Box<dyn Error>> {
+    let vector = |value: &serde_json::Value| {
+        value
+            .as_array()
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector::<D>)
+            .ok_or("Failed to parse vector")
+    };
+
+    let plane = if let Some(plane) = json.get("plane") {
+        let plane = plane
+            .as_array()
+            .filter(|l| l.len() == 2)
+            .ok_or("rotation plane must be a pair of vectors")?;
+        let mut vectors = [vector(&plane[0])?, vector(&plane[1])?];
+        if SVector::orthonormalize(&mut vectors) != 2 {
+            return Err("the vectors spanning the rotation plane must be free".into());
+        }
+        vectors
+    } else if let Some(axis) = json.get("axis").filter(|_| D == 3) {
+        let axis = Unit::try_new(vector(axis)?, Float::EPSILON).ok_or("axis must not be zero")?;
+        let [a, mut u, mut v] = util::basis_from_vector(&axis)[..] else {
+            unreachable!()
+        };
+        // rotate counterclockwise, when looking from the tip of the axis,
+        // i. e. (a, u, v) must be direct: det(a, u, v) > 0
+        if util::determinant(SMatrix::<Float, D, D>::from_columns(&[a, u, v])) < 0.0 {
+            (u, v) = (v, u);
+        }
+        [u, v]
+    } else if D == 2 {
+        [0, 1].map(|k| SVector::from_fn(|i, _| if i == k { 1.0 } else { 0.0 }))
+    } else {
+        return Err("the rotation plane (or axis, in 3D) must be specified".into());
+    };
+
+    Ok(plane)
+}
+
+impl<T: JsonDes, const D: usize> JsonDes for Transformed<T, D> {
+    /// Deserialize a new transformed mirror from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "translation": [1., 2., 3., ...], // (optional, an array of D floats, defaults to zero)
+    ///     "scale": 2., // (optional, must be positive, defaults to 1)
+    ///     "rotation": // (optional, defaults to the identity) either a D x D rotation matrix:
+    ///         [
+    ///             [1., 0., 0., ...], // (D arrays of D floats, the rows of the matrix)
+    ///             [0., 1., 0., ...],
+    ///             ...
+    ///         ],
+    ///         // or, an angle, in radians, and the plane to rotate in:
+    ///         {
+    ///             "angle": 0.5,
+    ///             "plane": [[1., 0., 0., ...], [0., 1., 0., ...]], // (2 arrays of D floats)
+    ///             // or, only in 3D, the axis to rotate around, counterclockwise:
+    ///             "axis": [0., 0., 1.],
+    ///             // the plane can be omitted in 2D
+    ///         },
+    ///     "mirror": // <the inner mirror's layout>
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let translation = match json.get("translation") {
+            Some(value) => value
+                .as_array()
+                .map(Vec::as_slice)
+                .and_then(util::json_array_to_vector)
+                .ok_or("Failed to parse translation")?,
+            None => SVector::zeros(),
+        };
+
+        let scale = match json.get("scale") {
+            Some(value) => value.as_f64().ok_or("Failed to parse scale")? as Float,
+            None => 1.0,
+        };
+
+        let rotation = match json.get("rotation") {
+            Some(value) => rotation_from_json(value)?,
+            None => SMatrix::identity(),
+        };
+
+        let inner = T::from_json(json.get("mirror").ok_or("Missing inner mirror")?)?;
+
+        Self::new(inner, rotation, translation, scale)
+            .ok_or("rotation must be a rotation matrix, and scale must be positive".into())
+    }
+}
+
+impl<T: JsonSer, const D: usize> JsonSer for Transformed<T, D> {
+    /// Serialize a transformed mirror into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`],
+    /// the rotation is always written as a matrix.
+    fn to_json(&self) -> serde_json::Value {
+        let rotation = Vec::from_iter(
+            self.rotation
+                .row_iter()
+                .map(|row| Vec::from_iter(row.iter().copied())),
+        );
+
+        serde_json::json!({
+            "translation": self.translation.as_slice(),
+            "scale": self.scale,
+            "rotation": rotation,
+            "mirror": self.inner.to_json(),
+        })
+    }
+}
+
+/// Render data transformed by a model matrix, see [`render::RenderData::model`]
+struct TransformedRenderData {
+    inner: Box<dyn render::RenderData>,
+    model: [[f32; 4]; 4],
+}
+
+impl render::RenderData for TransformedRenderData {
+    fn vertices(&self) -> gl::vertex::VerticesSource {
+        self.inner.vertices()
+    }
+
+    fn indices(&self) -> gl::index::IndicesSource {
+        self.inner.indices()
+    }
+
+    fn color(&self) -> Option<[f32; 4]> {
+        self.inner.color()
+    }
+
+    fn model(&self) -> [[f32; 4]; 4] {
+        // compose with the inner data's own transformation (for nested transforms)
+        let [outer, inner] = [self.model, self.inner.model()].map(nalgebra::Matrix4::from);
+        (outer * inner).into()
+    }
+
+    fn model_at(&self, time: Float) -> [[f32; 4]; 4] {
+        let [outer, inner] = [self.model, self.inner.model_at(time)].map(nalgebra::Matrix4::from);
+        (outer * inner).into()
+    }
+}
+
+impl<T, const D: usize> Transformed<T, D> {
+    /// The column-major homogeneous 4x4 matrix of this transformation, only valid if `D <= 3`
+    pub(super) fn model_matrix(&self) -> [[f32; 4]; 4] {
+        let mut model = render::IDENTITY;
+
+        for (j, column) in model.iter_mut().take(D).enumerate() {
+            for (i, x) in column.iter_mut().take(D).enumerate() {
+                *x = (self.scale * self.rotation[(i, j)]) as f32;
+            }
+        }
+
+        for (i, x) in model[3].iter_mut().take(D).enumerate() {
+            *x = self.translation[i] as f32;
+        }
+
+        model
+    }
+
+    fn append_transformed_render_data(
+        &self,
+        display: &gl::Display,
+        mut list: List<Box<dyn render::RenderData>>,
+    ) where
+        T: render::OpenGLRenderable,
+    {
+        let mut inner = vec![];
+        self.inner
+            .append_render_data(display, List::from(&mut inner));
+
+        let model = self.model_matrix();
+
+        list.extend(inner.into_iter().map(|inner| {
+            Box::new(TransformedRenderData { inner, model }) as Box<dyn render::RenderData>
+        }));
+    }
+}
+
+impl<T: render::OpenGLRenderable> render::OpenGLRenderable for Transformed<T, 2> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.append_transformed_render_data(display, list)
+    }
+}
+
+impl<T: render::OpenGLRenderable> render::OpenGLRenderable for Transformed<T, 3> {
+    fn append_render_data(&self, display: &gl::Display, list: List<Box<dyn render::RenderData>>) {
+        self.append_transformed_render_data(display, list)
+    }
+}
+
+impl<T: Random, const D: usize> Random for Transformed<T, D> {
+    fn random(rng: &mut (impl rand::Rng + ?Sized)) -> Self {
+        const MIN_SCALE: Float = 0.5;
+        const MAX_SCALE: Float = 2.0;
+
+        loop {
+            let mut rotation: SMatrix<Float, D, D> =
+                SMatrix::from_columns(&util::basis_from_vector(&util::rand_unit_vect(rng)));
+
+            // make it a rotation, not a reflection
+            if util::determinant(rotation) < 0.0 {
+                rotation.column_mut(0).neg_mut();
+            }
+
+            if let Some(mirror) = Self::new(
+                T::random(rng),
+                rotation,
+                util::rand_vect(rng, 9.0),
+                MIN_SCALE + rng.gen::<Float>() * (MAX_SCALE - MIN_SCALE),
+            ) {
+                break mirror;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_transformed_plane() {
+        use core::f64::consts::FRAC_PI_2;
+
+        // the segment x = 1, y in [-1 ; 1], rotated a quarter turn, scaled
+        // by 2, then translated, becomes y = 3, x in [-1 ; 3]
+        let mirror = Transformed::<plane::PlaneMirror<2>, 2>::from_json(&json!({
+            "translation": [1., 1.],
+            "scale": 2.,
+            "rotation": { "angle": FRAC_PI_2 },
+            "mirror": {
+                "center": [1., 0.],
+                "basis": [[0., 1.]],
+            },
+        }))
+        .expect("json error");
+
+        let mut ray = Ray::new([2.5, 0.].into(), Unit::new_normalize([0., 1.].into()));
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [tangent] = intersections.as_slice() else {
+            panic!("there must be one intersection");
+        };
+
+        let t = tangent.try_ray_intersection(&ray).unwrap();
+        assert!((t - 3.).abs() < 1e-12);
+
+        ray.advance(t);
+        ray.reflect_dir(&tangent.direction);
+
+        assert!((ray.origin - SVector::from([2.5, 3.])).norm() < 1e-12);
+        assert!((ray.direction.into_inner() - SVector::from([0., -1.])).norm() < 1e-12);
+
+        // outside of the transformed segment
+        ray.origin = [3.5, 0.].into();
+        ray.direction = Unit::new_normalize([0., 1.].into());
+        intersections.clear();
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+        assert!(intersections.is_empty());
+    }
+
+    #[test]
+    fn test_transformed_sphere() {
+        // a unit sphere, scaled by 3 and moved to (0, 0, 5), the axis doesn't matter
+        let mirror = Transformed::new(
+            sphere::EuclideanSphereMirror::<3>::new(SVector::zeros(), 1.).unwrap(),
+            plane_rotation(&SVector::x(), &SVector::y(), 1.),
+            [0., 0., 5.].into(),
+            3.,
+        )
+        .unwrap();
+
+        let ray = Ray::new(
+            [1., 0., -5.].into(),
+            Unit::new_normalize([0., 0., 1.].into()),
+        );
+
+        let mut intersections = vec![];
+        mirror.append_intersecting_points(&ray, List::from(&mut intersections));
+
+        let [tangent, _] = intersections.as_slice() else {
+            panic!("there must be two intersections");
+        };
+
+        let t = tangent.try_ray_intersection(&ray).unwrap();
+        let expected = 10. - Float::sqrt(8.);
+        assert!((t - expected).abs() < 1e-12);
+
+        let TangentSpace::Normal(normal) = tangent.direction else {
+            panic!("expected a normal");
+        };
+        let expected_normal = (ray.at(t) - SVector::from([0., 0., 5.])) / 3.;
+        assert!((normal.into_inner() - expected_normal).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_axis_rotation() {
+        use core::f64::consts::FRAC_PI_2;
+
+        let rotation = rotation_from_json::<3>(&json!({
+            "axis": [0., 0., 1.],
+            "angle": FRAC_PI_2,
+        }))
+        .unwrap();
+
+        assert!((rotation * SVector::x() - SVector::y()).norm() < 1e-12);
+        assert!((rotation * SVector::z() - SVector::z()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_reflection_rejected() {
+        let inner =
+            disk::DiskMirror::<3>::new([1., 2., 3.].into(), [0., 0., 1.].into(), 2.).unwrap();
+        let reflection = SMatrix::from_diagonal(&SVector::from([1., 1., -1.]));
+
+        assert!(Transformed::new(inner, reflection, SVector::zeros(), 1.).is_none());
+        assert!(Transformed::<disk::DiskMirror<3>, 3>::from_json(&json!({
+            "rotation": [[0., 1., 0.], [1., 0., 0.], [0., 0., 1.]],
+            "mirror": inner.to_json(),
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let mirror = Transformed::new(
+            disk::DiskMirror::<3>::new([1., 2., 3.].into(), [0., 0., 1.].into(), 2.).unwrap(),
+            plane_rotation(&SVector::y(), &SVector::z(), 0.3),
+            [-1., 0., 1.].into(),
+            0.5,
+        )
+        .unwrap();
+
+        let mirror2 = Transformed::<disk::DiskMirror<3>, 3>::from_json(&mirror.to_json())
+            .expect("json error");
+
+        assert_eq!(mirror.inner(), mirror2.inner());
+        assert_eq!(mirror.translation(), mirror2.translation());
+        assert_eq!(mirror.scale(), mirror2.scale());
+        assert!((mirror.rotation() - mirror2.rotation()).amax() < Float::EPSILON * 4.0);
+    }
+}
@@ -52,9 +52,10 @@ pub(crate) const VERTEX_SHADER_SRC_3D: &str = r#"
     in vec3 position;
     uniform mat4 perspective;
     uniform mat4 view;
+    uniform mat4 model;
 
     void main() {
-        gl_Position = perspective * view * vec4(position, 1.0);
+        gl_Position = perspective * view * model * vec4(position, 1.0);
     }
 "#;
 
@@ -64,18 +65,58 @@ pub(crate) const VERTEX_SHADER_SRC_2D: &str = r#"
     in vec2 position;
     uniform mat4 perspective;
     uniform mat4 view;
+    uniform mat4 model;
 
     void main() {
-        gl_Position = perspective * view * vec4(position, 0.0, 1.0);
+        gl_Position = perspective * view * model * vec4(position, 0.0, 1.0);
     }
 "#;
 
+/// The identity transformation, see [`RenderData::model`]
+pub const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// The (approximate) colour of light of a certain `wavelength`, in nanometres.
+///
+/// Wavelengths outside of the visible spectrum are drawn in grey.
+pub fn wavelength_color(wavelength: Float) -> [f32; 4] {
+    let w = wavelength as f32;
+
+    let [r, g, b] = match w {
+        w if (380.0..440.0).contains(&w) => [(440.0 - w) / 60.0, 0.0, 1.0],
+        w if (440.0..490.0).contains(&w) => [0.0, (w - 440.0) / 50.0, 1.0],
+        w if (490.0..510.0).contains(&w) => [0.0, 1.0, (510.0 - w) / 20.0],
+        w if (510.0..580.0).contains(&w) => [(w - 510.0) / 70.0, 1.0, 0.0],
+        w if (580.0..645.0).contains(&w) => [1.0, (645.0 - w) / 65.0, 0.0],
+        w if (645.0..=750.0).contains(&w) => [1.0, 0.0, 0.0],
+        _ => return [0.5, 0.5, 0.5, 1.0],
+    };
+
+    // darker at the edges of the spectrum, where the eye is less sensitive
+    let intensity = match w {
+        w if w < 420.0 => 0.3 + 0.7 * (w - 380.0) / 40.0,
+        w if w > 700.0 => 0.3 + 0.7 * (750.0 - w) / 50.0,
+        _ => 1.0,
+    };
+
+    [r * intensity, g * intensity, b * intensity, 1.0]
+}
+
 pub(crate) struct RayRenderData<const D: usize> {
     // TODO: find another way to draw this, that preserves
     // it's size no matter how far away you are from it
     pub origin: Box<dyn RenderData>,
+    /// The segments of the path, drawn as a list of lines, see [`crate::RayPath::wraps`]
     pub non_loop_path: VertexBuffer<Vertex<D>>,
     pub loop_path: VertexBuffer<Vertex<D>>,
+    /// The `(non_loop_path, loop_path)` pairs of the branches split from the ray
+    pub branches: Vec<(VertexBuffer<Vertex<D>>, VertexBuffer<Vertex<D>>)>,
+    /// The colour of the (non-looping part of the) path, if it isn't the default one
+    pub color: Option<[f32; 4]>,
 }
 
 pub(crate) struct DrawableSimulation<const D: usize> {
@@ -131,7 +172,11 @@ where
 
         let mut camera_controller = CameraController::new(SPEED, MOUSE_SENSITIVITY);
 
-        let mut last_render_time = time::Instant::now();
+        // the simulation time shown advances by this much every second, to animate moving mirrors
+        const ANIMATION_SPEED: Float = 10.0;
+
+        let start_time = time::Instant::now();
+        let mut last_render_time = start_time;
         let mut mouse_pressed = false;
 
         events_loop.run(move |ev, _, control_flow| match ev {
@@ -195,7 +240,8 @@ where
                 last_render_time = now;
 
                 camera_controller.update_camera(&mut camera, dt);
-                self.render_3d(&display, &camera, &projection);
+                let animation_time = (now - start_time).as_secs_f64() * ANIMATION_SPEED;
+                self.render_3d(&display, &camera, &projection, animation_time);
             }
             event::Event::MainEventsCleared => display.gl_window().window().request_redraw(),
             event::Event::DeviceEvent {
@@ -225,6 +271,7 @@ where
         display: &gl::Display,
         camera: &Camera,
         projection: &Projection,
+        animation_time: Float,
     ) {
         const ORIGIN_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
         const RAY_NON_LOOP_COL: [f32; 4] = [0.7, 0.3, 0.1, 1.0];
@@ -255,33 +302,43 @@ where
         };
 
         for ray in &self.ray_render_data {
-            target
-                .draw(
-                    &ray.non_loop_path,
-                    NoIndices(PrimitiveType::LineStrip),
-                    &self.program,
-                    &gl::uniform! {
-                        perspective: perspective,
-                        view: view,
-                        color_vec: RAY_NON_LOOP_COL,
-                    },
-                    &params,
-                )
-                .unwrap();
-
-            target
-                .draw(
-                    &ray.loop_path,
-                    NoIndices(PrimitiveType::LineStrip),
-                    &self.program,
-                    &gl::uniform! {
-                        perspective: perspective,
-                        view: view,
-                        color_vec: RAY_LOOP_COL,
-                    },
-                    &params,
-                )
-                .unwrap();
+            let paths = iter::once((&ray.non_loop_path, &ray.loop_path)).chain(
+                ray.branches
+                    .iter()
+                    .map(|(non_loop, loop_)| (non_loop, loop_)),
+            );
+
+            for (non_loop_path, loop_path) in paths {
+                target
+                    .draw(
+                        non_loop_path,
+                        NoIndices(PrimitiveType::LinesList),
+                        &self.program,
+                        &gl::uniform! {
+                            perspective: perspective,
+                            view: view,
+                            model: IDENTITY,
+                            color_vec: ray.color.unwrap_or(RAY_NON_LOOP_COL),
+                        },
+                        &params,
+                    )
+                    .unwrap();
+
+                target
+                    .draw(
+                        loop_path,
+                        NoIndices(PrimitiveType::LinesList),
+                        &self.program,
+                        &gl::uniform! {
+                            perspective: perspective,
+                            view: view,
+                            model: IDENTITY,
+                            color_vec: RAY_LOOP_COL,
+                        },
+                        &params,
+                    )
+                    .unwrap();
+            }
 
             let o = &ray.origin;
             target
@@ -292,6 +349,7 @@ where
                     &gl::uniform! {
                         perspective: perspective,
                         view: view,
+                        model: o.model(),
                         color_vec: ORIGIN_COLOR,
                     },
                     &params,
@@ -308,7 +366,8 @@ where
                     &gl::uniform! {
                         perspective: perspective,
                         view: view,
-                        color_vec: mirror_color,
+                        model: render_data.model_at(animation_time),
+                        color_vec: render_data.color().unwrap_or(mirror_color),
                     },
                     &params,
                 )
@@ -328,6 +387,23 @@ where
 pub trait RenderData {
     fn vertices(&self) -> gl::vertex::VerticesSource;
     fn indices(&self) -> gl::index::IndicesSource;
+
+    /// The transformation to apply to the vertices before drawing them,
+    /// as a column-major 4x4 matrix, acting on homogeneous coordinates.
+    fn model(&self) -> [[f32; 4]; 4] {
+        IDENTITY
+    }
+
+    /// Same as [`Self::model`], at time `time` of the simulation, for data
+    /// that moves, (see [`crate::mirror::motion::Moving`]) defaults to [`Self::model`]
+    fn model_at(&self, _time: Float) -> [[f32; 4]; 4] {
+        self.model()
+    }
+
+    /// The colour to draw this with, if it isn't the default one of mirrors
+    fn color(&self) -> Option<[f32; 4]> {
+        None
+    }
 }
 
 // glium_shapes 3d convenience blanket impl
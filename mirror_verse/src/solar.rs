@@ -0,0 +1,418 @@
+use super::*;
+
+use mirror::{aperture::Aperture, plane::PlaneMirror};
+
+/// The angular radius of the sun, seen from the earth, in radians
+pub const SUN_HALF_ANGLE: Float = 4.65e-3;
+
+/// The coefficient of the linear limb-darkening law of the sun, in visible light, see [`SunSource::radiance`]
+pub const SUN_LIMB_DARKENING: Float = 0.6;
+
+/// The area, (or length, in 2D) of the parallelotope of `plane`, see [`PlaneMirror`]
+fn area<const D: usize>(plane: &PlaneMirror<D>) -> Float {
+    let normal = plane.orthonormalised().normal();
+    let basis = plane.inner_plane().basis();
+
+    let m =
+        SMatrix::<Float, D, D>::from_fn(|i, j| if j == 0 { normal[i] } else { basis[j - 1][i] });
+
+    util::determinant(m).abs() * (1 << (D - 1)) as Float
+}
+
+/// Sunlight, falling on an entry aperture: rays start at points spread uniformly over the
+/// aperture, in directions spread over the disk of the sun, (a cone of half-angle `half_angle`
+/// around `direction`) darker near it's limb, see [`Self::radiance`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SunSource<const D: usize> {
+    direction: Unit<SVector<Float, D>>,
+    half_angle: Float,
+    limb_darkening: Float,
+    aperture: PlaneMirror<D>,
+    num_rays: usize,
+}
+
+impl<const D: usize> SunSource<D> {
+    /// `direction` is the one in which sunlight travels.
+    ///
+    /// Returns `None` if there are no rays, if `half_angle` isn't in `[0 ; π/2)`,
+    /// if `limb_darkening` isn't in `[0 ; 1]`, or if `direction` is parallel to the aperture.
+    pub fn new(
+        direction: Unit<SVector<Float, D>>,
+        half_angle: Float,
+        limb_darkening: Float,
+        aperture: PlaneMirror<D>,
+        num_rays: usize,
+    ) -> Option<Self> {
+        let facing = direction.dot(&aperture.orthonormalised().normal()).abs() > Float::EPSILON;
+
+        (num_rays > 0
+            && (0.0..core::f64::consts::FRAC_PI_2).contains(&half_angle)
+            && (0.0..=1.0).contains(&limb_darkening)
+            && facing)
+            .then_some(Self {
+                direction,
+                half_angle,
+                limb_darkening,
+                aperture,
+                num_rays,
+            })
+    }
+
+    pub fn direction(&self) -> &Unit<SVector<Float, D>> {
+        &self.direction
+    }
+
+    pub fn aperture(&self) -> &PlaneMirror<D> {
+        &self.aperture
+    }
+
+    /// The radiance of the sun, relative to that at it's center, at a fraction `r` of it's radius
+    /// from it, with the linear limb-darkening law: `1 - u * (1 - μ)`, where `u` is
+    /// `limb_darkening`, and `μ = sqrt(1 - r²)` is the cosine of the angle between the normal
+    /// to the surface of the sun, and the line of sight.
+    pub fn radiance(&self, r: Float) -> Float {
+        1.0 - self.limb_darkening * (1.0 - (1.0 - r * r).max(0.0).sqrt())
+    }
+
+    /// Returns `num_rays` rays, starting on the aperture, whose directions are drawn from `rng`, with
+    /// a density proportional to the radiance of the sun, and whose powers add up to `1.0`
+    pub fn rays(&self, rng: &mut (impl rand::Rng + ?Sized)) -> Vec<mirror::Ray<D>> {
+        // the first vector is `direction`, the others span the plane orthogonal to it
+        let frame = util::basis_from_vector(&self.direction);
+        let tan = self.half_angle.tan();
+
+        let v0 = self.aperture.inner_plane().v0();
+        let basis = self.aperture.inner_plane().basis();
+
+        Vec::from_iter((0..self.num_rays).map(|_| {
+            let origin = basis
+                .iter()
+                .fold(*v0, |p, v| p + rng.gen_range(-1.0..=1.0) * v);
+
+            // rejection sampling, the radiance being at most 1, at the center of the sun
+            let offset = loop {
+                let coords = util::rand_vect::<D>(rng, 1.0);
+                let r = coords.rows(0, D - 1).norm();
+
+                if r <= 1.0 && rng.gen::<Float>() < self.radiance(r) {
+                    break frame[1..]
+                        .iter()
+                        .zip(&coords)
+                        .map(|(e, c)| *c * e)
+                        .sum::<SVector<Float, D>>();
+                }
+            };
+
+            mirror::Ray {
+                power: 1.0 / self.num_rays as Float,
+                ..mirror::Ray::new(
+                    origin,
+                    Unit::new_normalize(self.direction.into_inner() + tan * offset),
+                )
+            }
+        }))
+    }
+}
+
+impl<const D: usize> JsonDes for SunSource<D> {
+    /// Deserialize a new sun source from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "direction": [0., -1., 0., ...], // (an array of D floats, the direction sunlight travels in)
+    ///     "half_angle": 0.00465, // (optional, in radians, defaults to `SUN_HALF_ANGLE`)
+    ///     "limb_darkening": 0.6, // (optional, in [0 ; 1], defaults to `SUN_LIMB_DARKENING`)
+    ///     "aperture": { // (the entry aperture, see `PlaneMirror`)
+    ///         "center": [1., 2., 3., ...],
+    ///         "basis": [
+    ///             [1., 0., 0., ...],
+    ///             ...
+    ///         ],
+    ///     },
+    ///     "num_rays": 1000, // (a positive integer)
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let direction = json
+            .get("direction")
+            .and_then(serde_json::Value::as_array)
+            .map(Vec::as_slice)
+            .and_then(util::json_array_to_vector)
+            .and_then(|v| Unit::try_new(v, Float::EPSILON))
+            .ok_or("Failed to parse direction")?;
+
+        let get_or = |name: &str, default: Float| {
+            json.get(name)
+                .map(|value| value.as_f64().ok_or(format!("Failed to parse {name}")))
+                .transpose()
+                .map(|value| value.unwrap_or(default))
+        };
+
+        let half_angle = get_or("half_angle", SUN_HALF_ANGLE)?;
+        let limb_darkening = get_or("limb_darkening", SUN_LIMB_DARKENING)?;
+
+        let aperture = PlaneMirror::from_json(json.get("aperture").ok_or("Missing aperture")?)?;
+
+        let num_rays = json
+            .get("num_rays")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or("Failed to parse num_rays")? as usize;
+
+        Self::new(direction, half_angle, limb_darkening, aperture, num_rays).ok_or(
+            "num_rays must be positive, half_angle in [0 ; π/2), limb_darkening in [0 ; 1], \
+            and the direction must not be parallel to the aperture"
+                .into(),
+        )
+    }
+}
+
+impl<const D: usize> JsonSer for SunSource<D> {
+    /// Serialize a sun source into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "direction": self.direction.as_slice(),
+            "half_angle": self.half_angle,
+            "limb_darkening": self.limb_darkening,
+            "aperture": self.aperture.to_json(),
+            "num_rays": self.num_rays,
+        })
+    }
+}
+
+/// A solar concentrator, the mirrors of a [`Simulation`] concentrating the light
+/// of a [`SunSource`] on a flat receiver, which absorbs it, see [`Self::report`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Concentrator<const D: usize> {
+    sun: SunSource<D>,
+    receiver: PlaneMirror<D>,
+}
+
+impl<const D: usize> Concentrator<D> {
+    pub fn new(sun: SunSource<D>, receiver: PlaneMirror<D>) -> Self {
+        Self { sun, receiver }
+    }
+
+    pub fn sun(&self) -> &SunSource<D> {
+        &self.sun
+    }
+
+    pub fn receiver(&self) -> &PlaneMirror<D> {
+        &self.receiver
+    }
+
+    /// The receiver, as a mirror absorbing the rays hitting it, to be added to those
+    /// of the simulation tracing the rays of the sun, before calling [`Self::report`]
+    pub fn receiver_mirror(&self) -> Aperture<D> {
+        // SAFETY: there are no holes
+        Aperture::new(self.receiver, vec![]).unwrap()
+    }
+
+    /// Integrates the power of the rays of `paths`, (and of the rays split from them)
+    /// traced from the rays of the sun, that were absorbed by the receiver.
+    pub fn report(&self, paths: &[RayPath<D>]) -> ConcentratorReport {
+        let received_power = paths
+            .iter()
+            .flat_map(RayPath::all_branches)
+            .filter(|path| path.termination() == Termination::Absorbed)
+            .filter_map(|path| {
+                let (ray, length) = path.segments().last()?;
+
+                let distance = self
+                    .receiver
+                    .intersection(&ray)?
+                    .try_ray_intersection(&ray)?;
+
+                ((distance - length).abs() <= Float::EPSILON * 64.0 * (1.0 + length))
+                    .then_some(ray.power)
+            })
+            .sum();
+
+        ConcentratorReport {
+            incident_power: paths
+                .iter()
+                .filter_map(|path| path.bounces().first())
+                .map(|(_, ray)| ray.power)
+                .sum(),
+            received_power,
+            aperture_area: area(&self.sun.aperture),
+            receiver_area: area(&self.receiver),
+        }
+    }
+}
+
+impl<const D: usize> JsonDes for Concentrator<D> {
+    /// Deserialize a new solar concentrator from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "sun": // <the sun's layout, see `SunSource::from_json`>,
+    ///     "receiver": { // (see `PlaneMirror`)
+    ///         "center": [1., 2., 3., ...],
+    ///         "basis": [
+    ///             [1., 0., 0., ...],
+    ///             ...
+    ///         ],
+    ///     },
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        let sun = SunSource::from_json(json.get("sun").ok_or("Missing sun")?)?;
+        let receiver = PlaneMirror::from_json(json.get("receiver").ok_or("Missing receiver")?)?;
+
+        Ok(Self::new(sun, receiver))
+    }
+}
+
+impl<const D: usize> JsonSer for Concentrator<D> {
+    /// Serialize a solar concentrator into a JSON object.
+    ///
+    /// The format of the returned object is explained in [`Self::from_json`]
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "sun": self.sun.to_json(),
+            "receiver": self.receiver.to_json(),
+        })
+    }
+}
+
+/// The performance of a solar concentrator, see [`Concentrator::report`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConcentratorReport {
+    /// The total power of the rays of the sun, entering the aperture
+    pub incident_power: Float,
+    /// The power absorbed by the receiver
+    pub received_power: Float,
+    pub aperture_area: Float,
+    pub receiver_area: Float,
+}
+
+impl ConcentratorReport {
+    /// The fraction of the incident power landing on the receiver, (the intercept factor)
+    pub fn received_fraction(&self) -> Float {
+        self.received_power / self.incident_power
+    }
+
+    /// The ratio of the area of the aperture to that of the receiver
+    pub fn geometric_concentration(&self) -> Float {
+        self.aperture_area / self.receiver_area
+    }
+
+    /// The ratio of the mean flux on the receiver to that through the aperture
+    pub fn flux_concentration(&self) -> Float {
+        self.received_fraction() * self.geometric_concentration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use serde_json::json;
+
+    /// Sunlight falling vertically on the aperture `[-1 ; 1] x {5}`
+    fn sun(limb_darkening: Float) -> SunSource<2> {
+        SunSource::from_json(&json!({
+            "direction": [0., -1.],
+            "half_angle": 0.1,
+            "limb_darkening": limb_darkening,
+            "aperture": { "center": [0., 5.], "basis": [[1., 0.]] },
+            "num_rays": 2000,
+        }))
+        .expect("json error")
+    }
+
+    #[test]
+    fn test_sun_rays() {
+        let sun = sun(SUN_LIMB_DARKENING);
+        let rays = sun.rays(&mut rand::rngs::StdRng::seed_from_u64(0));
+
+        assert_eq!(rays.len(), 2000);
+        assert!((rays.iter().map(|ray| ray.power).sum::<Float>() - 1.0).abs() < 1e-9);
+
+        for ray in &rays {
+            assert_eq!(ray.origin.y, 5.0);
+            assert!(ray.origin.x.abs() <= 1.0);
+            assert!(ray.direction.y < 0.0 && ray.direction.x.abs() <= Float::sin(0.1) + 1e-12);
+        }
+
+        assert_eq!(SunSource::from_json(&sun.to_json()).ok(), Some(sun));
+
+        let mut json = sun.to_json();
+        json["direction"] = json!([1., 0.]);
+        assert!(SunSource::<2>::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_limb_darkening() {
+        let (uniform, darkened) = (sun(0.0), sun(1.0));
+
+        assert_eq!(uniform.radiance(0.5), 1.0);
+        assert_eq!(darkened.radiance(0.0), 1.0);
+        assert_eq!(darkened.radiance(1.0), 0.0);
+
+        // in 2D, the disk of the sun is a segment, the mean distance to it's center of the
+        // directions of rays, relative to it's radius, is 1/2 when it is uniform, and
+        // ∫ r * sqrt(1 - r²) / ∫ sqrt(1 - r²) = 4 / 3π when it's fully darkened
+        let mean_offset = |sun: &SunSource<2>| {
+            let rays = sun.rays(&mut rand::rngs::StdRng::seed_from_u64(0));
+            rays.iter()
+                .map(|ray| ray.direction.x.atan2(-ray.direction.y).abs() / 0.1)
+                .sum::<Float>()
+                / rays.len() as Float
+        };
+
+        assert!((mean_offset(&uniform) - 0.5).abs() < 0.02);
+        assert!((mean_offset(&darkened) - 4.0 / (3.0 * core::f64::consts::PI)).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_concentrator() {
+        // a V-shaped trough, reflecting the rays missing the receiver, at the bottom, onto it
+        let concentrator = Concentrator::new(
+            sun(SUN_LIMB_DARKENING),
+            PlaneMirror::try_new([[0., 0.].into(), [0.5, 0.].into()]).unwrap(),
+        );
+
+        let side = |x: Float| {
+            Box::new(PlaneMirror::<2>::try_new([[x, 2.5].into(), [x / 3.0, 2.5].into()]).unwrap())
+                as Box<dyn mirror::Mirror<2>>
+        };
+
+        let simulation = Simulation {
+            rays: concentrator
+                .sun()
+                .rays(&mut rand::rngs::StdRng::seed_from_u64(0)),
+            mirror: vec![
+                Box::new(concentrator.receiver_mirror()) as Box<dyn mirror::Mirror<2>>,
+                side(0.75),
+                side(-0.75),
+            ],
+            seed: 0,
+            propagation: Propagation::Straight,
+            periodic: None,
+            world: None,
+        };
+
+        let report = concentrator.report(&simulation.get_ray_paths(100));
+
+        assert!((report.incident_power - 1.0).abs() < 1e-9);
+        assert!((report.geometric_concentration() - 2.0).abs() < 1e-12);
+        assert!(report.received_fraction() > 0.9);
+
+        // without the trough, only the rays falling on the receiver directly are received
+        let simulation = Simulation {
+            mirror: vec![Box::new(concentrator.receiver_mirror()) as Box<dyn mirror::Mirror<2>>],
+            ..simulation
+        };
+
+        let report = concentrator.report(&simulation.get_ray_paths(100));
+        assert!((report.received_fraction() - 0.5).abs() < 0.05);
+        assert!(report.flux_concentration() < 1.1);
+    }
+}
@@ -1,12 +1,40 @@
 use mirror_verse::{
+    acoustics::Acoustics,
     mirror::{
-        self, cylinder::CylindricalMirror, plane::PlaneMirror, sphere::EuclideanSphereMirror,
-        JsonType,
-        JsonDes,
+        self,
+        aperture::Aperture,
+        cone::ConicalMirror,
+        csg::{Csg, HalfSpace, Solid},
+        cylinder::CylindricalMirror,
+        disk::DiskMirror,
+        grating::GratingMirror,
+        grin::GradientIndex,
+        heightfield::HeightfieldMirror,
+        hyperbolic::GeodesicMirror,
+        implicit::ImplicitMirror,
+        material::{Coated, Dielectric, Metal},
+        motion::Moving,
+        optics::{BeamSplitter, ThinLens},
+        plane::PlaneMirror,
+        portal::Portals,
+        scattering::Scattered,
+        simplex::SimplexMirror,
+        sphere::{EuclideanSphereMirror, LpSphereMirror},
+        spherical_cap::SphericalCapMirror,
+        torus::TorusMirror,
+        transform::Transformed,
+        JsonDes, JsonType,
     },
-    render, serde_json, util, Simulation,
+    nalgebra::SVector,
+    rand::{rngs::StdRng, SeedableRng},
+    render, serde_json,
+    solar::Concentrator,
+    util, Float, Propagation, RayPath, Simulation, Termination,
+};
+use std::{
+    collections::HashMap, error::Error, format as f, fs::File, io::BufWriter, path::Path,
+    sync::OnceLock,
 };
-use std::{collections::HashMap, error::Error, format as f, fs::File, sync::OnceLock};
 
 trait SimulationMirror<const D: usize>: mirror::Mirror<D> + render::OpenGLRenderable {}
 
@@ -53,6 +81,191 @@ fn deserialize_boxed<const D: usize>(
     }
 }
 
+trait SimulationSolid<const D: usize>: Solid<D> + render::OpenGLRenderable {}
+
+impl<const D: usize, T: Solid<D> + render::OpenGLRenderable + ?Sized> SimulationSolid<D> for T {}
+
+impl<const D: usize> JsonType for dyn SimulationSolid<D> {
+    fn json_type() -> String {
+        "dynamic".into()
+    }
+}
+
+fn boxed_solid<'a, const D: usize, T: SimulationSolid<D> + 'a>(
+    solid: T,
+) -> Box<dyn SimulationSolid<D> + 'a> {
+    Box::new(solid)
+}
+
+type SolidDeserializer<const D: usize> =
+    fn(&serde_json::Value) -> Result<Box<dyn SimulationSolid<D>>, Box<dyn Error>>;
+
+/// Same as [`deserialize_boxed`], for solids, lists (`"[]"` types) aren't solids
+fn deserialize_solid<const D: usize>(
+    json: &serde_json::Value,
+    deserializers: &HashMap<String, SolidDeserializer<D>>,
+) -> Result<Box<dyn SimulationSolid<D>>, Box<dyn Error>> {
+    let solid_type = json
+        .get("type")
+        .ok_or("Missing solid type")?
+        .as_str()
+        .ok_or("type must be a string")?;
+
+    let solid_json = json.get("mirror").ok_or("Missing solid data")?;
+
+    let deserializer = deserializers
+        .get(solid_type)
+        .ok_or(f!("invalid_solid_type: {solid_type}"))?;
+
+    deserializer(solid_json)
+}
+
+impl mirror::JsonDes for Box<dyn SimulationSolid<2>> {
+    /// Deserialize a new 2D solid object, to be used in constructive solid geometry,
+    /// from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "type": "string",
+    ///     "mirror": // <layout depends on the value at "type">
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        static DESERIALIZERS: OnceLock<HashMap<String, SolidDeserializer<2>>> = OnceLock::new();
+
+        #[rustfmt::skip]
+        let deserializers = DESERIALIZERS.get_or_init(|| HashMap::from([
+            (
+                EuclideanSphereMirror::<2>::json_type(),
+                (|value| EuclideanSphereMirror::<2>::from_json(value).map(boxed_solid)) as SolidDeserializer<2>,
+            ),
+            (
+                LpSphereMirror::<2>::json_type(),
+                |value| LpSphereMirror::<2>::from_json(value).map(boxed_solid),
+            ),
+            (
+                CylindricalMirror::<2>::json_type(),
+                |value| CylindricalMirror::<2>::from_json(value).map(boxed_solid),
+            ),
+            (
+                ImplicitMirror::<2>::json_type(),
+                |value| ImplicitMirror::<2>::from_json(value).map(boxed_solid),
+            ),
+            (
+                HalfSpace::<2>::json_type(),
+                |value| HalfSpace::<2>::from_json(value).map(boxed_solid),
+            ),
+            (
+                Transformed::<Box<dyn SimulationSolid<2>>, 2>::json_type(),
+                |value| Transformed::<Box<dyn SimulationSolid<2>>, 2>::from_json(value).map(boxed_solid),
+            ),
+            (
+                Csg::<Box<dyn SimulationSolid<2>>, 2>::json_type(),
+                |value| Csg::<Box<dyn SimulationSolid<2>>, 2>::from_json(value).map(boxed_solid),
+            ),
+            (
+                Coated::<Box<dyn SimulationSolid<2>>>::json_type(),
+                |value| Coated::<Box<dyn SimulationSolid<2>>>::from_json(value).map(boxed_solid),
+            ),
+            (
+                Metal::<Box<dyn SimulationSolid<2>>>::json_type(),
+                |value| Metal::<Box<dyn SimulationSolid<2>>>::from_json(value).map(boxed_solid),
+            ),
+            (
+                Scattered::<Box<dyn SimulationSolid<2>>>::json_type(),
+                |value| Scattered::<Box<dyn SimulationSolid<2>>>::from_json(value).map(boxed_solid),
+            ),
+            (
+                Dielectric::<Box<dyn SimulationSolid<2>>>::json_type(),
+                |value| Dielectric::<Box<dyn SimulationSolid<2>>>::from_json(value).map(boxed_solid),
+            ),
+            (
+                GradientIndex::<Box<dyn SimulationSolid<2>>, 2>::json_type(),
+                |value| GradientIndex::<Box<dyn SimulationSolid<2>>, 2>::from_json(value).map(boxed_solid),
+            ),
+        ]));
+
+        deserialize_solid(json, deserializers)
+    }
+}
+
+impl mirror::JsonDes for Box<dyn SimulationSolid<3>> {
+    /// Deserialize a new 3D solid object, to be used in constructive solid geometry,
+    /// from a JSON object.
+    ///
+    /// The JSON object must follow the following format:
+    ///
+    /// ```json
+    /// {
+    ///     "type": "string",
+    ///     "mirror": // <layout depends on the value at "type">
+    /// }
+    /// ```
+    fn from_json(json: &serde_json::Value) -> Result<Self, Box<dyn Error>> {
+        static DESERIALIZERS: OnceLock<HashMap<String, SolidDeserializer<3>>> = OnceLock::new();
+
+        #[rustfmt::skip]
+        let deserializers = DESERIALIZERS.get_or_init(|| HashMap::from([
+            (
+                EuclideanSphereMirror::<3>::json_type(),
+                (|json| EuclideanSphereMirror::<3>::from_json(json).map(boxed_solid)) as SolidDeserializer<3>,
+            ),
+            (
+                LpSphereMirror::<3>::json_type(),
+                |json| LpSphereMirror::<3>::from_json(json).map(boxed_solid),
+            ),
+            (
+                CylindricalMirror::<3>::json_type(),
+                |json| CylindricalMirror::<3>::from_json(json).map(boxed_solid),
+            ),
+            (
+                TorusMirror::json_type(),
+                |json| TorusMirror::from_json(json).map(boxed_solid),
+            ),
+            (
+                ImplicitMirror::<3>::json_type(),
+                |json| ImplicitMirror::<3>::from_json(json).map(boxed_solid),
+            ),
+            (
+                HalfSpace::<3>::json_type(),
+                |json| HalfSpace::<3>::from_json(json).map(boxed_solid),
+            ),
+            (
+                Transformed::<Box<dyn SimulationSolid<3>>, 3>::json_type(),
+                |json| Transformed::<Box<dyn SimulationSolid<3>>, 3>::from_json(json).map(boxed_solid),
+            ),
+            (
+                Csg::<Box<dyn SimulationSolid<3>>, 3>::json_type(),
+                |json| Csg::<Box<dyn SimulationSolid<3>>, 3>::from_json(json).map(boxed_solid),
+            ),
+            (
+                Coated::<Box<dyn SimulationSolid<3>>>::json_type(),
+                |json| Coated::<Box<dyn SimulationSolid<3>>>::from_json(json).map(boxed_solid),
+            ),
+            (
+                Metal::<Box<dyn SimulationSolid<3>>>::json_type(),
+                |json| Metal::<Box<dyn SimulationSolid<3>>>::from_json(json).map(boxed_solid),
+            ),
+            (
+                Scattered::<Box<dyn SimulationSolid<3>>>::json_type(),
+                |json| Scattered::<Box<dyn SimulationSolid<3>>>::from_json(json).map(boxed_solid),
+            ),
+            (
+                Dielectric::<Box<dyn SimulationSolid<3>>>::json_type(),
+                |json| Dielectric::<Box<dyn SimulationSolid<3>>>::from_json(json).map(boxed_solid),
+            ),
+            (
+                GradientIndex::<Box<dyn SimulationSolid<3>>, 3>::json_type(),
+                |json| GradientIndex::<Box<dyn SimulationSolid<3>>, 3>::from_json(json).map(boxed_solid),
+            ),
+        ]));
+
+        deserialize_solid(json, deserializers)
+    }
+}
+
 impl mirror::JsonDes for Box<dyn SimulationMirror<2>> {
     /// Deserialize a new 2D simulation mirror object from a JSON object.
     ///
@@ -82,6 +295,94 @@ impl mirror::JsonDes for Box<dyn SimulationMirror<2>> {
                 EuclideanSphereMirror::<2>::json_type(),
                 |value| EuclideanSphereMirror::<2>::from_json(value).map(boxed),
             ),
+            (
+                ImplicitMirror::<2>::json_type(),
+                |value| ImplicitMirror::<2>::from_json(value).map(boxed),
+            ),
+            (
+                ConicalMirror::<2>::json_type(),
+                |value| ConicalMirror::<2>::from_json(value).map(boxed),
+            ),
+            (
+                CylindricalMirror::<2>::json_type(),
+                |value| CylindricalMirror::<2>::from_json(value).map(boxed),
+            ),
+            (
+                DiskMirror::<2>::json_type(),
+                |value| DiskMirror::<2>::from_json(value).map(boxed),
+            ),
+            (
+                SimplexMirror::<2>::json_type(),
+                |value| SimplexMirror::<2>::from_json(value).map(boxed),
+            ),
+            (
+                SphericalCapMirror::<2>::json_type(),
+                |value| SphericalCapMirror::<2>::from_json(value).map(boxed),
+            ),
+            (
+                LpSphereMirror::<2>::json_type(),
+                |value| LpSphereMirror::<2>::from_json(value).map(boxed),
+            ),
+            (
+                Transformed::<Box<dyn SimulationMirror<2>>, 2>::json_type(),
+                |value| Transformed::<Box<dyn SimulationMirror<2>>, 2>::from_json(value).map(boxed),
+            ),
+            (
+                Moving::<Box<dyn SimulationMirror<2>>, 2>::json_type(),
+                |value| Moving::<Box<dyn SimulationMirror<2>>, 2>::from_json(value).map(boxed),
+            ),
+            (
+                Portals::<2>::json_type(),
+                |value| Portals::<2>::from_json(value).map(boxed),
+            ),
+            (
+                Aperture::<2>::json_type(),
+                |value| Aperture::<2>::from_json(value).map(boxed),
+            ),
+            (
+                Csg::<Box<dyn SimulationSolid<2>>, 2>::json_type(),
+                |value| Csg::<Box<dyn SimulationSolid<2>>, 2>::from_json(value).map(boxed),
+            ),
+            (
+                HalfSpace::<2>::json_type(),
+                |value| HalfSpace::<2>::from_json(value).map(boxed),
+            ),
+            (
+                GratingMirror::<2>::json_type(),
+                |value| GratingMirror::<2>::from_json(value).map(boxed),
+            ),
+            (
+                ThinLens::<2>::json_type(),
+                |value| ThinLens::<2>::from_json(value).map(boxed),
+            ),
+            (
+                BeamSplitter::<2>::json_type(),
+                |value| BeamSplitter::<2>::from_json(value).map(boxed),
+            ),
+            (
+                GeodesicMirror::json_type(),
+                |value| GeodesicMirror::from_json(value).map(boxed),
+            ),
+            (
+                Coated::<Box<dyn SimulationMirror<2>>>::json_type(),
+                |value| Coated::<Box<dyn SimulationMirror<2>>>::from_json(value).map(boxed),
+            ),
+            (
+                Metal::<Box<dyn SimulationMirror<2>>>::json_type(),
+                |value| Metal::<Box<dyn SimulationMirror<2>>>::from_json(value).map(boxed),
+            ),
+            (
+                Scattered::<Box<dyn SimulationMirror<2>>>::json_type(),
+                |value| Scattered::<Box<dyn SimulationMirror<2>>>::from_json(value).map(boxed),
+            ),
+            (
+                Dielectric::<Box<dyn SimulationSolid<2>>>::json_type(),
+                |value| Dielectric::<Box<dyn SimulationSolid<2>>>::from_json(value).map(boxed),
+            ),
+            (
+                GradientIndex::<Box<dyn SimulationSolid<2>>, 2>::json_type(),
+                |value| GradientIndex::<Box<dyn SimulationSolid<2>>, 2>::from_json(value).map(boxed),
+            ),
         ]));
 
         deserialize_boxed(json, deserializers)
@@ -119,27 +420,316 @@ impl mirror::JsonDes for Box<dyn SimulationMirror<3>> {
                 |json| EuclideanSphereMirror::<3>::from_json(json).map(boxed),
             ),
             (
-                CylindricalMirror::json_type(),
-                |json| CylindricalMirror::from_json(json).map(boxed)
-            )
+                CylindricalMirror::<3>::json_type(),
+                |json| CylindricalMirror::<3>::from_json(json).map(boxed)
+            ),
+            (
+                ImplicitMirror::<3>::json_type(),
+                |json| ImplicitMirror::<3>::from_json(json).map(boxed),
+            ),
+            (
+                TorusMirror::json_type(),
+                |json| TorusMirror::from_json(json).map(boxed),
+            ),
+            (
+                ConicalMirror::<3>::json_type(),
+                |json| ConicalMirror::<3>::from_json(json).map(boxed),
+            ),
+            (
+                DiskMirror::<3>::json_type(),
+                |json| DiskMirror::<3>::from_json(json).map(boxed),
+            ),
+            (
+                SimplexMirror::<3>::json_type(),
+                |json| SimplexMirror::<3>::from_json(json).map(boxed),
+            ),
+            (
+                SphericalCapMirror::<3>::json_type(),
+                |json| SphericalCapMirror::<3>::from_json(json).map(boxed),
+            ),
+            (
+                LpSphereMirror::<3>::json_type(),
+                |json| LpSphereMirror::<3>::from_json(json).map(boxed),
+            ),
+            (
+                Transformed::<Box<dyn SimulationMirror<3>>, 3>::json_type(),
+                |json| Transformed::<Box<dyn SimulationMirror<3>>, 3>::from_json(json).map(boxed),
+            ),
+            (
+                Moving::<Box<dyn SimulationMirror<3>>, 3>::json_type(),
+                |json| Moving::<Box<dyn SimulationMirror<3>>, 3>::from_json(json).map(boxed),
+            ),
+            (
+                Portals::<3>::json_type(),
+                |json| Portals::<3>::from_json(json).map(boxed),
+            ),
+            (
+                Aperture::<3>::json_type(),
+                |json| Aperture::<3>::from_json(json).map(boxed),
+            ),
+            (
+                HeightfieldMirror::json_type(),
+                |json| HeightfieldMirror::from_json(json).map(boxed),
+            ),
+            (
+                Csg::<Box<dyn SimulationSolid<3>>, 3>::json_type(),
+                |json| Csg::<Box<dyn SimulationSolid<3>>, 3>::from_json(json).map(boxed),
+            ),
+            (
+                HalfSpace::<3>::json_type(),
+                |json| HalfSpace::<3>::from_json(json).map(boxed),
+            ),
+            (
+                GratingMirror::<3>::json_type(),
+                |json| GratingMirror::<3>::from_json(json).map(boxed),
+            ),
+            (
+                ThinLens::<3>::json_type(),
+                |json| ThinLens::<3>::from_json(json).map(boxed),
+            ),
+            (
+                BeamSplitter::<3>::json_type(),
+                |json| BeamSplitter::<3>::from_json(json).map(boxed),
+            ),
+            (
+                Coated::<Box<dyn SimulationMirror<3>>>::json_type(),
+                |json| Coated::<Box<dyn SimulationMirror<3>>>::from_json(json).map(boxed),
+            ),
+            (
+                Metal::<Box<dyn SimulationMirror<3>>>::json_type(),
+                |json| Metal::<Box<dyn SimulationMirror<3>>>::from_json(json).map(boxed),
+            ),
+            (
+                Scattered::<Box<dyn SimulationMirror<3>>>::json_type(),
+                |json| Scattered::<Box<dyn SimulationMirror<3>>>::from_json(json).map(boxed),
+            ),
+            (
+                Dielectric::<Box<dyn SimulationSolid<3>>>::json_type(),
+                |json| Dielectric::<Box<dyn SimulationSolid<3>>>::from_json(json).map(boxed),
+            ),
+            (
+                GradientIndex::<Box<dyn SimulationSolid<3>>, 3>::json_type(),
+                |json| GradientIndex::<Box<dyn SimulationSolid<3>>, 3>::from_json(json).map(boxed),
+            ),
         ]));
 
         deserialize_boxed(json, deserializers)
     }
 }
 
-fn run_simulation(reflection_cap: usize, json: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+/// Prints the power and Stokes parameters (in the frame of `polarization::transverse_frame`)
+/// of polarized rays, and the rays split from them, at the end of their paths
+fn print_polarization_report<const D: usize>(
+    simulation: &Simulation<Box<dyn SimulationMirror<D>>, D>,
+    reflection_cap: usize,
+) {
+    // don't trace the simulation twice for nothing
+    if simulation.rays.iter().all(|ray| ray.polarization.is_none()) {
+        return;
+    }
+
+    let paths = simulation.get_ray_paths(reflection_cap);
+
+    for (i, (ray, path)) in simulation.rays.iter().zip(&paths).enumerate() {
+        if ray.polarization.is_none() {
+            continue;
+        }
+
+        // rays split by beam splitters are reported as `ray <i>.<branch index>`
+        for (j, branch) in path.all_branches().enumerate() {
+            let Some(final_ray) = branch.final_ray() else {
+                continue;
+            };
+
+            let stokes = final_ray
+                .polarization
+                .map(|p| p.stokes(&final_ray.direction).map(|s| s * final_ray.power))
+                .unwrap_or_default();
+
+            let name = if j == 0 { f!("{i}") } else { f!("{i}.{j}") };
+
+            println!(
+                "ray {name}: power = {:.4}, stokes = [{:.4}, {:.4}, {:.4}, {:.4}]",
+                final_ray.power, stokes[0], stokes[1], stokes[2], stokes[3],
+            );
+        }
+    }
+}
+
+/// Returns an error if some of `points`, where the rays of `simulation` start, are outside of it's
+/// periodic, or world, box. [`Simulation::from_json`] only checks the rays listed in the JSON file.
+fn check_ray_starts<const D: usize>(
+    simulation: &Simulation<Box<dyn SimulationMirror<D>>, D>,
+    points: &[SVector<Float, D>],
+    emitter: &str,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(periodic) = &simulation.periodic {
+        if !points.iter().all(|p| periodic.contains(p)) {
+            return Err(f!("the {emitter} must be inside the periodic box").into());
+        }
+    }
+
+    if let Some(world) = &simulation.world {
+        if !points.iter().all(|p| world.contains(p)) {
+            return Err(f!("the {emitter} must be inside the world box").into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Traces the rays emitted by the source of `acoustics`, in the room of `simulation`, and
+/// writes the impulse response at it's receiver to `output`, with the extensions `csv` and `wav`
+fn run_acoustics<const D: usize>(
+    mut simulation: Simulation<Box<dyn SimulationMirror<D>>, D>,
+    acoustics: &Acoustics<D>,
+    reflection_cap: usize,
+    output: &Path,
+) -> Result<(), Box<dyn Error>> {
+    if simulation.propagation != Propagation::Straight {
+        return Err("sound travels in straight lines: acoustic simulations \
+            only support straight propagation"
+            .into());
+    }
+
+    check_ray_starts(&simulation, &[*acoustics.source()], "sound source")?;
+
+    simulation.rays = acoustics.source_rays(&mut StdRng::seed_from_u64(simulation.seed));
+
+    let paths = simulation.get_ray_paths(reflection_cap);
+    let truncated = paths
+        .iter()
+        .flat_map(RayPath::all_branches)
+        .filter(|path| path.termination() == Termination::Limit)
+        .count();
+
+    let response = acoustics.impulse_response(&paths);
+
+    let csv_path = output.with_extension("csv");
+    let wav_path = output.with_extension("wav");
+    response.write_csv(BufWriter::new(File::create(&csv_path)?))?;
+    response.write_wav(BufWriter::new(File::create(&wav_path)?))?;
+
+    println!(
+        "impulse response: {} bins at {} Hz, received energy = {:.6}, written to {} and {}",
+        response.energies().len(),
+        response.sample_rate(),
+        response.energies().iter().sum::<Float>(),
+        csv_path.display(),
+        wav_path.display(),
+    );
+
+    if truncated > 0 {
+        println!("{truncated} rays reached the reflection limit, the response may be truncated");
+    }
+
+    Ok(())
+}
+
+/// Replaces the rays of `simulation` with those of the sun of `concentrator`, and adds it's receiver
+/// to it's mirrors, then prints the fraction of the power of the sun landing on the receiver
+fn with_concentrator<const D: usize>(
+    simulation: Simulation<Box<dyn SimulationMirror<D>>, D>,
+    concentrator: &Concentrator<D>,
+    reflection_cap: usize,
+) -> Result<Simulation<Box<dyn SimulationMirror<D>>, D>, Box<dyn Error>>
+where
+    Aperture<D>: render::OpenGLRenderable,
+    Vec<Box<dyn SimulationMirror<D>>>: render::OpenGLRenderable,
+{
+    let aperture = Vec::from_iter(concentrator.sun().aperture().vertices());
+    check_ray_starts(&simulation, &aperture, "sun's aperture")?;
+
+    let simulation = Simulation {
+        rays: concentrator
+            .sun()
+            .rays(&mut StdRng::seed_from_u64(simulation.seed)),
+        mirror: boxed(vec![
+            simulation.mirror,
+            boxed(concentrator.receiver_mirror()),
+        ]),
+        seed: simulation.seed,
+        propagation: simulation.propagation,
+        periodic: simulation.periodic,
+        world: simulation.world,
+    };
+
+    let report = concentrator.report(&simulation.get_ray_paths(reflection_cap));
+
+    println!(
+        "solar concentrator: received fraction = {:.4}, geometric concentration = {:.2}, \
+        flux concentration = {:.2}",
+        report.received_fraction(),
+        report.geometric_concentration(),
+        report.flux_concentration(),
+    );
+
+    Ok(simulation)
+}
+
+fn run_simulation(
+    reflection_cap: usize,
+    json: &serde_json::Value,
+    file_path: &Path,
+) -> Result<(), Box<dyn Error>> {
     let dim = json
         .get("dim")
         .ok_or(r#"invalid json: expected a "dim" field"#)?
         .as_u64()
         .ok_or(r#""dim" field must be a number"#)?;
 
+    if let Some(acoustics) = json.get("acoustics") {
+        // the rays are emitted by the source
+        let mut json = json.clone();
+        json["rays"] = serde_json::json!([]);
+
+        return match dim {
+            2 => run_acoustics(
+                Simulation::<Box<dyn SimulationMirror<2>>, 2>::from_json(&json)?,
+                &Acoustics::from_json(acoustics)?,
+                reflection_cap,
+                file_path,
+            ),
+            3 => run_acoustics(
+                Simulation::<Box<dyn SimulationMirror<3>>, 3>::from_json(&json)?,
+                &Acoustics::from_json(acoustics)?,
+                reflection_cap,
+                file_path,
+            ),
+            _ => Err("dimension must be 2 or 3".into()),
+        };
+    }
+
+    if let Some(solar) = json.get("solar") {
+        // the rays are emitted by the sun
+        let mut json = json.clone();
+        json["rays"] = serde_json::json!([]);
+
+        return match dim {
+            2 => {
+                let concentrator = Concentrator::from_json(solar)?;
+                let sim = Simulation::<Box<dyn SimulationMirror<2>>, 2>::from_json(&json)?;
+                with_concentrator(sim, &concentrator, reflection_cap)
+                    .map(|sim| sim.run_opengl_3d(reflection_cap))
+            }
+            3 => {
+                let concentrator = Concentrator::from_json(solar)?;
+                let sim = Simulation::<Box<dyn SimulationMirror<3>>, 3>::from_json(&json)?;
+                with_concentrator(sim, &concentrator, reflection_cap)
+                    .map(|sim| sim.run_opengl_3d(reflection_cap))
+            }
+            _ => Err("dimension must be 2 or 3".into()),
+        };
+    }
+
     match dim {
+        // polarization is only tracked in 3D, (2D rays can't have one) so there's nothing to report
         2 => Simulation::<Box<dyn SimulationMirror<2>>, 2>::from_json(json)
             .map(|sim| sim.run_opengl_3d(reflection_cap)),
-        3 => Simulation::<Box<dyn SimulationMirror<3>>, 3>::from_json(json)
-            .map(|sim| sim.run_opengl_3d(reflection_cap)),
+        3 => Simulation::<Box<dyn SimulationMirror<3>>, 3>::from_json(json).map(|sim| {
+            print_polarization_report(&sim, reflection_cap);
+            sim.run_opengl_3d(reflection_cap)
+        }),
         _ => Err("dimension must be 2 or 3".into()),
     }
 }
@@ -158,7 +748,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     run_simulation(
         max_num_reflections,
-        &serde_json::from_reader(File::open(file_path)?)?,
+        &serde_json::from_reader(File::open(&file_path)?)?,
+        Path::new(&file_path),
     )
 }
 
@@ -206,6 +797,27 @@ mod tests {
         assert!(path.first().unwrap().all_points_raw().len() == 4);
     }
 
+    #[test]
+    fn test_source_outside_world() {
+        let json = serde_json::json!({
+            "dim": 2,
+            "mirror": {
+                "type": "[]plane",
+                "mirror": [{ "center": [1., 0.], "basis": [[0., 1.]] }],
+            },
+            "world": { "min": [-5., -5.], "max": [5., 5.] },
+            "acoustics": {
+                "source": [6., 0.],
+                "num_rays": 10,
+                "receiver": { "center": [0., 0.], "radius": 0.5 },
+            },
+        });
+
+        // rejected before anything is traced, or written
+        let error = run_simulation(10, &json, Path::new("unused")).unwrap_err();
+        assert!(error.to_string().contains("world box"));
+    }
+
     #[test]
     fn test_no_loop_detection() {
         let simulation = Simulation::<Box<dyn SimulationMirror<2>>, 2>::from_json(